@@ -0,0 +1,83 @@
+//! Shielder state read from a JSON snapshot, used when no node backend is available
+use std::{collections::BTreeMap, fs, path::Path};
+
+use anyhow::{anyhow, bail, Result};
+use mocked_zk::{Scalar, MERKLE_TREE_DEPTH, TOKENS_NUMBER};
+use shielder_wallet::{
+    calls::{AddNoteCall, UpdateNoteCall},
+    transport::Transport,
+};
+
+/// Public state of the shielder contract, as returned by its queries
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct ChainState {
+    pub domain_separator: Scalar,
+    pub supported_tokens: [Scalar; TOKENS_NUMBER],
+    pub merkle_root: Scalar,
+    /// merkle paths of leaves, only the ones of the wallet notes are needed
+    pub merkle_paths: BTreeMap<u32, [Scalar; MERKLE_TREE_DEPTH]>,
+}
+
+/// Error of submitting through `SnapshotTransport`
+pub const SNAPSHOT_CANNOT_SUBMIT: &str = "`--submit` needs a node, the JSON snapshot of the \
+     shielder state can't submit calls: run the command without `--submit`, submit the printed \
+     payload and run `confirm --leaf-id`";
+
+/// Answers queries from `ChainState`, calls can be only printed
+#[derive(Debug, Default)]
+pub struct SnapshotTransport {
+    chain: Option<ChainState>,
+}
+
+impl SnapshotTransport {
+    pub fn new(chain: ChainState) -> Self {
+        Self { chain: Some(chain) }
+    }
+
+    /// Without `path`, only commands not querying the shielder can be run
+    pub fn load(path: Option<&Path>) -> Result<Self> {
+        let Some(path) = path else {
+            return Ok(Self::default());
+        };
+        let json = fs::read_to_string(path)?;
+        Ok(Self::new(serde_json::from_str(&json)?))
+    }
+
+    fn chain(&self) -> Result<&ChainState> {
+        self.chain
+            .as_ref()
+            .ok_or(anyhow!("shielder state not provided, use `--chain-state`"))
+    }
+}
+
+impl Transport for SnapshotTransport {
+    type Error = anyhow::Error;
+
+    fn domain_separator(&mut self) -> Result<Scalar> {
+        Ok(self.chain()?.domain_separator)
+    }
+
+    fn supported_tokens(&mut self) -> Result<[Scalar; TOKENS_NUMBER]> {
+        Ok(self.chain()?.supported_tokens)
+    }
+
+    fn notes_merkle_root(&mut self) -> Result<Scalar> {
+        Ok(self.chain()?.merkle_root)
+    }
+
+    fn notes_merkle_path(&mut self, leaf_id: u32) -> Result<[Scalar; MERKLE_TREE_DEPTH]> {
+        self.chain()?
+            .merkle_paths
+            .get(&leaf_id)
+            .copied()
+            .ok_or(anyhow!("merkle path of leaf {leaf_id} not provided"))
+    }
+
+    fn add_note(&mut self, _call: &AddNoteCall) -> Result<u32> {
+        bail!(SNAPSHOT_CANNOT_SUBMIT)
+    }
+
+    fn update_note(&mut self, _call: &UpdateNoteCall) -> Result<u32> {
+        bail!(SNAPSHOT_CANNOT_SUBMIT)
+    }
+}
@@ -0,0 +1,223 @@
+//! Command-line wallet of a single shielded account
+//!
+//! Wallet state is stored in a local JSON file. Commands building calls print them as JSON
+//! together with the SCALE-encoded payload (selector and arguments) of the contract message.
+//! With `--submit` the call is sent through the transport and confirmed right away, otherwise
+//! the payload has to be submitted by other means and confirmed with `confirm --leaf-id`.
+//! The binary reads the shielder state from a JSON snapshot, which can't submit calls, so it
+//! hides `--submit`.
+pub mod chain;
+#[cfg(test)]
+mod tests;
+
+use std::{fmt::Debug, io::Write, path::PathBuf};
+
+use anyhow::{anyhow, bail, Result};
+use clap::{Args, Parser, Subcommand};
+use mocked_zk::{
+    derivation::Seed,
+    ops::{OpPriv, OpPub},
+    wire, Scalar,
+};
+use serde_json::{json, Value};
+use shielder_wallet::{calls::to_hex, transport::Transport, Wallet, WalletError};
+
+#[derive(Debug, Parser)]
+#[command(name = "shielder-cli", about = "Wallet of a single shielded account")]
+pub struct Cli {
+    /// file holding the wallet state
+    #[arg(long, default_value = "shielder-wallet.json")]
+    pub state: PathBuf,
+    /// JSON snapshot of the shielder state, see `chain::ChainState`
+    #[arg(long)]
+    pub chain_state: Option<PathBuf>,
+    #[command(subcommand)]
+    pub command: Command,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Creates the wallet state file
+    Init {
+        /// hex-encoded 32 bytes, random if not given
+        #[arg(long, value_parser = parse_seed)]
+        seed: Option<Seed>,
+    },
+    /// Builds `add_note` call creating the account
+    CreateAccount {
+        /// submits the call and confirms it, needs a transport able to submit calls
+        #[arg(long)]
+        submit: bool,
+    },
+    /// Builds `update_note` call depositing tokens
+    Deposit(OperationArgs),
+    /// Builds `update_note` call withdrawing tokens
+    Withdraw(OperationArgs),
+    /// Stores the pending note, once its call was included at `leaf_id`
+    Confirm {
+        #[arg(long)]
+        leaf_id: u32,
+    },
+    /// Shows balances of the account
+    Balance {
+        /// hex-encoded token address, all tokens if not given
+        #[arg(long, value_parser = parse_scalar)]
+        token: Option<Scalar>,
+    },
+    /// Lists notes of the account
+    Notes,
+}
+
+#[derive(Debug, Args)]
+pub struct OperationArgs {
+    /// hex-encoded token address
+    #[arg(long, value_parser = parse_scalar)]
+    pub token: Scalar,
+    #[arg(long)]
+    pub amount: u128,
+    /// hex-encoded address of depositor or recipient
+    #[arg(long, value_parser = parse_scalar)]
+    pub user: Scalar,
+    /// last block in which the call is valid
+    #[arg(long)]
+    pub valid_until: Option<u32>,
+    /// hex-encoded address of the only account allowed to submit the call
+    #[arg(long, value_parser = parse_scalar)]
+    pub submitter: Option<Scalar>,
+    /// submits the call and confirms it, needs a transport able to submit calls
+    #[arg(long)]
+    pub submit: bool,
+}
+
+fn parse_bytes(s: &str) -> Result<[u8; 32], String> {
+    let bytes = hex::decode(s.trim_start_matches("0x")).map_err(|err| err.to_string())?;
+    bytes
+        .try_into()
+        .map_err(|bytes: Vec<u8>| format!("expected 32 bytes, got {}", bytes.len()))
+}
+
+fn parse_scalar(s: &str) -> Result<Scalar, String> {
+    parse_bytes(s).map(Scalar::from_bytes)
+}
+
+fn parse_seed(s: &str) -> Result<Seed, String> {
+    parse_bytes(s).map(Seed::from_bytes)
+}
+
+fn wallet_error<E: Debug>(err: WalletError<E>) -> anyhow::Error {
+    anyhow!("{err:?}")
+}
+
+/// Prints built call in the JSON wire format with its `payload`, `leaf_id` is set if the call
+/// was submitted
+fn print_call(
+    out: &mut impl Write,
+    message: &str,
+    payload: &[u8],
+    call: &impl serde::Serialize,
+    leaf_id: Option<u32>,
+) -> Result<()> {
+    print_json(
+        out,
+        &json!({
+            "message": message,
+            "payload": to_hex(payload),
+            "call": wire::to_json(call),
+            "leaf_id": leaf_id,
+        }),
+    )
+}
+
+fn print_json(out: &mut impl Write, value: &Value) -> Result<()> {
+    writeln!(out, "{}", serde_json::to_string_pretty(value)?)?;
+    Ok(())
+}
+
+/// Runs `cli` command, querying and submitting calls through `transport`
+pub fn run<T>(cli: &Cli, transport: &mut T, out: &mut impl Write) -> Result<()>
+where
+    T: Transport,
+    T::Error: Debug,
+{
+    if let Command::Init { seed } = cli.command {
+        if cli.state.exists() {
+            bail!("wallet state {} already exists", cli.state.display());
+        }
+        let wallet = Wallet::new(seed.unwrap_or_else(|| Seed::from_bytes(rand::random())));
+        wallet.save(&cli.state)?;
+        return print_json(out, &json!({ "id": wallet.state().seed.id() }));
+    }
+
+    let mut wallet = Wallet::load(&cli.state)?;
+    match &cli.command {
+        Command::Init { .. } => unreachable!(),
+        Command::CreateAccount { submit } => {
+            let call = wallet
+                .prepare_create_account(transport)
+                .map_err(wallet_error)?;
+            let mut leaf_id = None;
+            if *submit {
+                let id = transport
+                    .add_note(&call)
+                    .map_err(|err| anyhow!("{err:?}"))?;
+                wallet.confirm::<T::Error>(id).map_err(wallet_error)?;
+                leaf_id = Some(id);
+            }
+            wallet.save(&cli.state)?;
+            print_call(out, "add_note", &call.payload(), &call, leaf_id)
+        }
+        Command::Deposit(args) | Command::Withdraw(args) => {
+            let op_pub = match cli.command {
+                Command::Deposit(_) => OpPub::Deposit {
+                    amount: args.amount,
+                    token: args.token,
+                    user: args.user,
+                    valid_until: args.valid_until,
+                },
+                _ => OpPub::Withdraw {
+                    amount: args.amount,
+                    token: args.token,
+                    user: args.user,
+                    valid_until: args.valid_until,
+                },
+            };
+            let op_priv = OpPriv { user: args.user };
+            let call = wallet
+                .prepare_update(transport, op_pub, op_priv, args.submitter)
+                .map_err(wallet_error)?;
+            let mut leaf_id = None;
+            if args.submit {
+                let id = transport
+                    .update_note(&call)
+                    .map_err(|err| anyhow!("{err:?}"))?;
+                wallet.confirm::<T::Error>(id).map_err(wallet_error)?;
+                leaf_id = Some(id);
+            }
+            wallet.save(&cli.state)?;
+            print_call(out, "update_note", &call.payload(), &call, leaf_id)
+        }
+        Command::Confirm { leaf_id } => {
+            wallet.confirm::<T::Error>(*leaf_id).map_err(wallet_error)?;
+            wallet.save(&cli.state)?;
+            print_json(out, &json!({ "leaf_id": leaf_id }))
+        }
+        Command::Balance { token } => {
+            let note = wallet.note().ok_or(anyhow!("account not created"))?;
+            let balances: serde_json::Map<String, Value> = note
+                .account
+                .balances()
+                .into_iter()
+                .filter(|(t, _)| token.map_or(*t != Scalar::from(0_u128), |token| *t == token))
+                .map(|(t, balance)| (to_hex(&t.bytes), json!(u128::from(balance))))
+                .collect();
+            print_json(out, &Value::Object(balances))
+        }
+        Command::Notes => print_json(
+            out,
+            &json!({
+                "notes": wallet.state().notes,
+                "pending": wallet.state().pending,
+            }),
+        ),
+    }
+}
@@ -0,0 +1,22 @@
+use std::io;
+
+use anyhow::Result;
+use clap::{Command, CommandFactory, FromArgMatches};
+use shielder_cli::{chain::SnapshotTransport, run, Cli};
+
+/// Commands of `Cli`, with `--submit` hidden as `SnapshotTransport` can't submit calls
+fn command() -> Command {
+    ["create-account", "deposit", "withdraw"]
+        .into_iter()
+        .fold(Cli::command(), |command, name| {
+            command.mut_subcommand(name, |subcommand| {
+                subcommand.mut_arg("submit", |arg| arg.hide(true))
+            })
+        })
+}
+
+fn main() -> Result<()> {
+    let cli = Cli::from_arg_matches(&command().get_matches())?;
+    let mut transport = SnapshotTransport::load(cli.chain_state.as_deref())?;
+    run(&cli, &mut transport, &mut io::stdout())
+}
@@ -0,0 +1,101 @@
+use std::{collections::BTreeMap, path::Path};
+
+use clap::Parser;
+use mocked_zk::{
+    account::Account, derivation::Seed, domain_separator, traits::Hashable, wire, Scalar,
+    MERKLE_TREE_DEPTH, MOCKED_TOKEN, TOKENS_NUMBER,
+};
+use serde_json::Value;
+use shielder_wallet::{
+    calls::{AddNoteCall, UpdateNoteCall},
+    state::WalletState,
+};
+
+use crate::{
+    chain::{ChainState, SnapshotTransport, SNAPSHOT_CANNOT_SUBMIT},
+    run, Cli,
+};
+
+fn chain_state() -> ChainState {
+    let mut supported_tokens = [0_u128.into(); TOKENS_NUMBER];
+    supported_tokens[0] = MOCKED_TOKEN;
+    ChainState {
+        domain_separator: domain_separator(Scalar::from_bytes([0x1; 32]), 0_u128.into()),
+        supported_tokens,
+        merkle_root: 0_u128.into(),
+        merkle_paths: BTreeMap::from([(0, [0_u128.into(); MERKLE_TREE_DEPTH])]),
+    }
+}
+
+fn run_cli(
+    state: &Path,
+    transport: &mut SnapshotTransport,
+    args: &[&str],
+) -> anyhow::Result<Value> {
+    let state = state.to_str().unwrap();
+    let cli = Cli::try_parse_from(["shielder-cli", "--state", state].iter().chain(args))?;
+    let mut out = vec![];
+    run(&cli, transport, &mut out)?;
+    Ok(serde_json::from_slice(&out)?)
+}
+
+#[test]
+fn test_prepare_and_confirm() -> anyhow::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let state = dir.path().join("wallet.json");
+    let mut transport = SnapshotTransport::new(chain_state());
+    let seed = hex::encode([0x1; 32]);
+    let token = hex::encode(MOCKED_TOKEN.bytes);
+    let user = hex::encode([0x7; 32]);
+
+    run_cli(&state, &mut transport, &["init", "--seed", &seed])?;
+    assert!(run_cli(&state, &mut transport, &["init"]).is_err());
+
+    let out = run_cli(&state, &mut transport, &["create-account"])?;
+    assert_eq!(out["message"], "add_note");
+    assert_eq!(out["leaf_id"], Value::Null);
+    let note =
+        Seed::from_bytes([0x1; 32]).note(0, Account::new(chain_state().supported_tokens).hash());
+    assert_eq!(
+        wire::from_json::<AddNoteCall>(&out["call"])
+            .unwrap()
+            .h_note_new,
+        note.hash()
+    );
+    let err = run_cli(&state, &mut transport, &["create-account", "--submit"]).unwrap_err();
+    assert!(err.to_string().starts_with(SNAPSHOT_CANNOT_SUBMIT));
+
+    run_cli(&state, &mut transport, &["confirm", "--leaf-id", "0"])?;
+    let args = [
+        "--token",
+        token.as_str(),
+        "--amount",
+        "10",
+        "--user",
+        user.as_str(),
+    ];
+    let out = run_cli(
+        &state,
+        &mut transport,
+        &[&["deposit"][..], &args[..]].concat(),
+    )?;
+    let payload = hex::decode(out["payload"].as_str().unwrap().trim_start_matches("0x"))?;
+    let call = UpdateNoteCall::from_payload(&payload).unwrap();
+    assert_eq!(wire::to_json(&call), out["call"]);
+    run_cli(&state, &mut transport, &["confirm", "--leaf-id", "1"])?;
+
+    let out = run_cli(&state, &mut transport, &["balance"])?;
+    assert_eq!(out[format!("0x{token}")], 10);
+    let out = run_cli(&state, &mut transport, &["notes"])?;
+    assert_eq!(out["notes"].as_array().unwrap().len(), 2);
+    assert_eq!(WalletState::load(&state)?.notes[1].leaf_id, 1);
+
+    // merkle path of the leaf 1 is not provided
+    assert!(run_cli(
+        &state,
+        &mut transport,
+        &[&["withdraw"][..], &args[..]].concat()
+    )
+    .is_err());
+    Ok(())
+}
@@ -0,0 +1,128 @@
+//! Discovery of received notes in the stream of shielder events
+use ink::scale::Decode;
+use mocked_zk::{account::Account, note::Note, traits::Hashable, Scalar};
+
+use crate::{
+    keys::SpendingKey,
+    memo::{decrypt_memo, NotePlaintext},
+};
+
+/// Mirror of `NoteAdded` event emitted by the shielder contract
+#[ink::scale_derive(Encode, Decode)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NoteAdded {
+    pub leaf_id: u32,
+    pub note_hash: Scalar,
+    pub nullifier_old: Option<Scalar>,
+    pub memo: Vec<u8>,
+}
+
+impl NoteAdded {
+    /// Decodes event from raw event data, `None` for other events
+    pub fn decode_event(data: &[u8]) -> Option<Self> {
+        let mut data = data;
+        let event = Self::decode(&mut data).ok()?;
+        data.is_empty().then_some(event)
+    }
+}
+
+/// Note found in the events, spendable by the owner of the key
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReceivedNote {
+    pub leaf_id: u32,
+    pub note: Note,
+    pub account: Account,
+}
+
+/// Trial-decrypts memos of all `events` with `key`
+/// Memos which don't match the emitted note hash are skipped, so that sender can't lie about note
+pub fn scan_notes<'a>(
+    key: &SpendingKey,
+    events: impl IntoIterator<Item = &'a NoteAdded>,
+) -> Vec<ReceivedNote> {
+    events
+        .into_iter()
+        .filter_map(|event| {
+            let NotePlaintext { note, account } = decrypt_memo(key, &event.memo).ok()?;
+            (note.hash() == event.note_hash).then_some(ReceivedNote {
+                leaf_id: event.leaf_id,
+                note,
+                account,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use mocked_zk::{MOCKED_TOKEN, TOKENS_NUMBER};
+    use rand::{rngs::StdRng, SeedableRng};
+
+    use super::*;
+    use crate::memo::encrypt_memo;
+
+    fn note(id: u128) -> (Note, Account) {
+        let account = Account::new([MOCKED_TOKEN; TOKENS_NUMBER]);
+        let note = Note::new(id.into(), 0_u128.into(), 0_u128.into(), account.hash());
+        (note, account)
+    }
+
+    #[test]
+    fn test_scan_notes() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let key = SpendingKey::random(&mut rng);
+        let other_key = SpendingKey::random(&mut rng);
+
+        let (mine, account) = note(1);
+        let (other, _) = note(2);
+        let (lying, _) = note(3);
+        let events = [
+            NoteAdded {
+                leaf_id: 0,
+                note_hash: other.hash(),
+                nullifier_old: None,
+                memo: encrypt_memo(&mut rng, &other_key.address(), other, account),
+            },
+            NoteAdded {
+                leaf_id: 1,
+                note_hash: mine.hash(),
+                nullifier_old: None,
+                memo: encrypt_memo(&mut rng, &key.address(), mine, account),
+            },
+            NoteAdded {
+                leaf_id: 2,
+                note_hash: lying.hash(),
+                nullifier_old: None,
+                memo: encrypt_memo(&mut rng, &key.address(), mine, account),
+            },
+            NoteAdded {
+                leaf_id: 3,
+                note_hash: mine.hash(),
+                nullifier_old: Some(1_u128.into()),
+                memo: vec![],
+            },
+        ];
+
+        assert_eq!(
+            scan_notes(&key, &events),
+            vec![ReceivedNote {
+                leaf_id: 1,
+                note: mine,
+                account,
+            }]
+        );
+    }
+
+    #[test]
+    fn test_decode_event() {
+        let event = NoteAdded {
+            leaf_id: 7,
+            note_hash: 8_u128.into(),
+            nullifier_old: Some(9_u128.into()),
+            memo: vec![1, 2, 3],
+        };
+        let data = ink::scale::Encode::encode(&event);
+        assert_eq!(NoteAdded::decode_event(&data), Some(event));
+        assert_eq!(NoteAdded::decode_event(&data[1..]), None);
+    }
+}
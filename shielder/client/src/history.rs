@@ -0,0 +1,217 @@
+//! Read-only reconstruction of account history with a viewing key
+//!
+//! Viewing key doesn't reveal note secrets, so the note hash can't be checked against the memo:
+//! history is only as honest as the memos emitted for the viewed address.
+use mocked_zk::{account::Account, Scalar};
+
+use crate::{
+    events::NoteAdded,
+    keys::ViewingKey,
+    memo::{view_memo, NoteView},
+};
+
+/// Change of account balances made by a single note
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AccountChange {
+    Created,
+    Deposit { token: Scalar, amount: u128 },
+    Withdraw { token: Scalar, amount: u128 },
+    Unchanged,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HistoryEntry {
+    pub leaf_id: u32,
+    pub account: Account,
+    pub change: AccountChange,
+}
+
+/// All notes of a single account, ordered by leaf id
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AccountHistory {
+    pub id: Scalar,
+    pub entries: Vec<HistoryEntry>,
+}
+
+impl AccountHistory {
+    /// Latest known state of the account
+    pub fn account(&self) -> Account {
+        self.entries
+            .last()
+            .expect("history has at least one entry")
+            .account
+    }
+}
+
+/// Reconstructs history of all accounts whose memos decrypt with `key`
+/// Accounts are ordered by their first note
+pub fn account_history<'a>(
+    key: &ViewingKey,
+    events: impl IntoIterator<Item = &'a NoteAdded>,
+) -> Vec<AccountHistory> {
+    let mut notes: Vec<(u32, NoteView)> = events
+        .into_iter()
+        .filter_map(|event| Some((event.leaf_id, view_memo(key, &event.memo).ok()?)))
+        .collect();
+    notes.sort_by_key(|(leaf_id, _)| *leaf_id);
+
+    let mut histories: Vec<AccountHistory> = vec![];
+    for (leaf_id, NoteView { id, account }) in notes {
+        match histories.iter_mut().find(|history| history.id == id) {
+            Some(history) => {
+                let change = account_change(&history.account(), &account);
+                history.entries.push(HistoryEntry {
+                    leaf_id,
+                    account,
+                    change,
+                });
+            }
+            None => histories.push(AccountHistory {
+                id,
+                entries: vec![HistoryEntry {
+                    leaf_id,
+                    account,
+                    change: AccountChange::Created,
+                }],
+            }),
+        }
+    }
+    histories
+}
+
+/// Single operation changes balance of at most one token
+fn account_change(old: &Account, new: &Account) -> AccountChange {
+    for ((token, old_balance), (_, new_balance)) in old.balances().into_iter().zip(new.balances()) {
+        let (old_balance, new_balance) = (u128::from(old_balance), u128::from(new_balance));
+        if new_balance > old_balance {
+            return AccountChange::Deposit {
+                token,
+                amount: new_balance - old_balance,
+            };
+        }
+        if new_balance < old_balance {
+            return AccountChange::Withdraw {
+                token,
+                amount: old_balance - new_balance,
+            };
+        }
+    }
+    AccountChange::Unchanged
+}
+
+#[cfg(test)]
+mod tests {
+    use mocked_zk::{
+        note::Note,
+        ops::{OpPriv, OpPub, Operation},
+        traits::Hashable,
+        MOCKED_TOKEN, TOKENS_NUMBER,
+    };
+    use rand::{rngs::StdRng, SeedableRng};
+
+    use super::*;
+    use crate::{keys::SpendingKey, memo::encrypt_memo};
+
+    fn apply(account: Account, op_pub: OpPub) -> Account {
+        let op_priv = OpPriv {
+            user: 0_u128.into(),
+        };
+        account
+            .update(Operation::combine(op_pub, op_priv).unwrap())
+            .unwrap()
+    }
+
+    fn note_added(
+        rng: &mut StdRng,
+        key: &SpendingKey,
+        leaf_id: u32,
+        id: u128,
+        account: Account,
+    ) -> NoteAdded {
+        let note = Note::new(id.into(), 0_u128.into(), 0_u128.into(), account.hash());
+        NoteAdded {
+            leaf_id,
+            note_hash: note.hash(),
+            nullifier_old: None,
+            memo: encrypt_memo(rng, &key.address(), note, account),
+        }
+    }
+
+    #[test]
+    fn test_account_history() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let key = SpendingKey::random(&mut rng);
+        let other_key = SpendingKey::random(&mut rng);
+
+        let created = Account::new([MOCKED_TOKEN; TOKENS_NUMBER]);
+        let deposited = apply(
+            created,
+            OpPub::Deposit {
+                amount: 10,
+                token: MOCKED_TOKEN,
+                user: 0_u128.into(),
+                valid_until: None,
+            },
+        );
+        let withdrawn = apply(
+            deposited,
+            OpPub::Withdraw {
+                amount: 3,
+                token: MOCKED_TOKEN,
+                user: 0_u128.into(),
+                valid_until: None,
+            },
+        );
+
+        // events come unordered and mixed with notes of others
+        let events = [
+            note_added(&mut rng, &key, 4, 1, withdrawn),
+            note_added(&mut rng, &key, 1, 2, created),
+            note_added(&mut rng, &other_key, 2, 1, deposited),
+            note_added(&mut rng, &key, 0, 1, created),
+            note_added(&mut rng, &key, 3, 1, deposited),
+        ];
+
+        let history = account_history(&key.viewing_key(), &events);
+        assert_eq!(
+            history,
+            vec![
+                AccountHistory {
+                    id: 1_u128.into(),
+                    entries: vec![
+                        HistoryEntry {
+                            leaf_id: 0,
+                            account: created,
+                            change: AccountChange::Created,
+                        },
+                        HistoryEntry {
+                            leaf_id: 3,
+                            account: deposited,
+                            change: AccountChange::Deposit {
+                                token: MOCKED_TOKEN,
+                                amount: 10,
+                            },
+                        },
+                        HistoryEntry {
+                            leaf_id: 4,
+                            account: withdrawn,
+                            change: AccountChange::Withdraw {
+                                token: MOCKED_TOKEN,
+                                amount: 3,
+                            },
+                        },
+                    ],
+                },
+                AccountHistory {
+                    id: 2_u128.into(),
+                    entries: vec![HistoryEntry {
+                        leaf_id: 1,
+                        account: created,
+                        change: AccountChange::Created,
+                    }],
+                },
+            ]
+        );
+        assert_eq!(history[0].account(), withdrawn);
+    }
+}
@@ -0,0 +1,119 @@
+//! Keys of a shielder user
+//!
+//! Spending key decrypts all secrets of received notes, so it allows spending them.
+//! Viewing key is derived from the spending key and decrypts only account balances,
+//! so it can be handed to an auditor.
+use mocked_zk::derivation::Seed;
+use rand::{CryptoRng, RngCore};
+use sha2::{Digest, Sha256};
+use x25519_dalek::{PublicKey, StaticSecret};
+
+const SPENDING_KEY_TAG: &[u8] = b"shielder-spending-key";
+const VIEWING_KEY_TAG: &[u8] = b"shielder-viewing-key";
+
+#[derive(Clone)]
+pub struct SpendingKey {
+    secret: StaticSecret,
+}
+
+#[derive(Clone)]
+pub struct ViewingKey {
+    secret: StaticSecret,
+}
+
+/// Public keys which notes are encrypted to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Address {
+    pub spending: PublicKey,
+    pub viewing: PublicKey,
+}
+
+impl SpendingKey {
+    pub fn random<R: RngCore + CryptoRng>(rng: &mut R) -> Self {
+        Self {
+            secret: StaticSecret::random_from_rng(rng),
+        }
+    }
+
+    pub fn from_bytes(bytes: [u8; 32]) -> Self {
+        Self {
+            secret: StaticSecret::from(bytes),
+        }
+    }
+
+    /// Key of the wallet with `seed`, memos of its notes are encrypted to its address
+    pub fn from_seed(seed: &Seed) -> Self {
+        let bytes: [u8; 32] = Sha256::new()
+            .chain_update(SPENDING_KEY_TAG)
+            .chain_update(seed.to_bytes())
+            .finalize()
+            .into();
+        Self::from_bytes(bytes)
+    }
+
+    pub fn to_bytes(&self) -> [u8; 32] {
+        self.secret.to_bytes()
+    }
+
+    pub fn viewing_key(&self) -> ViewingKey {
+        let bytes: [u8; 32] = Sha256::new()
+            .chain_update(VIEWING_KEY_TAG)
+            .chain_update(self.secret.as_bytes())
+            .finalize()
+            .into();
+        ViewingKey::from_bytes(bytes)
+    }
+
+    pub fn address(&self) -> Address {
+        Address {
+            spending: PublicKey::from(&self.secret),
+            viewing: self.viewing_key().public_key(),
+        }
+    }
+
+    pub(crate) fn secret(&self) -> &StaticSecret {
+        &self.secret
+    }
+}
+
+impl ViewingKey {
+    pub fn from_bytes(bytes: [u8; 32]) -> Self {
+        Self {
+            secret: StaticSecret::from(bytes),
+        }
+    }
+
+    pub fn to_bytes(&self) -> [u8; 32] {
+        self.secret.to_bytes()
+    }
+
+    pub fn public_key(&self) -> PublicKey {
+        PublicKey::from(&self.secret)
+    }
+
+    pub(crate) fn secret(&self) -> &StaticSecret {
+        &self.secret
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{rngs::StdRng, SeedableRng};
+
+    use super::*;
+
+    #[test]
+    fn test_keys_derivation() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let key = SpendingKey::random(&mut rng);
+        let restored = SpendingKey::from_bytes(key.to_bytes());
+
+        assert_eq!(key.address(), restored.address());
+        assert_eq!(
+            key.viewing_key().to_bytes(),
+            restored.viewing_key().to_bytes()
+        );
+        assert_ne!(key.to_bytes(), key.viewing_key().to_bytes());
+        assert_ne!(key.address().spending, key.address().viewing);
+    }
+}
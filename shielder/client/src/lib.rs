@@ -0,0 +1,8 @@
+//! Off-chain part of shielder: everything user needs that is not verified by the contract
+pub mod events;
+pub mod history;
+pub mod keys;
+pub mod memo;
+pub mod recovery;
+
+pub use x25519_dalek::PublicKey;
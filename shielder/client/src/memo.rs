@@ -0,0 +1,284 @@
+//! Memos let the recipient learn secrets of a note emitted by the shielder contract.
+//!
+//! Memo layout: `version || ephemeral public key || SCALE((view ciphertext, spend ciphertext))`.
+//! View part is SCALE encoded `NoteView` encrypted to the viewing key, spend part is SCALE
+//! encoded `NoteSecrets` encrypted to the spending key. Both are encrypted with ChaCha20Poly1305
+//! under keys derived from the ephemeral-recipient Diffie-Hellman secrets.
+//! Every memo has a fresh ephemeral key, so a constant nonce is never reused with the same key.
+use chacha20poly1305::{aead::Aead, ChaCha20Poly1305, Key, KeyInit, Nonce};
+use ink::scale::{Decode, Encode};
+use mocked_zk::{account::Account, note::Note, traits::Hashable, Scalar};
+use rand::{CryptoRng, RngCore};
+use sha2::{Digest, Sha256};
+use x25519_dalek::{PublicKey, StaticSecret};
+
+use crate::keys::{Address, SpendingKey, ViewingKey};
+
+pub const MEMO_VERSION: u8 = 1;
+
+const VIEW_KDF_TAG: &[u8] = b"shielder-memo-view-v1";
+const SPEND_KDF_TAG: &[u8] = b"shielder-memo-spend-v1";
+const KEY_LEN: usize = 32;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MemoError {
+    UnsupportedVersion,
+    Malformed,
+    DecryptionFail,
+}
+
+/// Part of the note visible with the viewing key
+#[ink::scale_derive(Encode, Decode)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NoteView {
+    /// id of the account, the same for all its notes
+    pub id: Scalar,
+    pub account: Account,
+}
+
+/// Part of the note needed to spend it
+#[ink::scale_derive(Encode, Decode)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct NoteSecrets {
+    trapdoor: Scalar,
+    nullifier_key: Scalar,
+}
+
+/// Secrets of a note, enough to spend it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NotePlaintext {
+    pub note: Note,
+    pub account: Account,
+}
+
+/// Encrypts `note` and `account` so that only the owner of `recipient` can spend it,
+/// and only holders of its viewing key can see the account
+pub fn encrypt_memo<R: RngCore + CryptoRng>(
+    rng: &mut R,
+    recipient: &Address,
+    note: Note,
+    account: Account,
+) -> Vec<u8> {
+    // used for both parts, so it can't be `EphemeralSecret` consumed by Diffie-Hellman
+    let ephemeral_secret = StaticSecret::random_from_rng(rng);
+    let ephemeral_public = PublicKey::from(&ephemeral_secret);
+
+    let view = NoteView {
+        id: note.id(),
+        account,
+    };
+    let secrets = NoteSecrets {
+        trapdoor: note.trapdoor(),
+        nullifier_key: note.nullifier_key(),
+    };
+    let view_ciphertext = encrypt(
+        VIEW_KDF_TAG,
+        &ephemeral_secret,
+        &ephemeral_public,
+        &recipient.viewing,
+        &view.encode(),
+    );
+    let spend_ciphertext = encrypt(
+        SPEND_KDF_TAG,
+        &ephemeral_secret,
+        &ephemeral_public,
+        &recipient.spending,
+        &secrets.encode(),
+    );
+
+    let mut memo = vec![MEMO_VERSION];
+    memo.extend_from_slice(ephemeral_public.as_bytes());
+    (view_ciphertext, spend_ciphertext).encode_to(&mut memo);
+    memo
+}
+
+/// Trial-decrypts view part of `memo` with `key`
+/// Returns `DecryptionFail` if the memo was meant for someone else
+pub fn view_memo(key: &ViewingKey, memo: &[u8]) -> Result<NoteView, MemoError> {
+    let (ephemeral_public, view_ciphertext, _) = split_memo(memo)?;
+    let plaintext = decrypt(
+        VIEW_KDF_TAG,
+        key.secret(),
+        &ephemeral_public,
+        &view_ciphertext,
+    )?;
+    NoteView::decode(&mut plaintext.as_slice()).map_err(|_| MemoError::Malformed)
+}
+
+/// Trial-decrypts whole `memo` with `key`
+/// Returns `DecryptionFail` if the memo was meant for someone else
+pub fn decrypt_memo(key: &SpendingKey, memo: &[u8]) -> Result<NotePlaintext, MemoError> {
+    let NoteView { id, account } = view_memo(&key.viewing_key(), memo)?;
+    let (ephemeral_public, _, spend_ciphertext) = split_memo(memo)?;
+    let plaintext = decrypt(
+        SPEND_KDF_TAG,
+        key.secret(),
+        &ephemeral_public,
+        &spend_ciphertext,
+    )?;
+    let NoteSecrets {
+        trapdoor,
+        nullifier_key,
+    } = NoteSecrets::decode(&mut plaintext.as_slice()).map_err(|_| MemoError::Malformed)?;
+    Ok(NotePlaintext {
+        note: Note::new(id, trapdoor, nullifier_key, account.hash()),
+        account,
+    })
+}
+
+fn split_memo(memo: &[u8]) -> Result<(PublicKey, Vec<u8>, Vec<u8>), MemoError> {
+    let (&version, rest) = memo.split_first().ok_or(MemoError::Malformed)?;
+    if version != MEMO_VERSION {
+        return Err(MemoError::UnsupportedVersion);
+    }
+    if rest.len() < KEY_LEN {
+        return Err(MemoError::Malformed);
+    }
+    let (ephemeral_public, mut ciphertexts) = rest.split_at(KEY_LEN);
+    let ephemeral_public: [u8; KEY_LEN] = ephemeral_public.try_into().unwrap();
+    let (view_ciphertext, spend_ciphertext) =
+        <(Vec<u8>, Vec<u8>)>::decode(&mut ciphertexts).map_err(|_| MemoError::Malformed)?;
+    if !ciphertexts.is_empty() {
+        return Err(MemoError::Malformed);
+    }
+    Ok((
+        PublicKey::from(ephemeral_public),
+        view_ciphertext,
+        spend_ciphertext,
+    ))
+}
+
+fn encrypt(
+    tag: &[u8],
+    ephemeral_secret: &StaticSecret,
+    ephemeral_public: &PublicKey,
+    recipient: &PublicKey,
+    plaintext: &[u8],
+) -> Vec<u8> {
+    let shared = ephemeral_secret.diffie_hellman(recipient);
+    cipher(tag, shared.as_bytes(), ephemeral_public, recipient)
+        .encrypt(&Nonce::default(), plaintext)
+        .expect("encryption with ChaCha20Poly1305 is infallible for short messages")
+}
+
+fn decrypt(
+    tag: &[u8],
+    secret: &StaticSecret,
+    ephemeral_public: &PublicKey,
+    ciphertext: &[u8],
+) -> Result<Vec<u8>, MemoError> {
+    let shared = secret.diffie_hellman(ephemeral_public);
+    cipher(
+        tag,
+        shared.as_bytes(),
+        ephemeral_public,
+        &PublicKey::from(secret),
+    )
+    .decrypt(&Nonce::default(), ciphertext)
+    .map_err(|_| MemoError::DecryptionFail)
+}
+
+fn cipher(
+    tag: &[u8],
+    shared: &[u8; 32],
+    ephemeral: &PublicKey,
+    recipient: &PublicKey,
+) -> ChaCha20Poly1305 {
+    let key = Sha256::new()
+        .chain_update(tag)
+        .chain_update(shared)
+        .chain_update(ephemeral.as_bytes())
+        .chain_update(recipient.as_bytes())
+        .finalize();
+    ChaCha20Poly1305::new(Key::from_slice(&key))
+}
+
+#[cfg(test)]
+mod tests {
+    use mocked_zk::{MOCKED_TOKEN, TOKENS_NUMBER};
+    use rand::{rngs::StdRng, SeedableRng};
+
+    use super::*;
+
+    fn note() -> (Note, Account) {
+        let account = Account::new([MOCKED_TOKEN; TOKENS_NUMBER]);
+        let note = Note::new(1_u128.into(), 2_u128.into(), 3_u128.into(), account.hash());
+        (note, account)
+    }
+
+    #[test]
+    fn test_memo_roundtrip() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let key = SpendingKey::random(&mut rng);
+        let (note, account) = note();
+
+        let memo = encrypt_memo(&mut rng, &key.address(), note, account);
+        assert_eq!(
+            decrypt_memo(&key, &memo),
+            Ok(NotePlaintext { note, account })
+        );
+        assert_eq!(
+            view_memo(&key.viewing_key(), &memo),
+            Ok(NoteView {
+                id: note.id(),
+                account
+            })
+        );
+    }
+
+    #[test]
+    fn test_memo_for_other_recipient() {
+        let mut rng = StdRng::seed_from_u64(1);
+        let key = SpendingKey::random(&mut rng);
+        let other_key = SpendingKey::random(&mut rng);
+        let (note, account) = note();
+
+        let memo = encrypt_memo(&mut rng, &key.address(), note, account);
+        assert_eq!(
+            decrypt_memo(&other_key, &memo),
+            Err(MemoError::DecryptionFail)
+        );
+        assert_eq!(
+            view_memo(&other_key.viewing_key(), &memo),
+            Err(MemoError::DecryptionFail)
+        );
+    }
+
+    #[test]
+    fn test_viewing_key_cannot_spend() {
+        let mut rng = StdRng::seed_from_u64(3);
+        let key = SpendingKey::random(&mut rng);
+        let (note, account) = note();
+
+        let memo = encrypt_memo(&mut rng, &key.address(), note, account);
+        let viewing_key_as_spending = SpendingKey::from_bytes(key.viewing_key().to_bytes());
+        assert_eq!(
+            decrypt_memo(&viewing_key_as_spending, &memo),
+            Err(MemoError::DecryptionFail)
+        );
+    }
+
+    #[test]
+    fn test_memo_malformed() {
+        let mut rng = StdRng::seed_from_u64(2);
+        let key = SpendingKey::random(&mut rng);
+        let (note, account) = note();
+
+        let mut memo = encrypt_memo(&mut rng, &key.address(), note, account);
+        assert_eq!(decrypt_memo(&key, &[]), Err(MemoError::Malformed));
+        assert_eq!(
+            decrypt_memo(&key, &memo[..KEY_LEN]),
+            Err(MemoError::Malformed)
+        );
+
+        let last = memo.len() - 1;
+        memo[last] ^= 1;
+        assert_eq!(decrypt_memo(&key, &memo), Err(MemoError::DecryptionFail));
+
+        memo[0] = MEMO_VERSION + 1;
+        assert_eq!(
+            decrypt_memo(&key, &memo),
+            Err(MemoError::UnsupportedVersion)
+        );
+    }
+}
@@ -0,0 +1,130 @@
+//! Recovery of an account from its wallet seed
+//!
+//! Secrets of notes are derived from the seed and memos of the notes are encrypted to the address
+//! of `SpendingKey::from_seed`, so the seed and the emitted events are enough to find all notes.
+use mocked_zk::{account::Account, derivation::Seed, traits::Hashable};
+
+use crate::{
+    events::{scan_notes, NoteAdded},
+    keys::SpendingKey,
+};
+
+/// Note of the account of the seed, found in the events
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RecoveredNote {
+    /// number of the note of the account, its secrets are derived from the seed and counter
+    pub counter: u32,
+    pub leaf_id: u32,
+    pub account: Account,
+}
+
+/// Finds consecutive notes of the account of `seed` in `events`, the last one is the current note
+/// Received notes with secrets not derived from the seed are not a part of the account
+pub fn recover<'a>(
+    seed: &Seed,
+    events: impl IntoIterator<Item = &'a NoteAdded>,
+) -> Vec<RecoveredNote> {
+    let received = scan_notes(&SpendingKey::from_seed(seed), events);
+    let mut notes = vec![];
+    let mut counter = 0;
+    while let Some(found) = received
+        .iter()
+        .find(|found| found.note == seed.note(counter, found.account.hash()))
+    {
+        notes.push(RecoveredNote {
+            counter,
+            leaf_id: found.leaf_id,
+            account: found.account,
+        });
+        counter += 1;
+    }
+    notes
+}
+
+#[cfg(test)]
+mod tests {
+    use mocked_zk::{
+        note::Note,
+        ops::{OpPriv, OpPub, Operation},
+        MOCKED_TOKEN, TOKENS_NUMBER,
+    };
+    use rand::{rngs::StdRng, SeedableRng};
+
+    use super::*;
+    use crate::memo::encrypt_memo;
+
+    fn note_added(
+        rng: &mut StdRng,
+        seed: &Seed,
+        leaf_id: u32,
+        note: Note,
+        account: Account,
+    ) -> NoteAdded {
+        NoteAdded {
+            leaf_id,
+            note_hash: note.hash(),
+            nullifier_old: None,
+            memo: encrypt_memo(rng, &SpendingKey::from_seed(seed).address(), note, account),
+        }
+    }
+
+    #[test]
+    fn test_recover_from_seed() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let seed = Seed::from_bytes([0x1; 32]);
+        let other_seed = Seed::from_bytes([0x2; 32]);
+
+        let created = Account::new([MOCKED_TOKEN; TOKENS_NUMBER]);
+        let op_priv = OpPriv {
+            user: 0_u128.into(),
+        };
+        let deposit = OpPub::Deposit {
+            amount: 10,
+            token: MOCKED_TOKEN,
+            user: 0_u128.into(),
+            valid_until: None,
+        };
+        let deposited = created
+            .update(Operation::combine(deposit, op_priv).unwrap())
+            .unwrap();
+        // received from someone else, so its secrets are not derived from the seed
+        let sent = Note::new(seed.id(), 5_u128.into(), 6_u128.into(), deposited.hash());
+
+        let events = [
+            note_added(
+                &mut rng,
+                &other_seed,
+                0,
+                other_seed.note(0, created.hash()),
+                created,
+            ),
+            note_added(&mut rng, &seed, 1, seed.note(0, created.hash()), created),
+            note_added(&mut rng, &seed, 2, sent, deposited),
+            note_added(
+                &mut rng,
+                &seed,
+                3,
+                seed.note(1, deposited.hash()),
+                deposited,
+            ),
+        ];
+
+        let notes = recover(&seed, &events);
+        assert_eq!(
+            notes,
+            vec![
+                RecoveredNote {
+                    counter: 0,
+                    leaf_id: 1,
+                    account: created,
+                },
+                RecoveredNote {
+                    counter: 1,
+                    leaf_id: 3,
+                    account: deposited,
+                },
+            ]
+        );
+        assert_eq!(recover(&other_seed, &events[1..]), vec![]);
+    }
+}
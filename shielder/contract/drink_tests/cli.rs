@@ -0,0 +1,110 @@
+use std::path::Path;
+
+use anyhow::Result;
+use clap::Parser;
+use drink::{minimal::MinimalSandbox, session::Session, AccountId32};
+use mocked_zk::wire;
+use serde_json::Value;
+use shielder_cli::{run, Cli};
+use shielder_wallet::{calls::UpdateNoteCall, drink::DrinkTransport};
+
+use super::utils::{chain::*, psp22::*, shielder::*};
+use crate::errors::ShielderError;
+
+fn run_cli(
+    session: &mut Session<MinimalSandbox>,
+    shielder_address: &AccountId32,
+    state: &Path,
+    args: &[&str],
+) -> Result<Value> {
+    let state = state.to_str().unwrap();
+    let cli = Cli::try_parse_from(["shielder-cli", "--state", state].iter().chain(args))?;
+    let mut transport = DrinkTransport::<ShielderError>::new(session, shielder_address.clone());
+    let mut out = vec![];
+    run(&cli, &mut transport, &mut out)?;
+    Ok(serde_json::from_slice(&out)?)
+}
+
+fn to_hex(address: &AccountId32) -> String {
+    let bytes: &[u8; 32] = address.as_ref();
+    hex::encode(bytes)
+}
+
+#[drink::test]
+fn cli_deposit_withdraw(mut session: Session) -> Result<()> {
+    let alice = init_alice(&mut session)?;
+    let bob = init_bob(&mut session)?;
+
+    session = session.with_actor(alice.clone());
+
+    let psp22_address = deploy_test_token(&mut session, 100)?;
+    let shielder_address = deploy_shielder(&mut session, &psp22_address)?;
+    let token = to_hex(&psp22_address);
+
+    let dir = tempfile::tempdir()?;
+    let state = dir.path().join("wallet.json");
+    run_cli(
+        &mut session,
+        &shielder_address,
+        &state,
+        &["init", "--seed", &hex::encode([0x19; 32])],
+    )?;
+
+    // CREATE ACCOUNT
+    let out = run_cli(
+        &mut session,
+        &shielder_address,
+        &state,
+        &["create-account", "--submit"],
+    )?;
+    assert_eq!(out["leaf_id"], 0);
+
+    // APPROVE TRANSFER
+    psp22_approve(&mut session, &psp22_address, &shielder_address, 10)?;
+
+    // DEPOSIT
+    let alice_hex = to_hex(&alice);
+    let out = run_cli(
+        &mut session,
+        &shielder_address,
+        &state,
+        &[
+            "deposit", "--token", &token, "--amount", "10", "--user", &alice_hex, "--submit",
+        ],
+    )?;
+    assert_eq!(out["leaf_id"], 1);
+    let out = run_cli(&mut session, &shielder_address, &state, &["balance"])?;
+    assert_eq!(out[format!("0x{token}")], 10);
+
+    // SWITCH TO bob
+    session = session.with_actor(bob.clone());
+
+    // WITHDRAW, printed call is submitted separately and confirmed
+    let bob_hex = to_hex(&bob);
+    let out = run_cli(
+        &mut session,
+        &shielder_address,
+        &state,
+        &[
+            "withdraw", "--token", &token, "--amount", "3", "--user", &bob_hex,
+        ],
+    )?;
+    let call: UpdateNoteCall = wire::from_json(&out["call"]).unwrap();
+    let leaf_id = shielder_update_note(&mut session, &shielder_address, &call)?.unwrap();
+    run_cli(
+        &mut session,
+        &shielder_address,
+        &state,
+        &["confirm", "--leaf-id", &leaf_id.to_string()],
+    )?;
+    let out = run_cli(&mut session, &shielder_address, &state, &["balance"])?;
+    assert_eq!(out[format!("0x{token}")], 7);
+
+    let bob_psp22_balance = get_psp22_balance(&mut session, &psp22_address, &bob)?;
+    assert_eq!(bob_psp22_balance, 3);
+    let shielder_psp22_balance =
+        get_psp22_balance(&mut session, &psp22_address, &shielder_address)?;
+    assert_eq!(shielder_psp22_balance, 7);
+
+    Ok(())
+}
@@ -0,0 +1,135 @@
+use anyhow::Result;
+use drink::session::Session;
+use mocked_zk::{derivation::Seed, errors::ZkpError, ops::OpPub};
+use rand::prelude::*;
+
+use super::utils::{chain::*, ops::*, psp22::*, shielder::*};
+use crate::errors::ShielderError;
+
+#[drink::test]
+fn update_within_deadline(mut session: Session) -> Result<()> {
+    let mut rng = StdRng::seed_from_u64(9);
+
+    let alice = init_alice(&mut session)?;
+    session = session.with_actor(alice.clone());
+
+    let psp22_address = deploy_test_token(&mut session, 100)?;
+    let shielder_address = deploy_shielder(&mut session, &psp22_address)?;
+
+    // CREATE ACCOUNT
+    let user_shielded_data =
+        create_shielder_account(&mut session, &shielder_address, Seed::from_bytes(rng.gen()))?;
+
+    // APPROVE TRANSFER
+    psp22_approve(&mut session, &psp22_address, &shielder_address, 10)?;
+
+    // DEPOSIT, valid for next block too
+    let call = prepare_shielder_update(
+        &mut session,
+        &shielder_address,
+        deposit_op(&psp22_address, &alice, 10).valid_until(current_block(&mut session) + 1),
+        user_shielded_data,
+        None,
+    )?;
+    advance_blocks(&mut session, 1);
+    shielder_update_note(&mut session, &shielder_address, &call)?.unwrap();
+
+    let shielder_psp22_balance =
+        get_psp22_balance(&mut session, &psp22_address, &shielder_address)?;
+    assert_eq!(shielder_psp22_balance, 10);
+
+    Ok(())
+}
+
+#[drink::test]
+fn update_after_deadline_rejected(mut session: Session) -> Result<()> {
+    let mut rng = StdRng::seed_from_u64(10);
+
+    let alice = init_alice(&mut session)?;
+    let bob = init_bob(&mut session)?;
+
+    session = session.with_actor(alice.clone());
+
+    let psp22_address = deploy_test_token(&mut session, 100)?;
+    let shielder_address = deploy_shielder(&mut session, &psp22_address)?;
+
+    // CREATE ACCOUNT
+    let user_shielded_data =
+        create_shielder_account(&mut session, &shielder_address, Seed::from_bytes(rng.gen()))?;
+
+    // APPROVE TRANSFER
+    psp22_approve(&mut session, &psp22_address, &shielder_address, 10)?;
+
+    // DEPOSIT
+    let user_shielded_data = shielder_update(
+        &mut session,
+        &shielder_address,
+        deposit_op(&psp22_address, &alice, 10),
+        user_shielded_data,
+    )?;
+
+    // WITHDRAW, valid only in current block
+    let call = prepare_shielder_update(
+        &mut session,
+        &shielder_address,
+        withdraw_op(&psp22_address, &bob, 1).valid_until(current_block(&mut session)),
+        user_shielded_data,
+        None,
+    )?;
+
+    // relayer holds the call
+    advance_blocks(&mut session, 2);
+    session = session.with_actor(bob.clone());
+    assert_eq!(
+        shielder_update_note(&mut session, &shielder_address, &call)?,
+        Err(ShielderError::OperationExpired)
+    );
+
+    let bob_psp22_balance = get_psp22_balance(&mut session, &psp22_address, &bob)?;
+    assert_eq!(bob_psp22_balance, 0);
+    let shielder_psp22_balance =
+        get_psp22_balance(&mut session, &psp22_address, &shielder_address)?;
+    assert_eq!(shielder_psp22_balance, 10);
+
+    Ok(())
+}
+
+#[drink::test]
+fn deadline_cannot_be_extended(mut session: Session) -> Result<()> {
+    let mut rng = StdRng::seed_from_u64(11);
+
+    let alice = init_alice(&mut session)?;
+    session = session.with_actor(alice.clone());
+
+    let psp22_address = deploy_test_token(&mut session, 100)?;
+    let shielder_address = deploy_shielder(&mut session, &psp22_address)?;
+
+    // CREATE ACCOUNT
+    let user_shielded_data =
+        create_shielder_account(&mut session, &shielder_address, Seed::from_bytes(rng.gen()))?;
+
+    psp22_approve(&mut session, &psp22_address, &shielder_address, 10)?;
+
+    let block = current_block(&mut session);
+    let mut call = prepare_shielder_update(
+        &mut session,
+        &shielder_address,
+        deposit_op(&psp22_address, &alice, 10).valid_until(block),
+        user_shielded_data,
+        None,
+    )?;
+    advance_blocks(&mut session, 2);
+
+    // deadline is committed by the proof
+    for deadline in [Some(block + 10), None] {
+        if let OpPub::Deposit { valid_until, .. } = &mut call.op_pub {
+            *valid_until = deadline;
+        }
+        assert_eq!(
+            shielder_update_note(&mut session, &shielder_address, &call)?,
+            Err(ShielderError::Zkp(ZkpError::OperationHashMismatch))
+        );
+    }
+
+    Ok(())
+}
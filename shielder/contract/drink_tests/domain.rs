@@ -0,0 +1,56 @@
+use anyhow::Result;
+use drink::session::Session;
+use mocked_zk::{derivation::Seed, errors::ZkpError};
+use rand::prelude::*;
+
+use super::utils::{chain::*, psp22::*, shielder::*};
+use crate::errors::ShielderError;
+
+#[drink::test]
+fn note_for_other_deployment_rejected(mut session: Session) -> Result<()> {
+    let mut rng = StdRng::seed_from_u64(6);
+
+    let alice = init_alice(&mut session)?;
+    let bob = init_bob(&mut session)?;
+
+    session = session.with_actor(alice.clone());
+
+    let psp22_address = deploy_test_token(&mut session, 100)?;
+    let shielder_a = deploy_shielder(&mut session, &psp22_address)?;
+
+    // same code and chain tag, different address
+    session = session.with_actor(bob.clone());
+    let shielder_b = deploy_shielder(&mut session, &psp22_address)?;
+
+    let domain_a = get_shielder_domain(&mut session, &shielder_a)?;
+    let (h_note_new, proof) =
+        new_account_note(&psp22_address, Seed::from_bytes(rng.gen()), domain_a);
+
+    assert_eq!(
+        shielder_add_note(&mut session, &shielder_b, h_note_new, proof, vec![])?,
+        Err(ShielderError::Zkp(ZkpError::DomainMismatch))
+    );
+    assert_eq!(
+        shielder_add_note(&mut session, &shielder_a, h_note_new, proof, vec![])?,
+        Ok(0)
+    );
+
+    Ok(())
+}
+
+#[drink::test]
+fn domain_depends_on_chain_tag(mut session: Session) -> Result<()> {
+    let alice = init_alice(&mut session)?;
+    session = session.with_actor(alice.clone());
+
+    let psp22_address = deploy_test_token(&mut session, 100)?;
+    let shielder_a = deploy_shielder_with_chain_tag(&mut session, &psp22_address, 1_u128.into())?;
+    let shielder_b = deploy_shielder_with_chain_tag(&mut session, &psp22_address, 2_u128.into())?;
+
+    assert_ne!(
+        get_shielder_domain(&mut session, &shielder_a)?,
+        get_shielder_domain(&mut session, &shielder_b)?
+    );
+
+    Ok(())
+}
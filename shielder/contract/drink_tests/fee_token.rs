@@ -0,0 +1,83 @@
+use anyhow::Result;
+use drink::session::Session;
+use mocked_zk::derivation::Seed;
+use rand::prelude::*;
+
+use super::utils::{chain::*, ops::*, psp22::*, shielder::*};
+use crate::errors::ShielderError;
+
+#[drink::test]
+fn deposit_of_fee_token_rejected(mut session: Session) -> Result<()> {
+    let mut rng = StdRng::seed_from_u64(14);
+
+    let alice = init_alice(&mut session)?;
+    session = session.with_actor(alice.clone());
+
+    let psp22_address = deploy_fee_token(&mut session, 100, 1)?;
+    let shielder_address = deploy_shielder(&mut session, &psp22_address)?;
+
+    // CREATE ACCOUNT
+    let user_shielded_data =
+        create_shielder_account(&mut session, &shielder_address, Seed::from_bytes(rng.gen()))?;
+
+    // APPROVE TRANSFER
+    psp22_approve(&mut session, &psp22_address, &shielder_address, 10)?;
+
+    // DEPOSIT, shielder would receive only 9 tokens
+    let merkle_root = get_shielder_merkle_root(&mut session, &shielder_address)?;
+    let call = prepare_shielder_update(
+        &mut session,
+        &shielder_address,
+        deposit_op(&psp22_address, &alice, 10),
+        user_shielded_data,
+        None,
+    )?;
+    assert_eq!(
+        shielder_update_note(&mut session, &shielder_address, &call)?,
+        Err(ShielderError::DepositShortfall)
+    );
+
+    assert_eq!(
+        get_shielder_merkle_root(&mut session, &shielder_address)?,
+        merkle_root
+    );
+    let alice_psp22_balance = get_psp22_balance(&mut session, &psp22_address, &alice)?;
+    assert_eq!(alice_psp22_balance, 100);
+    let shielder_psp22_balance =
+        get_psp22_balance(&mut session, &psp22_address, &shielder_address)?;
+    assert_eq!(shielder_psp22_balance, 0);
+
+    Ok(())
+}
+
+#[drink::test]
+fn deposit_of_zero_fee_token_accepted(mut session: Session) -> Result<()> {
+    let mut rng = StdRng::seed_from_u64(15);
+
+    let alice = init_alice(&mut session)?;
+    session = session.with_actor(alice.clone());
+
+    let psp22_address = deploy_fee_token(&mut session, 100, 0)?;
+    let shielder_address = deploy_shielder(&mut session, &psp22_address)?;
+
+    // CREATE ACCOUNT
+    let user_shielded_data =
+        create_shielder_account(&mut session, &shielder_address, Seed::from_bytes(rng.gen()))?;
+
+    // APPROVE TRANSFER
+    psp22_approve(&mut session, &psp22_address, &shielder_address, 10)?;
+
+    // DEPOSIT
+    shielder_update(
+        &mut session,
+        &shielder_address,
+        deposit_op(&psp22_address, &alice, 10),
+        user_shielded_data,
+    )?;
+
+    let shielder_psp22_balance =
+        get_psp22_balance(&mut session, &psp22_address, &shielder_address)?;
+    assert_eq!(shielder_psp22_balance, 10);
+
+    Ok(())
+}
@@ -0,0 +1,107 @@
+use anyhow::Result;
+use drink::{session::Session, AccountId32};
+use mocked_zk::{derivation::Seed, errors::ZkpError, Scalar};
+use rand::prelude::*;
+
+use super::utils::{chain::*, ops::*, psp22::*, shielder::*};
+use crate::errors::ShielderError;
+
+#[drink::test]
+fn update_submitted_by_other_actor_rejected(mut session: Session) -> Result<()> {
+    let mut rng = StdRng::seed_from_u64(7);
+
+    let alice = init_alice(&mut session)?;
+    let bob = init_bob(&mut session)?;
+    let eve = AccountId32::new([4u8; 32]);
+    init_acc_with_balance(&mut session, &eve)?;
+
+    session = session.with_actor(alice.clone());
+
+    let psp22_address = deploy_test_token(&mut session, 100)?;
+    let shielder_address = deploy_shielder(&mut session, &psp22_address)?;
+
+    // CREATE ACCOUNT
+    let user_shielded_data =
+        create_shielder_account(&mut session, &shielder_address, Seed::from_bytes(rng.gen()))?;
+
+    // APPROVE TRANSFER
+    psp22_approve(&mut session, &psp22_address, &shielder_address, 10)?;
+
+    // DEPOSIT
+    let user_shielded_data = shielder_update(
+        &mut session,
+        &shielder_address,
+        deposit_op(&psp22_address, &alice, 10),
+        user_shielded_data,
+    )?;
+
+    // WITHDRAW, only bob can submit
+    let call = prepare_shielder_update(
+        &mut session,
+        &shielder_address,
+        withdraw_op(&psp22_address, &bob, 1),
+        user_shielded_data,
+        Some(&bob),
+    )?;
+
+    // SWITCH TO eve
+    session = session.with_actor(eve.clone());
+    assert_eq!(
+        shielder_update_note(&mut session, &shielder_address, &call)?,
+        Err(ShielderError::SubmitterMismatch)
+    );
+
+    // SWITCH TO bob
+    session = session.with_actor(bob.clone());
+    shielder_update_note(&mut session, &shielder_address, &call)?.unwrap();
+
+    let bob_psp22_balance = get_psp22_balance(&mut session, &psp22_address, &bob)?;
+    assert_eq!(bob_psp22_balance, 1);
+    let shielder_psp22_balance =
+        get_psp22_balance(&mut session, &psp22_address, &shielder_address)?;
+    assert_eq!(shielder_psp22_balance, 9);
+
+    Ok(())
+}
+
+#[drink::test]
+fn submitter_cannot_be_replaced(mut session: Session) -> Result<()> {
+    let mut rng = StdRng::seed_from_u64(8);
+
+    let alice = init_alice(&mut session)?;
+    let bob = init_bob(&mut session)?;
+    let eve = AccountId32::new([4u8; 32]);
+    init_acc_with_balance(&mut session, &eve)?;
+
+    session = session.with_actor(alice.clone());
+
+    let psp22_address = deploy_test_token(&mut session, 100)?;
+    let shielder_address = deploy_shielder(&mut session, &psp22_address)?;
+
+    // CREATE ACCOUNT
+    let user_shielded_data =
+        create_shielder_account(&mut session, &shielder_address, Seed::from_bytes(rng.gen()))?;
+
+    psp22_approve(&mut session, &psp22_address, &shielder_address, 10)?;
+
+    // DEPOSIT, only bob can submit
+    let mut call = prepare_shielder_update(
+        &mut session,
+        &shielder_address,
+        deposit_op(&psp22_address, &alice, 10),
+        user_shielded_data,
+        Some(&bob),
+    )?;
+
+    // eve claims the call, or allows anyone to submit it
+    session = session.with_actor(eve.clone());
+    for submitter in [Some(Scalar::from_bytes(*eve.as_ref())), None] {
+        call.submitter = submitter;
+        assert_eq!(
+            shielder_update_note(&mut session, &shielder_address, &call)?,
+            Err(ShielderError::Zkp(ZkpError::SubmitterMismatch))
+        );
+    }
+
+    Ok(())
+}
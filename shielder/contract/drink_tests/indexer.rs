@@ -0,0 +1,104 @@
+use anyhow::Result;
+use drink::session::Session;
+use mocked_zk::derivation::Seed;
+use rand::prelude::*;
+use shielder_indexer::{Indexer, IndexerError};
+
+use super::utils::{chain::*, ops::*, psp22::*, shielder::*};
+
+#[drink::test]
+fn indexer_mirrors_shielder(mut session: Session) -> Result<()> {
+    let mut rng = StdRng::seed_from_u64(25);
+
+    let alice = init_alice(&mut session)?;
+    session = session.with_actor(alice.clone());
+
+    let psp22_address = deploy_test_token(&mut session, 100)?;
+    let shielder_address = deploy_shielder(&mut session, &psp22_address)?;
+
+    psp22_approve(&mut session, &psp22_address, &shielder_address, 30)?;
+
+    let dir = tempfile::tempdir()?;
+    let store = dir.path().join("index.json");
+    let mut indexer = Indexer::new();
+    let mut users = vec![];
+    for _ in 0..3 {
+        let mut events = vec![];
+
+        // CREATE ACCOUNT
+        let user_shielded_data =
+            create_shielder_account(&mut session, &shielder_address, Seed::from_bytes(rng.gen()))?;
+        events.extend(get_shielder_note_events(&mut session));
+        let nullifier_old = user_shielded_data.nullifier();
+
+        // DEPOSIT
+        let user_shielded_data = shielder_update(
+            &mut session,
+            &shielder_address,
+            deposit_op(&psp22_address, &alice, 10),
+            user_shielded_data,
+        )?;
+        events.extend(get_shielder_note_events(&mut session));
+        users.push((user_shielded_data, events.last().unwrap().note_hash));
+
+        let block = current_block(&mut session);
+        let merkle_root = get_shielder_merkle_root(&mut session, &shielder_address)?;
+        indexer.process_block(block, &events, merkle_root).unwrap();
+        assert!(indexer.is_spent(nullifier_old));
+
+        // index survives restarts
+        indexer.save(&store)?;
+        indexer = Indexer::load(&store)?;
+        advance_blocks(&mut session, 1);
+    }
+
+    assert_eq!(indexer.leaves_number(), 6);
+    assert_eq!(indexer.state().nullifiers.len(), 3);
+    for leaf_id in 0..indexer.leaves_number() {
+        assert_eq!(
+            indexer.merkle_path(leaf_id),
+            Some(get_shielder_merkle_path(
+                &mut session,
+                &shielder_address,
+                leaf_id
+            )?)
+        );
+    }
+    for (user_shielded_data, note_hash) in users {
+        assert!(!indexer.is_spent(user_shielded_data.nullifier()));
+        assert_eq!(
+            indexer.leaf(user_shielded_data.note.leaf_id),
+            Some(note_hash)
+        );
+    }
+
+    Ok(())
+}
+
+#[drink::test]
+fn indexer_detects_missed_events(mut session: Session) -> Result<()> {
+    let mut rng = StdRng::seed_from_u64(26);
+
+    let alice = init_alice(&mut session)?;
+    session = session.with_actor(alice.clone());
+
+    let psp22_address = deploy_test_token(&mut session, 100)?;
+    let shielder_address = deploy_shielder(&mut session, &psp22_address)?;
+
+    let mut indexer = Indexer::new();
+    create_shielder_account(&mut session, &shielder_address, Seed::from_bytes(rng.gen()))?;
+    // events of the first account are missed
+    create_shielder_account(&mut session, &shielder_address, Seed::from_bytes(rng.gen()))?;
+    let events = get_shielder_note_events(&mut session);
+    let merkle_root = get_shielder_merkle_root(&mut session, &shielder_address)?;
+    let block = current_block(&mut session);
+    assert_eq!(
+        indexer.process_block(block, &events, merkle_root),
+        Err(IndexerError::UnexpectedLeafId {
+            expected: 0,
+            actual: 1
+        })
+    );
+
+    Ok(())
+}
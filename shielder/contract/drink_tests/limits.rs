@@ -0,0 +1,149 @@
+use anyhow::Result;
+use drink::session::Session;
+use mocked_zk::derivation::Seed;
+use rand::prelude::*;
+
+use super::utils::{chain::*, ops::*, psp22::*, shielder::*};
+use crate::{errors::ShielderError, types::TokenLimits};
+
+#[drink::test]
+fn limits_enforced(mut session: Session) -> Result<()> {
+    let mut rng = StdRng::seed_from_u64(18);
+
+    let alice = init_alice(&mut session)?;
+    let bob = init_bob(&mut session)?;
+
+    session = session.with_actor(alice.clone());
+
+    let psp22_address = deploy_test_token(&mut session, 100)?;
+    let shielder_address = deploy_shielder(&mut session, &psp22_address)?;
+
+    let limits = TokenLimits {
+        max_deposit: Some(10),
+        max_withdraw: Some(5),
+        max_withdraw_per_block: Some(6),
+    };
+    shielder_set_token_limits(&mut session, &shielder_address, &psp22_address, limits)?.unwrap();
+    assert_eq!(
+        get_shielder_token_limits(&mut session, &shielder_address, &psp22_address)?,
+        limits
+    );
+
+    // CREATE ACCOUNT
+    let mut user_shielded_data =
+        create_shielder_account(&mut session, &shielder_address, Seed::from_bytes(rng.gen()))?;
+
+    // APPROVE TRANSFER
+    psp22_approve(&mut session, &psp22_address, &shielder_address, 20)?;
+
+    // DEPOSIT over the limit
+    let call = prepare_shielder_update(
+        &mut session,
+        &shielder_address,
+        deposit_op(&psp22_address, &alice, 11),
+        user_shielded_data,
+        None,
+    )?;
+    assert_eq!(
+        shielder_update_note(&mut session, &shielder_address, &call)?,
+        Err(ShielderError::DepositLimitExceeded)
+    );
+
+    // DEPOSIT
+    user_shielded_data = shielder_update(
+        &mut session,
+        &shielder_address,
+        deposit_op(&psp22_address, &alice, 10),
+        user_shielded_data,
+    )?;
+
+    // SWITCH TO bob
+    session = session.with_actor(bob.clone());
+
+    // WITHDRAW over the limit
+    let call = prepare_shielder_update(
+        &mut session,
+        &shielder_address,
+        withdraw_op(&psp22_address, &bob, 6),
+        user_shielded_data,
+        None,
+    )?;
+    assert_eq!(
+        shielder_update_note(&mut session, &shielder_address, &call)?,
+        Err(ShielderError::WithdrawLimitExceeded)
+    );
+
+    // WITHDRAW
+    user_shielded_data = shielder_update(
+        &mut session,
+        &shielder_address,
+        withdraw_op(&psp22_address, &bob, 5),
+        user_shielded_data,
+    )?;
+    assert_eq!(
+        get_shielder_withdrawn_in_current_block(&mut session, &shielder_address, &psp22_address)?,
+        5
+    );
+
+    // WITHDRAW over the limit of the block
+    let call = prepare_shielder_update(
+        &mut session,
+        &shielder_address,
+        withdraw_op(&psp22_address, &bob, 2),
+        user_shielded_data,
+        None,
+    )?;
+    assert_eq!(
+        shielder_update_note(&mut session, &shielder_address, &call)?,
+        Err(ShielderError::BlockWithdrawLimitExceeded)
+    );
+
+    // WITHDRAW in the next block
+    advance_blocks(&mut session, 1);
+    assert_eq!(
+        get_shielder_withdrawn_in_current_block(&mut session, &shielder_address, &psp22_address)?,
+        0
+    );
+    shielder_update_note(&mut session, &shielder_address, &call)?.unwrap();
+    assert_eq!(
+        get_shielder_withdrawn_in_current_block(&mut session, &shielder_address, &psp22_address)?,
+        2
+    );
+
+    let bob_psp22_balance = get_psp22_balance(&mut session, &psp22_address, &bob)?;
+    assert_eq!(bob_psp22_balance, 7);
+    let shielder_psp22_balance =
+        get_psp22_balance(&mut session, &psp22_address, &shielder_address)?;
+    assert_eq!(shielder_psp22_balance, 3);
+
+    Ok(())
+}
+
+#[drink::test]
+fn limits_set_only_by_owner(mut session: Session) -> Result<()> {
+    let alice = init_alice(&mut session)?;
+    let bob = init_bob(&mut session)?;
+
+    session = session.with_actor(alice.clone());
+
+    let psp22_address = deploy_test_token(&mut session, 100)?;
+    let shielder_address = deploy_shielder(&mut session, &psp22_address)?;
+
+    // SWITCH TO bob
+    session = session.with_actor(bob.clone());
+
+    let limits = TokenLimits {
+        max_deposit: Some(0),
+        ..Default::default()
+    };
+    assert_eq!(
+        shielder_set_token_limits(&mut session, &shielder_address, &psp22_address, limits)?,
+        Err(ShielderError::NotOwner)
+    );
+    assert_eq!(
+        get_shielder_token_limits(&mut session, &shielder_address, &psp22_address)?,
+        TokenLimits::default()
+    );
+
+    Ok(())
+}
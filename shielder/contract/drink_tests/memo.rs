@@ -0,0 +1,234 @@
+use anyhow::Result;
+use drink::session::Session;
+use mocked_zk::{
+    derivation::Seed,
+    derive_nullifier,
+    ops::{OpPriv, Operation},
+    relations::ZkProof,
+    traits::Hashable,
+    Scalar,
+};
+use rand::prelude::*;
+use shielder_client::{
+    events::{scan_notes, ReceivedNote},
+    history::{account_history, AccountChange},
+    keys::SpendingKey,
+    memo::encrypt_memo,
+};
+
+use super::utils::{chain::*, ops::*, psp22::*, shielder::*};
+use crate::{contract::MAX_MEMO_LEN, errors::ShielderError};
+
+#[drink::test]
+fn recipient_spends_note_found_in_memo(mut session: Session) -> Result<()> {
+    let mut rng = StdRng::seed_from_u64(19);
+
+    let alice = init_alice(&mut session)?;
+    let bob = init_bob(&mut session)?;
+    let bob_key = SpendingKey::random(&mut rng);
+
+    session = session.with_actor(alice.clone());
+
+    let psp22_address = deploy_test_token(&mut session, 100)?;
+    let shielder_address = deploy_shielder(&mut session, &psp22_address)?;
+    psp22_transfer(&mut session, &psp22_address, &bob, 10)?;
+
+    // alice creates account for bob and tells him about it in memo
+    let domain = get_shielder_domain(&mut session, &shielder_address)?;
+    let alice_seed = Seed::from_bytes(rng.gen());
+    let (h_note_new, proof) = new_account_note(&psp22_address, alice_seed, domain);
+    let account = new_account(&psp22_address);
+    let note = alice_seed.note(0, account.hash());
+    let memo = encrypt_memo(&mut rng, &bob_key.address(), note, account);
+    let leaf_id =
+        shielder_add_note(&mut session, &shielder_address, h_note_new, proof, memo)?.unwrap();
+
+    // SWITCH TO bob
+    session = session.with_actor(bob.clone());
+
+    let events = get_shielder_note_events(&mut session);
+    let received = scan_notes(&bob_key, &events);
+    assert_eq!(
+        received,
+        vec![ReceivedNote {
+            leaf_id,
+            note,
+            account,
+        }]
+    );
+
+    // bob rebuilds knowledge of the note and deposits into it
+    // the note isn't derived from a wallet seed, so the call is built from its secrets
+    let ReceivedNote { note, account, .. } = received[0];
+    let bob_seed = Seed::from_bytes(rng.gen());
+    let upd_op = deposit_op(&psp22_address, &bob, 10);
+    let operation = Operation::combine(upd_op.op_pub, upd_op.op_priv).unwrap();
+    let merkle_root = get_shielder_merkle_root(&mut session, &shielder_address)?;
+    let merkle_path = get_shielder_merkle_path(&mut session, &shielder_address, leaf_id)?;
+    let (h_note_new, proof) = ZkProof::new(
+        note.id(),
+        note.trapdoor(),
+        note.nullifier_key(),
+        OpPriv {
+            user: 0_u128.into(),
+        },
+        account,
+        domain,
+    )
+    .update_account(
+        operation,
+        bob_seed.trapdoor(1),
+        bob_seed.nullifier_key(1),
+        merkle_path,
+        leaf_id,
+        None,
+    )
+    .unwrap();
+    let call = UpdateNoteCall {
+        op_pub: upd_op.op_pub,
+        h_note_new,
+        merkle_root,
+        nullifier_old: derive_nullifier(note.nullifier_key(), leaf_id),
+        submitter: None,
+        proof,
+        memo: vec![],
+    };
+    psp22_approve(&mut session, &psp22_address, &shielder_address, 10)?;
+    shielder_update_note(&mut session, &shielder_address, &call)?.unwrap();
+
+    let shielder_psp22_balance =
+        get_psp22_balance(&mut session, &psp22_address, &shielder_address)?;
+    assert_eq!(shielder_psp22_balance, 10);
+
+    Ok(())
+}
+
+#[drink::test]
+fn update_emits_memo(mut session: Session) -> Result<()> {
+    let mut rng = StdRng::seed_from_u64(20);
+
+    let alice = init_alice(&mut session)?;
+    let bob_key = SpendingKey::random(&mut rng);
+
+    session = session.with_actor(alice.clone());
+
+    let psp22_address = deploy_test_token(&mut session, 100)?;
+    let shielder_address = deploy_shielder(&mut session, &psp22_address)?;
+
+    // CREATE ACCOUNT
+    let seed = Seed::from_bytes(rng.gen());
+    let alice_key = SpendingKey::from_seed(&seed);
+    let user_shielded_data = create_shielder_account(&mut session, &shielder_address, seed)?;
+
+    // APPROVE TRANSFER
+    psp22_approve(&mut session, &psp22_address, &shielder_address, 10)?;
+
+    // DEPOSIT, the wallet keeps the new note in memo for itself
+    let upd_op = deposit_op(&psp22_address, &alice, 10);
+    let operation = Operation::combine(upd_op.op_pub, upd_op.op_priv).unwrap();
+    let account = new_account(&psp22_address).update(operation).unwrap();
+    let user_shielded_data =
+        shielder_update(&mut session, &shielder_address, upd_op, user_shielded_data)?;
+    assert_eq!(user_shielded_data.note.account, account);
+
+    let events = get_shielder_note_events(&mut session);
+    assert_eq!(
+        scan_notes(&alice_key, &events),
+        vec![ReceivedNote {
+            leaf_id: user_shielded_data.note.leaf_id,
+            note: seed.note(1, account.hash()),
+            account,
+        }]
+    );
+    assert_eq!(scan_notes(&bob_key, &events), vec![]);
+
+    Ok(())
+}
+
+#[drink::test]
+fn auditor_reconstructs_history(mut session: Session) -> Result<()> {
+    let mut rng = StdRng::seed_from_u64(22);
+
+    let alice = init_alice(&mut session)?;
+    let bob = init_bob(&mut session)?;
+
+    session = session.with_actor(alice.clone());
+
+    let psp22_address = deploy_test_token(&mut session, 100)?;
+    let shielder_address = deploy_shielder(&mut session, &psp22_address)?;
+    let token = Scalar::from_bytes(*psp22_address.as_ref());
+
+    // CREATE ACCOUNT
+    let seed = Seed::from_bytes(rng.gen());
+    let alice_key = SpendingKey::from_seed(&seed);
+    let mut user_shielded_data = create_shielder_account(&mut session, &shielder_address, seed)?;
+    let mut events = get_shielder_note_events(&mut session);
+
+    psp22_approve(&mut session, &psp22_address, &shielder_address, 10)?;
+
+    // DEPOSIT and WITHDRAW, the wallet attaches memo to every new note
+    for upd_op in [
+        deposit_op(&psp22_address, &alice, 10),
+        withdraw_op(&psp22_address, &bob, 4),
+    ] {
+        user_shielded_data =
+            shielder_update(&mut session, &shielder_address, upd_op, user_shielded_data)?;
+        events.extend(get_shielder_note_events(&mut session));
+    }
+
+    // auditor sees only the viewing key
+    let history = account_history(&alice_key.viewing_key(), &events);
+    assert_eq!(history.len(), 1);
+    let changes: Vec<_> = history[0]
+        .entries
+        .iter()
+        .map(|entry| (entry.leaf_id, entry.change))
+        .collect();
+    assert_eq!(
+        changes,
+        vec![
+            (0, AccountChange::Created),
+            (1, AccountChange::Deposit { token, amount: 10 }),
+            (2, AccountChange::Withdraw { token, amount: 4 }),
+        ]
+    );
+    assert_eq!(history[0].account(), user_shielded_data.note.account);
+
+    Ok(())
+}
+
+#[drink::test]
+fn too_long_memo_rejected(mut session: Session) -> Result<()> {
+    let mut rng = StdRng::seed_from_u64(21);
+
+    let alice = init_alice(&mut session)?;
+    session = session.with_actor(alice.clone());
+
+    let psp22_address = deploy_test_token(&mut session, 100)?;
+    let shielder_address = deploy_shielder(&mut session, &psp22_address)?;
+
+    let domain = get_shielder_domain(&mut session, &shielder_address)?;
+    let (h_note_new, proof) = new_account_note(&psp22_address, Seed::from_bytes(rng.gen()), domain);
+    assert_eq!(
+        shielder_add_note(
+            &mut session,
+            &shielder_address,
+            h_note_new,
+            proof,
+            vec![0; MAX_MEMO_LEN + 1],
+        )?,
+        Err(ShielderError::MemoTooLong)
+    );
+    assert_eq!(
+        shielder_add_note(
+            &mut session,
+            &shielder_address,
+            h_note_new,
+            proof,
+            vec![0; MAX_MEMO_LEN],
+        )?,
+        Ok(0)
+    );
+
+    Ok(())
+}
@@ -1,9 +1,26 @@
 use anyhow::Result;
 use drink::{session::Session, AccountId32};
+use mocked_zk::derivation::Seed;
 use rand::prelude::*;
 use utils::{chain::*, ops::*, psp22::*, shielder::*};
 
+mod cli;
+mod deadline;
+mod domain;
+mod fee_token;
+mod frontrunning;
+mod indexer;
+mod limits;
+mod memo;
+mod model;
+mod negative;
+mod pool;
+mod reentrancy;
+mod relayer;
+mod upgrade;
 mod utils;
+mod wallet;
+mod zkp_errors;
 
 #[drink::contract_bundle_provider]
 pub enum BundleProvider {}
@@ -21,12 +38,8 @@ fn deploy_single_deposit_single_withdraw(mut session: Session) -> Result<()> {
     let shielder_address = deploy_shielder(&mut session, &psp22_address)?;
 
     // CREATE ACCOUNT
-    let user_shielded_data = create_shielder_account(
-        &mut session,
-        &shielder_address,
-        &psp22_address,
-        rng.gen::<u128>().into(),
-    )?;
+    let user_shielded_data =
+        create_shielder_account(&mut session, &shielder_address, Seed::from_bytes(rng.gen()))?;
 
     // APPROVE TRANSFER
     psp22_approve(&mut session, &psp22_address, &shielder_address, 10)?;
@@ -37,7 +50,6 @@ fn deploy_single_deposit_single_withdraw(mut session: Session) -> Result<()> {
         &shielder_address,
         deposit_op(&psp22_address, &alice, 10),
         user_shielded_data,
-        rng.gen::<u128>().into(),
     )?;
 
     let alice_psp22_balance = get_psp22_balance(&mut session, &psp22_address, &alice)?;
@@ -55,7 +67,6 @@ fn deploy_single_deposit_single_withdraw(mut session: Session) -> Result<()> {
         &shielder_address,
         withdraw_op(&psp22_address, &bob, 1),
         user_shielded_data,
-        rng.gen::<u128>().into(),
     )?;
 
     let bob_psp22_balance = get_psp22_balance(&mut session, &psp22_address, &bob)?;
@@ -85,12 +96,8 @@ fn deploy_single_deposit_multiple_withdraw(mut session: Session) -> Result<()> {
     let shielder_address = deploy_shielder(&mut session, &psp22_address)?;
 
     // CREATE ACCOUNT
-    let mut user_shielded_data = create_shielder_account(
-        &mut session,
-        &shielder_address,
-        &psp22_address,
-        rng.gen::<u128>().into(),
-    )?;
+    let mut user_shielded_data =
+        create_shielder_account(&mut session, &shielder_address, Seed::from_bytes(rng.gen()))?;
 
     // APPROVE TRANSFER
     psp22_approve(&mut session, &psp22_address, &shielder_address, 50)?;
@@ -105,7 +112,6 @@ fn deploy_single_deposit_multiple_withdraw(mut session: Session) -> Result<()> {
         &shielder_address,
         deposit_op(&psp22_address, &alice, 50),
         user_shielded_data,
-        rng.gen::<u128>().into(),
     )?;
 
     for withdrawer_addr in withdrawers {
@@ -118,7 +124,6 @@ fn deploy_single_deposit_multiple_withdraw(mut session: Session) -> Result<()> {
             &shielder_address,
             withdraw_op(&psp22_address, &withdrawer_addr, 1),
             user_shielded_data,
-            rng.gen::<u128>().into(),
         )?;
         let psp22_balance = get_psp22_balance(&mut session, &psp22_address, &withdrawer_addr)?;
         assert_eq!(psp22_balance, 1);
@@ -167,8 +172,7 @@ fn deploy_multiple_deposit_multiple_withdraw(mut session: Session) -> Result<()>
         user_shielded_data.push(create_shielder_account(
             &mut session,
             &shielder_address,
-            &psp22_address,
-            rng.gen::<u128>().into(),
+            Seed::from_bytes(rng.gen()),
         )?);
 
         // APPROVE TRANSFER
@@ -180,7 +184,6 @@ fn deploy_multiple_deposit_multiple_withdraw(mut session: Session) -> Result<()>
             &shielder_address,
             deposit_op(&psp22_address, depositor_addr, 50),
             user_shielded_data[i],
-            rng.gen::<u128>().into(),
         )?;
     }
 
@@ -194,7 +197,6 @@ fn deploy_multiple_deposit_multiple_withdraw(mut session: Session) -> Result<()>
             &shielder_address,
             withdraw_op(&psp22_address, withdrawer_addr, 1),
             user_shielded_data[i],
-            rng.gen::<u128>().into(),
         )?;
         let psp22_balance = get_psp22_balance(&mut session, &psp22_address, withdrawer_addr)?;
         assert_eq!(psp22_balance, 1);
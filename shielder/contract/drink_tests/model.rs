@@ -0,0 +1,259 @@
+//! Model-based test: random sequences of actions are applied both to the contracts
+//! and to an in-memory model of PSP22 and shielded balances, which are compared after every step
+use anyhow::Result;
+use drink::{minimal::MinimalSandbox, session::Session, AccountId32};
+use mocked_zk::{derivation::Seed, errors::ZkpError};
+use proptest::{collection::vec, prelude::*};
+use rand::prelude::*;
+
+use super::utils::{chain::*, ops::*, psp22::*, shielder::*};
+use crate::{errors::ShielderError, traits::psp22::PSP22Error};
+
+const USERS: usize = 3;
+const INITIAL_BALANCE: u128 = 100;
+
+/// Performed by the current actor
+#[derive(Clone, Copy, Debug)]
+enum Action {
+    CreateAccount,
+    Approve(u128),
+    Deposit(u128),
+    Withdraw { recipient: usize, amount: u128 },
+    SwitchActor(usize),
+}
+
+fn action() -> impl Strategy<Value = Action> {
+    prop_oneof![
+        1 => Just(Action::CreateAccount),
+        2 => (0..60_u128).prop_map(Action::Approve),
+        3 => (0..60_u128).prop_map(Action::Deposit),
+        3 => (0..USERS, 0..60_u128)
+            .prop_map(|(recipient, amount)| Action::Withdraw { recipient, amount }),
+        2 => (0..USERS).prop_map(Action::SwitchActor),
+    ]
+}
+
+/// Expected state of the contracts
+#[derive(Debug, Default)]
+struct Model {
+    actor: usize,
+    balances: [u128; USERS],
+    /// allowances of the shielder
+    allowances: [u128; USERS],
+    /// `None` if the user has no shielder account
+    shielded: [Option<u128>; USERS],
+}
+
+struct Chain {
+    session: Session<MinimalSandbox>,
+    rng: StdRng,
+    users: [AccountId32; USERS],
+    psp22_address: AccountId32,
+    shielder_address: AccountId32,
+    accounts: [Option<ShielderUserEnv>; USERS],
+}
+
+impl Chain {
+    /// Deploys contracts and gives `INITIAL_BALANCE` tokens to every user
+    fn new() -> Result<(Self, Model)> {
+        let mut session = Session::<MinimalSandbox>::default();
+        let users: [AccountId32; USERS] =
+            core::array::from_fn(|i| AccountId32::new([2 + i as u8; 32]));
+        for user in &users {
+            init_acc_with_balance(&mut session, user)?;
+        }
+
+        session.set_actor(users[0].clone());
+        let psp22_address = deploy_test_token(&mut session, INITIAL_BALANCE * USERS as u128)?;
+        let shielder_address = deploy_shielder(&mut session, &psp22_address)?;
+        for user in &users[1..] {
+            psp22_transfer(&mut session, &psp22_address, user, INITIAL_BALANCE)?;
+        }
+
+        let chain = Self {
+            session,
+            rng: StdRng::seed_from_u64(46),
+            users,
+            psp22_address,
+            shielder_address,
+            accounts: [None; USERS],
+        };
+        let model = Model {
+            balances: [INITIAL_BALANCE; USERS],
+            ..Default::default()
+        };
+        Ok((chain, model))
+    }
+
+    fn apply(&mut self, model: &mut Model, action: Action) -> Result<()> {
+        let actor = model.actor;
+        let user = self.users[actor].clone();
+        match action {
+            Action::CreateAccount => {
+                if self.accounts[actor].is_some() {
+                    return Ok(());
+                }
+                self.accounts[actor] = Some(create_shielder_account(
+                    &mut self.session,
+                    &self.shielder_address,
+                    Seed::from_bytes(self.rng.gen()),
+                )?);
+                model.shielded[actor] = Some(0);
+            }
+            Action::Approve(amount) => {
+                psp22_approve(
+                    &mut self.session,
+                    &self.psp22_address,
+                    &self.shielder_address,
+                    amount,
+                )?;
+                model.allowances[actor] = amount;
+            }
+            Action::Deposit(amount) => {
+                let Some(user_shielded_data) = self.accounts[actor] else {
+                    return Ok(());
+                };
+                let upd_op = deposit_op(&self.psp22_address, &user, amount);
+                let expected_error = if model.allowances[actor] < amount {
+                    Some(PSP22Error::InsufficientAllowance)
+                } else if model.balances[actor] < amount {
+                    Some(PSP22Error::InsufficientBalance)
+                } else {
+                    None
+                };
+                if let Some(error) = expected_error {
+                    let call = prepare_shielder_update(
+                        &mut self.session,
+                        &self.shielder_address,
+                        upd_op,
+                        user_shielded_data,
+                        None,
+                    )?;
+                    assert_eq!(
+                        shielder_update_note(&mut self.session, &self.shielder_address, &call)?,
+                        Err(ShielderError::PSP22(error))
+                    );
+                    return Ok(());
+                }
+                self.accounts[actor] = Some(shielder_update(
+                    &mut self.session,
+                    &self.shielder_address,
+                    upd_op,
+                    user_shielded_data,
+                )?);
+                model.allowances[actor] -= amount;
+                model.balances[actor] -= amount;
+                model.shielded[actor] = model.shielded[actor].map(|balance| balance + amount);
+            }
+            Action::Withdraw { recipient, amount } => {
+                let (Some(user_shielded_data), Some(shielded)) =
+                    (self.accounts[actor], model.shielded[actor])
+                else {
+                    return Ok(());
+                };
+                let upd_op = withdraw_op(&self.psp22_address, &self.users[recipient], amount);
+                if shielded < amount {
+                    // proof can't be built, so nothing is submitted
+                    assert_eq!(
+                        shielder_local_update(&user_shielded_data, upd_op),
+                        Err(ZkpError::BalanceUnderflow)
+                    );
+                    return Ok(());
+                }
+                self.accounts[actor] = Some(shielder_update(
+                    &mut self.session,
+                    &self.shielder_address,
+                    upd_op,
+                    user_shielded_data,
+                )?);
+                model.shielded[actor] = Some(shielded - amount);
+                model.balances[recipient] += amount;
+            }
+            Action::SwitchActor(actor) => {
+                self.session.set_actor(self.users[actor].clone());
+                model.actor = actor;
+            }
+        }
+        Ok(())
+    }
+
+    /// Compares the contracts with the model
+    fn check(&mut self, model: &Model) -> Result<()> {
+        for (i, user) in self.users.iter().enumerate() {
+            assert_eq!(
+                get_psp22_balance(&mut self.session, &self.psp22_address, user)?,
+                model.balances[i],
+                "balance of user {i}"
+            );
+            assert_eq!(
+                get_psp22_allowance(
+                    &mut self.session,
+                    &self.psp22_address,
+                    user,
+                    &self.shielder_address
+                )?,
+                model.allowances[i],
+                "allowance of user {i}"
+            );
+            assert_eq!(self.accounts[i].is_some(), model.shielded[i].is_some());
+            if let (Some(user_shielded_data), Some(shielded)) =
+                (self.accounts[i], model.shielded[i])
+            {
+                // the account holds exactly the shielded balance
+                assert_eq!(
+                    shielder_local_update(
+                        &user_shielded_data,
+                        withdraw_op(&self.psp22_address, user, shielded)
+                    ),
+                    Ok(()),
+                    "shielded balance of user {i}"
+                );
+                assert_eq!(
+                    shielder_local_update(
+                        &user_shielded_data,
+                        withdraw_op(&self.psp22_address, user, shielded + 1)
+                    ),
+                    Err(ZkpError::BalanceUnderflow),
+                    "shielded balance of user {i}"
+                );
+            }
+        }
+
+        let total_shielded = model.shielded.iter().flatten().sum::<u128>();
+        assert_eq!(
+            get_psp22_balance(
+                &mut self.session,
+                &self.psp22_address,
+                &self.shielder_address
+            )?,
+            total_shielded
+        );
+        let pool_state = get_shielder_pool_state(
+            &mut self.session,
+            &self.shielder_address,
+            &self.psp22_address,
+        )?;
+        assert_eq!(pool_state.expected_balance(), Some(total_shielded));
+        Ok(())
+    }
+}
+
+fn run(actions: &[Action]) -> Result<()> {
+    let (mut chain, mut model) = Chain::new()?;
+    chain.check(&model)?;
+    for action in actions {
+        chain.apply(&mut model, *action)?;
+        chain.check(&model)?;
+    }
+    Ok(())
+}
+
+proptest! {
+    // each case deploys contracts and submits every action
+    #![proptest_config(ProptestConfig::with_cases(8))]
+
+    #[test]
+    fn contracts_follow_model(actions in vec(action(), 1..24)) {
+        run(&actions).unwrap();
+    }
+}
@@ -0,0 +1,337 @@
+use anyhow::Result;
+use drink::{minimal::MinimalSandbox, session::Session, AccountId32};
+use mocked_zk::{derivation::Seed, errors::ZkpError, ops::OpPub, Scalar, MERKLE_TREE_DEPTH};
+use rand::prelude::*;
+
+use super::utils::{chain::*, ops::*, psp22::*, shielder::*};
+use crate::{errors::ShielderError, traits::psp22::PSP22Error};
+
+struct Env {
+    alice: AccountId32,
+    bob: AccountId32,
+    psp22_address: AccountId32,
+    shielder_address: AccountId32,
+}
+
+impl Env {
+    fn snapshot(&self, session: &mut Session<MinimalSandbox>) -> Result<ShielderSnapshot> {
+        get_shielder_snapshot(
+            session,
+            &self.shielder_address,
+            &self.psp22_address,
+            &[&self.alice, &self.bob, &self.shielder_address],
+        )
+    }
+}
+
+/// Deploys contracts and deposits 10 tokens of alice
+fn setup(session: &mut Session<MinimalSandbox>, seed: u64) -> Result<(Env, ShielderUserEnv)> {
+    let mut rng = StdRng::seed_from_u64(seed);
+
+    let alice = init_alice(session)?;
+    let bob = init_bob(session)?;
+
+    session.set_actor(alice.clone());
+    let psp22_address = deploy_test_token(session, 100)?;
+    let shielder_address = deploy_shielder(session, &psp22_address)?;
+
+    // CREATE ACCOUNT
+    let user_shielded_data =
+        create_shielder_account(session, &shielder_address, Seed::from_bytes(rng.gen()))?;
+
+    // APPROVE TRANSFER
+    psp22_approve(session, &psp22_address, &shielder_address, 10)?;
+
+    // DEPOSIT
+    let user_shielded_data = shielder_update(
+        session,
+        &shielder_address,
+        deposit_op(&psp22_address, &alice, 10),
+        user_shielded_data,
+    )?;
+
+    let env = Env {
+        alice,
+        bob,
+        psp22_address,
+        shielder_address,
+    };
+    Ok((env, user_shielded_data))
+}
+
+#[drink::test]
+fn double_spend_rejected(mut session: Session) -> Result<()> {
+    let (env, user_shielded_data) = setup(&mut session, 30)?;
+
+    let first = prepare_shielder_update(
+        &mut session,
+        &env.shielder_address,
+        withdraw_op(&env.psp22_address, &env.bob, 1),
+        user_shielded_data,
+        None,
+    )?;
+    let second = prepare_shielder_update(
+        &mut session,
+        &env.shielder_address,
+        withdraw_op(&env.psp22_address, &env.bob, 2),
+        user_shielded_data,
+        None,
+    )?;
+    shielder_update_note(&mut session, &env.shielder_address, &first)?.unwrap();
+
+    let snapshot = env.snapshot(&mut session)?;
+    for call in [&first, &second] {
+        assert_eq!(
+            shielder_update_note(&mut session, &env.shielder_address, call)?,
+            Err(ShielderError::NullifierIsInSet)
+        );
+    }
+    assert_eq!(env.snapshot(&mut session)?, snapshot);
+
+    Ok(())
+}
+
+#[drink::test]
+fn unknown_merkle_root_rejected(mut session: Session) -> Result<()> {
+    let (env, user_shielded_data) = setup(&mut session, 31)?;
+
+    let valid_call = prepare_shielder_update(
+        &mut session,
+        &env.shielder_address,
+        withdraw_op(&env.psp22_address, &env.bob, 1),
+        user_shielded_data,
+        None,
+    )?;
+    let mut call = valid_call.clone();
+    call.merkle_root = Scalar::from_bytes([0x1; 32]);
+
+    let snapshot = env.snapshot(&mut session)?;
+    assert_eq!(
+        shielder_update_note(&mut session, &env.shielder_address, &call)?,
+        Err(ShielderError::MerkleTreeVerificationFail)
+    );
+    assert_eq!(env.snapshot(&mut session)?, snapshot);
+    // nullifier of the old note is still unspent
+    shielder_update_note(&mut session, &env.shielder_address, &valid_call)?.unwrap();
+
+    Ok(())
+}
+
+#[drink::test]
+fn tampered_op_pub_rejected(mut session: Session) -> Result<()> {
+    let (env, user_shielded_data) = setup(&mut session, 32)?;
+
+    let call = prepare_shielder_update(
+        &mut session,
+        &env.shielder_address,
+        withdraw_op(&env.psp22_address, &env.bob, 5),
+        user_shielded_data,
+        None,
+    )?;
+    let tamper = |tamper_op_pub: fn(&mut OpPub, &Env)| {
+        let mut call = call.clone();
+        tamper_op_pub(&mut call.op_pub, &env);
+        call
+    };
+    let cases = [
+        // withdraw more than the proof allows
+        (
+            tamper(|op_pub, _| {
+                if let OpPub::Withdraw { amount, .. } = op_pub {
+                    *amount = 6;
+                }
+            }),
+            ZkpError::NoteHashMismatch,
+        ),
+        (
+            tamper(|op_pub, env| {
+                if let OpPub::Withdraw { token, .. } = op_pub {
+                    *token = Scalar::from_bytes(*env.shielder_address.as_ref());
+                }
+            }),
+            ZkpError::UnknownToken,
+        ),
+        // redirect the withdrawal
+        (
+            tamper(|op_pub, env| {
+                if let OpPub::Withdraw { user, .. } = op_pub {
+                    *user = Scalar::from_bytes(*env.alice.as_ref());
+                }
+            }),
+            ZkpError::OperationCombineError,
+        ),
+    ];
+
+    let snapshot = env.snapshot(&mut session)?;
+    for (call, error) in cases {
+        assert_eq!(
+            shielder_update_note(&mut session, &env.shielder_address, &call)?,
+            Err(ShielderError::Zkp(error))
+        );
+    }
+    assert_eq!(env.snapshot(&mut session)?, snapshot);
+    // nullifier of the old note is still unspent
+    shielder_update_note(&mut session, &env.shielder_address, &call)?.unwrap();
+
+    Ok(())
+}
+
+#[drink::test]
+fn insufficient_allowance_rejected(mut session: Session) -> Result<()> {
+    let (env, user_shielded_data) = setup(&mut session, 33)?;
+
+    // allowance was used by the first deposit
+    psp22_approve(&mut session, &env.psp22_address, &env.shielder_address, 5)?;
+    let call = prepare_shielder_update(
+        &mut session,
+        &env.shielder_address,
+        deposit_op(&env.psp22_address, &env.alice, 10),
+        user_shielded_data,
+        None,
+    )?;
+
+    let snapshot = env.snapshot(&mut session)?;
+    assert_eq!(
+        shielder_update_note(&mut session, &env.shielder_address, &call)?,
+        Err(ShielderError::PSP22(PSP22Error::InsufficientAllowance))
+    );
+    assert_eq!(env.snapshot(&mut session)?, snapshot);
+    assert_eq!(
+        get_psp22_allowance(
+            &mut session,
+            &env.psp22_address,
+            &env.alice,
+            &env.shielder_address
+        )?,
+        5
+    );
+    // nullifier of the old note is still unspent
+    psp22_approve(&mut session, &env.psp22_address, &env.shielder_address, 10)?;
+    shielder_update_note(&mut session, &env.shielder_address, &call)?.unwrap();
+
+    Ok(())
+}
+
+#[drink::test]
+fn unsupported_token_rejected(mut session: Session) -> Result<()> {
+    let (env, user_shielded_data) = setup(&mut session, 34)?;
+    let other_token = deploy_test_token(&mut session, 100)?;
+    psp22_approve(&mut session, &other_token, &env.shielder_address, 10)?;
+
+    // account holds only the token supported by shielder
+    assert_eq!(
+        shielder_local_update(
+            &user_shielded_data,
+            deposit_op(&other_token, &env.alice, 10)
+        ),
+        Err(ZkpError::UnknownToken)
+    );
+
+    let valid_call = prepare_shielder_update(
+        &mut session,
+        &env.shielder_address,
+        deposit_op(&env.psp22_address, &env.alice, 10),
+        user_shielded_data,
+        None,
+    )?;
+    let mut call = valid_call.clone();
+    if let OpPub::Deposit { token, .. } = &mut call.op_pub {
+        *token = Scalar::from_bytes(*other_token.as_ref());
+    }
+
+    let snapshot = env.snapshot(&mut session)?;
+    let accounts = [&env.alice, &env.shielder_address];
+    let other_balances =
+        get_shielder_snapshot(&mut session, &env.shielder_address, &other_token, &accounts)?;
+    assert_eq!(
+        shielder_update_note(&mut session, &env.shielder_address, &call)?,
+        Err(ShielderError::Zkp(ZkpError::UnknownToken))
+    );
+    assert_eq!(env.snapshot(&mut session)?, snapshot);
+    assert_eq!(
+        get_shielder_snapshot(&mut session, &env.shielder_address, &other_token, &accounts)?,
+        other_balances
+    );
+    // nullifier of the old note is still unspent
+    psp22_approve(&mut session, &env.psp22_address, &env.shielder_address, 10)?;
+    shielder_update_note(&mut session, &env.shielder_address, &valid_call)?.unwrap();
+
+    Ok(())
+}
+
+#[drink::test]
+fn withdraw_beyond_balance_rejected(mut session: Session) -> Result<()> {
+    let (env, user_shielded_data) = setup(&mut session, 35)?;
+
+    // proof can't be built for more than the balance
+    assert_eq!(
+        shielder_local_update(
+            &user_shielded_data,
+            withdraw_op(&env.psp22_address, &env.bob, 11)
+        ),
+        Err(ZkpError::BalanceUnderflow)
+    );
+
+    // nor the amount of valid proof can be increased
+    let valid_call = prepare_shielder_update(
+        &mut session,
+        &env.shielder_address,
+        withdraw_op(&env.psp22_address, &env.bob, 10),
+        user_shielded_data,
+        None,
+    )?;
+    let mut call = valid_call.clone();
+    if let OpPub::Withdraw { amount, .. } = &mut call.op_pub {
+        *amount = 11;
+    }
+
+    let snapshot = env.snapshot(&mut session)?;
+    assert_eq!(
+        shielder_update_note(&mut session, &env.shielder_address, &call)?,
+        Err(ShielderError::Zkp(ZkpError::BalanceUnderflow))
+    );
+    assert_eq!(env.snapshot(&mut session)?, snapshot);
+    // nullifier of the old note is still unspent
+    shielder_update_note(&mut session, &env.shielder_address, &valid_call)?.unwrap();
+
+    Ok(())
+}
+
+#[drink::test]
+fn full_tree_rejected(mut session: Session) -> Result<()> {
+    let mut rng = StdRng::seed_from_u64(36);
+    let (env, user_shielded_data) = setup(&mut session, 37)?;
+
+    // setup added 2 leaves, the same empty note fills the rest of the tree
+    let domain = get_shielder_domain(&mut session, &env.shielder_address)?;
+    let (h_note_new, proof) =
+        new_account_note(&env.psp22_address, Seed::from_bytes(rng.gen()), domain);
+    let add_note = |session: &mut Session<MinimalSandbox>| {
+        shielder_add_note(session, &env.shielder_address, h_note_new, proof, vec![])
+    };
+    for _ in 2..(1 << MERKLE_TREE_DEPTH) - 1 {
+        add_note(&mut session)?.unwrap();
+    }
+    // valid update prepared while the tree had a free leaf
+    let call = prepare_shielder_update(
+        &mut session,
+        &env.shielder_address,
+        withdraw_op(&env.psp22_address, &env.bob, 1),
+        user_shielded_data,
+        None,
+    )?;
+    add_note(&mut session)?.unwrap();
+
+    let snapshot = env.snapshot(&mut session)?;
+    assert_eq!(
+        add_note(&mut session)?,
+        Err(ShielderError::MerkleTreeLimitExceeded)
+    );
+    assert_eq!(
+        shielder_update_note(&mut session, &env.shielder_address, &call)?,
+        Err(ShielderError::MerkleTreeLimitExceeded)
+    );
+    assert_eq!(env.snapshot(&mut session)?, snapshot);
+
+    Ok(())
+}
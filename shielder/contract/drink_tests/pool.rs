@@ -0,0 +1,136 @@
+use anyhow::Result;
+use drink::{minimal::MinimalSandbox, session::Session, AccountId32};
+use mocked_zk::derivation::Seed;
+use rand::prelude::*;
+
+use super::utils::{chain::*, ops::*, psp22::*, shielder::*};
+use crate::types::PoolState;
+
+fn assert_pool(
+    session: &mut Session<MinimalSandbox>,
+    shielder_address: &AccountId32,
+    psp22_address: &AccountId32,
+    expected: PoolState,
+) -> Result<()> {
+    assert_eq!(
+        get_shielder_pool_state(session, shielder_address, psp22_address)?,
+        expected
+    );
+    assert_eq!(
+        shielder_check_solvency(session, shielder_address, psp22_address)?,
+        Ok(true)
+    );
+    let shielder_psp22_balance = get_psp22_balance(session, psp22_address, shielder_address)?;
+    assert_eq!(Some(shielder_psp22_balance), expected.expected_balance());
+    Ok(())
+}
+
+#[drink::test]
+fn pool_state_follows_random_operations(mut session: Session) -> Result<()> {
+    let mut rng = StdRng::seed_from_u64(16);
+
+    let alice = init_alice(&mut session)?;
+    session = session.with_actor(alice.clone());
+
+    let mut users = vec![];
+    for i in 3..7 {
+        let acc = AccountId32::new([i as u8; 32]);
+        init_acc_with_balance(&mut session, &acc)?;
+        users.push(acc);
+    }
+
+    let psp22_address = deploy_test_token(&mut session, 400)?;
+    let shielder_address = deploy_shielder(&mut session, &psp22_address)?;
+
+    for user in &users {
+        psp22_transfer(&mut session, &psp22_address, user, 100)?;
+    }
+
+    let mut user_shielded_data = vec![];
+    let mut shielded_balances = vec![];
+    for user in &users {
+        session = session.with_actor(user.clone());
+        user_shielded_data.push(create_shielder_account(
+            &mut session,
+            &shielder_address,
+            Seed::from_bytes(rng.gen()),
+        )?);
+        psp22_approve(&mut session, &psp22_address, &shielder_address, 1000)?;
+        shielded_balances.push(0_u128);
+    }
+    let mut public_balances = vec![100_u128; users.len()];
+
+    let mut expected = PoolState::default();
+    assert_pool(&mut session, &shielder_address, &psp22_address, expected)?;
+
+    for _ in 0..20 {
+        let i = rng.gen_range(0..users.len());
+        session = session.with_actor(users[i].clone());
+        let deposit = rng.gen_bool(0.5) || shielded_balances[i] == 0;
+        let upd_op = if deposit {
+            let amount = rng.gen_range(0..=public_balances[i].min(10));
+            public_balances[i] -= amount;
+            shielded_balances[i] += amount;
+            expected.total_deposited += amount;
+            deposit_op(&psp22_address, &users[i], amount)
+        } else {
+            let amount = rng.gen_range(1..=shielded_balances[i]);
+            shielded_balances[i] -= amount;
+            public_balances[i] += amount;
+            expected.total_withdrawn += amount;
+            withdraw_op(&psp22_address, &users[i], amount)
+        };
+        user_shielded_data[i] = shielder_update(
+            &mut session,
+            &shielder_address,
+            upd_op,
+            user_shielded_data[i],
+        )?;
+        assert_pool(&mut session, &shielder_address, &psp22_address, expected)?;
+    }
+
+    Ok(())
+}
+
+#[drink::test]
+fn solvent_after_direct_transfer(mut session: Session) -> Result<()> {
+    let mut rng = StdRng::seed_from_u64(17);
+
+    let alice = init_alice(&mut session)?;
+    session = session.with_actor(alice.clone());
+
+    let psp22_address = deploy_test_token(&mut session, 100)?;
+    let shielder_address = deploy_shielder(&mut session, &psp22_address)?;
+
+    // CREATE ACCOUNT
+    let user_shielded_data =
+        create_shielder_account(&mut session, &shielder_address, Seed::from_bytes(rng.gen()))?;
+
+    // APPROVE TRANSFER
+    psp22_approve(&mut session, &psp22_address, &shielder_address, 10)?;
+
+    // DEPOSIT
+    shielder_update(
+        &mut session,
+        &shielder_address,
+        deposit_op(&psp22_address, &alice, 10),
+        user_shielded_data,
+    )?;
+
+    // tokens sent outside of shielder are not accounted
+    psp22_transfer(&mut session, &psp22_address, &shielder_address, 5)?;
+
+    assert_eq!(
+        get_shielder_pool_state(&mut session, &shielder_address, &psp22_address)?,
+        PoolState {
+            total_deposited: 10,
+            total_withdrawn: 0,
+        }
+    );
+    assert_eq!(
+        shielder_check_solvency(&mut session, &shielder_address, &psp22_address)?,
+        Ok(true)
+    );
+
+    Ok(())
+}
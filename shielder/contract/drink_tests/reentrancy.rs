@@ -0,0 +1,126 @@
+use anyhow::Result;
+use drink::session::Session;
+use mocked_zk::derivation::Seed;
+use rand::prelude::*;
+
+use super::utils::{chain::*, ops::*, psp22::*, shielder::*};
+use crate::{contract::DEFAULT_PSP22_GAS_LIMIT, errors::ShielderError, traits::psp22::PSP22Error};
+
+/// PSP22 calls of the shielder allow re-entry, so the nested `add_note` with a valid proof
+/// is rejected only by the lock of the shielder
+#[drink::test]
+fn reentrant_token_rejected(mut session: Session) -> Result<()> {
+    let mut rng = StdRng::seed_from_u64(12);
+
+    let alice = init_alice(&mut session)?;
+    session = session.with_actor(alice.clone());
+
+    let psp22_address = deploy_malicious_token(&mut session, 100)?;
+    let shielder_address = deploy_shielder(&mut session, &psp22_address)?;
+
+    // CREATE ACCOUNT
+    let user_shielded_data =
+        create_shielder_account(&mut session, &shielder_address, Seed::from_bytes(rng.gen()))?;
+
+    // APPROVE TRANSFER
+    psp22_approve(&mut session, &psp22_address, &shielder_address, 10)?;
+
+    // token calls `add_note` of other account from `transfer_from`
+    let domain = get_shielder_domain(&mut session, &shielder_address)?;
+    let (h_note_new, proof) = new_account_note(&psp22_address, Seed::from_bytes(rng.gen()), domain);
+    let reentry = AddNoteCall {
+        h_note_new,
+        proof,
+        memo: vec![],
+    };
+    malicious_token_set_reentry(&mut session, &psp22_address, &reentry)?;
+
+    // DEPOSIT
+    let call = prepare_shielder_update(
+        &mut session,
+        &shielder_address,
+        deposit_op(&psp22_address, &alice, 10),
+        user_shielded_data,
+        None,
+    )?;
+    let snapshot = get_shielder_snapshot(
+        &mut session,
+        &shielder_address,
+        &psp22_address,
+        &[&alice, &shielder_address],
+    )?;
+    assert_eq!(
+        shielder_update_note(&mut session, &shielder_address, &call)?,
+        Err(ShielderError::PSP22(PSP22Error::Custom(format!(
+            "{:?}",
+            ShielderError::ReentrantCall
+        ))))
+    );
+    assert_eq!(
+        get_shielder_snapshot(
+            &mut session,
+            &shielder_address,
+            &psp22_address,
+            &[&alice, &shielder_address],
+        )?,
+        snapshot
+    );
+
+    // the nested call is valid on its own
+    shielder_add_note(&mut session, &shielder_address, h_note_new, proof, vec![])?.unwrap();
+
+    Ok(())
+}
+
+#[drink::test]
+fn psp22_gas_limit_applied(mut session: Session) -> Result<()> {
+    let mut rng = StdRng::seed_from_u64(13);
+
+    let alice = init_alice(&mut session)?;
+    let bob = init_bob(&mut session)?;
+
+    session = session.with_actor(alice.clone());
+
+    let psp22_address = deploy_test_token(&mut session, 100)?;
+    let shielder_address = deploy_shielder(&mut session, &psp22_address)?;
+
+    // CREATE ACCOUNT
+    let user_shielded_data =
+        create_shielder_account(&mut session, &shielder_address, Seed::from_bytes(rng.gen()))?;
+
+    // APPROVE TRANSFER
+    psp22_approve(&mut session, &psp22_address, &shielder_address, 10)?;
+
+    let call = prepare_shielder_update(
+        &mut session,
+        &shielder_address,
+        deposit_op(&psp22_address, &alice, 10),
+        user_shielded_data,
+        None,
+    )?;
+
+    // DEPOSIT with too low gas limit
+    shielder_set_psp22_gas_limit(&mut session, &shielder_address, 1)?.unwrap();
+    assert_eq!(
+        shielder_update_note(&mut session, &shielder_address, &call)?,
+        Err(ShielderError::PSP22CallFail)
+    );
+
+    // DEPOSIT with default gas limit
+    shielder_set_psp22_gas_limit(&mut session, &shielder_address, DEFAULT_PSP22_GAS_LIMIT)?
+        .unwrap();
+    shielder_update_note(&mut session, &shielder_address, &call)?.unwrap();
+
+    let shielder_psp22_balance =
+        get_psp22_balance(&mut session, &psp22_address, &shielder_address)?;
+    assert_eq!(shielder_psp22_balance, 10);
+
+    // SWITCH TO bob
+    session = session.with_actor(bob.clone());
+    assert_eq!(
+        shielder_set_psp22_gas_limit(&mut session, &shielder_address, 0)?,
+        Err(ShielderError::NotOwner)
+    );
+
+    Ok(())
+}
@@ -0,0 +1,119 @@
+use std::thread;
+
+use anyhow::Result;
+use drink::{session::Session, AccountId32};
+use mocked_zk::derivation::Seed;
+use rand::prelude::*;
+use shielder_relayer::{client::relay, http::handle_request, Relayer, RelayerError};
+use shielder_wallet::drink::{DrinkTransport, DrinkTransportError};
+use tiny_http::Server;
+
+use super::utils::{chain::*, ops::*, psp22::*, shielder::*};
+use crate::errors::ShielderError;
+
+#[drink::test]
+fn relayer_withdraws_to_fresh_account(mut session: Session) -> Result<()> {
+    let mut rng = StdRng::seed_from_u64(27);
+
+    let alice = init_alice(&mut session)?;
+    let relayer_address = AccountId32::new([0x20; 32]);
+    init_acc_with_balance(&mut session, &relayer_address)?;
+    // fresh account without funds
+    let eve = AccountId32::new([0x21; 32]);
+
+    session = session.with_actor(alice.clone());
+
+    let psp22_address = deploy_test_token(&mut session, 100)?;
+    let shielder_address = deploy_shielder(&mut session, &psp22_address)?;
+
+    // CREATE ACCOUNT
+    let user_shielded_data =
+        create_shielder_account(&mut session, &shielder_address, Seed::from_bytes(rng.gen()))?;
+
+    // APPROVE TRANSFER
+    psp22_approve(&mut session, &psp22_address, &shielder_address, 10)?;
+
+    // DEPOSIT
+    let user_shielded_data = shielder_update(
+        &mut session,
+        &shielder_address,
+        deposit_op(&psp22_address, &alice, 10),
+        user_shielded_data,
+    )?;
+
+    // WITHDRAW to eve, bound to the relayer
+    let call = prepare_shielder_update(
+        &mut session,
+        &shielder_address,
+        withdraw_op(&psp22_address, &eve, 4),
+        user_shielded_data,
+        Some(&relayer_address),
+    )?;
+
+    session = session.with_actor(relayer_address.clone());
+    let server = Server::http("127.0.0.1:0").unwrap();
+    let addr = server.server_addr().to_ip().unwrap();
+    let client = thread::spawn(move || relay(addr, &call));
+    let mut relayer = Relayer::new(DrinkTransport::<ShielderError>::new(
+        &mut session,
+        shielder_address.clone(),
+    ));
+    handle_request(&mut relayer, server.recv()?)?;
+    assert_eq!(client.join().unwrap()?, Ok(2));
+
+    let eve_psp22_balance = get_psp22_balance(&mut session, &psp22_address, &eve)?;
+    assert_eq!(eve_psp22_balance, 4);
+    let shielder_psp22_balance =
+        get_psp22_balance(&mut session, &psp22_address, &shielder_address)?;
+    assert_eq!(shielder_psp22_balance, 6);
+
+    Ok(())
+}
+
+#[drink::test]
+fn relayer_rejects_failing_call(mut session: Session) -> Result<()> {
+    let mut rng = StdRng::seed_from_u64(28);
+
+    let alice = init_alice(&mut session)?;
+    let bob = init_bob(&mut session)?;
+    session = session.with_actor(alice.clone());
+
+    let psp22_address = deploy_test_token(&mut session, 100)?;
+    let shielder_address = deploy_shielder(&mut session, &psp22_address)?;
+
+    let user_shielded_data =
+        create_shielder_account(&mut session, &shielder_address, Seed::from_bytes(rng.gen()))?;
+    psp22_approve(&mut session, &psp22_address, &shielder_address, 10)?;
+    let user_shielded_data = shielder_update(
+        &mut session,
+        &shielder_address,
+        deposit_op(&psp22_address, &alice, 10),
+        user_shielded_data,
+    )?;
+
+    // the note is spent before the relayer gets the call
+    let call = prepare_shielder_update(
+        &mut session,
+        &shielder_address,
+        withdraw_op(&psp22_address, &bob, 4),
+        user_shielded_data,
+        None,
+    )?;
+    shielder_update_note(&mut session, &shielder_address, &call)?.unwrap();
+
+    let mut relayer = Relayer::new(DrinkTransport::<ShielderError>::new(
+        &mut session,
+        shielder_address.clone(),
+    ));
+    assert!(matches!(
+        relayer.relay(&call),
+        Err(RelayerError::SimulationFail(DrinkTransportError::Contract(
+            ShielderError::NullifierIsInSet
+        )))
+    ));
+
+    let bob_psp22_balance = get_psp22_balance(&mut session, &psp22_address, &bob)?;
+    assert_eq!(bob_psp22_balance, 4);
+
+    Ok(())
+}
@@ -0,0 +1,177 @@
+use anyhow::Result;
+use drink::session::Session;
+use mocked_zk::{derivation::Seed, domain_separator, Scalar};
+use rand::prelude::*;
+
+use super::utils::{chain::*, ops::*, psp22::*, shielder::*};
+use crate::{
+    contract::{DEFAULT_PSP22_GAS_LIMIT, STORAGE_VERSION},
+    errors::ShielderError,
+};
+
+#[drink::test]
+fn upgrade_from_v1_layout(mut session: Session) -> Result<()> {
+    let mut rng = StdRng::seed_from_u64(6);
+
+    let alice = init_alice(&mut session)?;
+    session = session.with_actor(alice.clone());
+
+    let psp22_address = deploy_test_token(&mut session, 100)?;
+    let shielder_address = deploy_shielder_v1(&mut session, &psp22_address)?;
+    upgrade_shielder_v1(&mut session, &shielder_address)?;
+
+    // CREATE ACCOUNT before migration
+    let domain = get_shielder_domain(&mut session, &shielder_address)?;
+    let (h_note_new, proof) = new_account_note(&psp22_address, Seed::from_bytes(rng.gen()), domain);
+    assert_eq!(
+        shielder_add_note(&mut session, &shielder_address, h_note_new, proof, vec![])?,
+        Err(ShielderError::StorageVersionMismatch)
+    );
+
+    // MIGRATE, fields added by version 2 are initialized
+    assert_eq!(
+        shielder_migrate(&mut session, &shielder_address)?,
+        Ok(STORAGE_VERSION)
+    );
+    assert_eq!(
+        get_shielder_domain(&mut session, &shielder_address)?,
+        domain_separator(
+            Scalar::from_bytes(*shielder_address.as_ref()),
+            Scalar::default()
+        )
+    );
+    assert_eq!(
+        get_shielder_psp22_gas_limit(&mut session, &shielder_address)?,
+        DEFAULT_PSP22_GAS_LIMIT
+    );
+
+    // CREATE ACCOUNT, DEPOSIT and WITHDRAW on migrated storage
+    let user_shielded_data =
+        create_shielder_account(&mut session, &shielder_address, Seed::from_bytes(rng.gen()))?;
+    psp22_approve(&mut session, &psp22_address, &shielder_address, 10)?;
+    let user_shielded_data = shielder_update(
+        &mut session,
+        &shielder_address,
+        deposit_op(&psp22_address, &alice, 10),
+        user_shielded_data,
+    )?;
+    shielder_update(
+        &mut session,
+        &shielder_address,
+        withdraw_op(&psp22_address, &alice, 3),
+        user_shielded_data,
+    )?;
+
+    let shielder_psp22_balance =
+        get_psp22_balance(&mut session, &psp22_address, &shielder_address)?;
+    assert_eq!(shielder_psp22_balance, 7);
+
+    Ok(())
+}
+
+#[drink::test]
+fn upgrade_keeps_notes_spendable(mut session: Session) -> Result<()> {
+    let mut rng = StdRng::seed_from_u64(4);
+
+    let alice = init_alice(&mut session)?;
+    let bob = init_bob(&mut session)?;
+
+    session = session.with_actor(alice.clone());
+
+    let psp22_address = deploy_test_token(&mut session, 100)?;
+    let shielder_address = deploy_shielder(&mut session, &psp22_address)?;
+
+    // CREATE ACCOUNT
+    let user_shielded_data =
+        create_shielder_account(&mut session, &shielder_address, Seed::from_bytes(rng.gen()))?;
+
+    // APPROVE TRANSFER
+    psp22_approve(&mut session, &psp22_address, &shielder_address, 10)?;
+
+    // DEPOSIT
+    let user_shielded_data = shielder_update(
+        &mut session,
+        &shielder_address,
+        deposit_op(&psp22_address, &alice, 10),
+        user_shielded_data,
+    )?;
+
+    // UPGRADE
+    let code_hash = upload_shielder_next(&mut session)?;
+    shielder_set_code(&mut session, &shielder_address, &code_hash)?.unwrap();
+    assert_eq!(
+        shielder_migrate(&mut session, &shielder_address)?,
+        Ok(STORAGE_VERSION + 1)
+    );
+
+    // SWITCH TO bob
+    session = session.with_actor(bob.clone());
+
+    // WITHDRAW with note created before upgrade
+    let _ = shielder_update(
+        &mut session,
+        &shielder_address,
+        withdraw_op(&psp22_address, &bob, 1),
+        user_shielded_data,
+    )?;
+
+    let bob_psp22_balance = get_psp22_balance(&mut session, &psp22_address, &bob)?;
+    assert_eq!(bob_psp22_balance, 1);
+    let shielder_psp22_balance =
+        get_psp22_balance(&mut session, &psp22_address, &shielder_address)?;
+    assert_eq!(shielder_psp22_balance, 9);
+
+    Ok(())
+}
+
+#[drink::test]
+fn upgrade_requires_migration(mut session: Session) -> Result<()> {
+    let mut rng = StdRng::seed_from_u64(5);
+
+    let alice = init_alice(&mut session)?;
+    session = session.with_actor(alice.clone());
+
+    let psp22_address = deploy_test_token(&mut session, 100)?;
+    let shielder_address = deploy_shielder(&mut session, &psp22_address)?;
+
+    let code_hash = upload_shielder_next(&mut session)?;
+    shielder_set_code(&mut session, &shielder_address, &code_hash)?.unwrap();
+
+    // CREATE ACCOUNT before migration
+    let res = create_shielder_account(&mut session, &shielder_address, Seed::from_bytes(rng.gen()));
+    assert!(res.is_err());
+
+    assert_eq!(
+        shielder_migrate(&mut session, &shielder_address)?,
+        Ok(STORAGE_VERSION + 1)
+    );
+    create_shielder_account(&mut session, &shielder_address, Seed::from_bytes(rng.gen()))?;
+
+    Ok(())
+}
+
+#[drink::test]
+fn upgrade_only_by_owner(mut session: Session) -> Result<()> {
+    let alice = init_alice(&mut session)?;
+    let bob = init_bob(&mut session)?;
+
+    session = session.with_actor(alice.clone());
+
+    let psp22_address = deploy_test_token(&mut session, 100)?;
+    let shielder_address = deploy_shielder(&mut session, &psp22_address)?;
+    let code_hash = upload_shielder_next(&mut session)?;
+
+    // SWITCH TO bob
+    session = session.with_actor(bob.clone());
+
+    assert_eq!(
+        shielder_set_code(&mut session, &shielder_address, &code_hash)?,
+        Err(ShielderError::NotOwner)
+    );
+    assert_eq!(
+        shielder_migrate(&mut session, &shielder_address)?,
+        Err(ShielderError::NotOwner)
+    );
+
+    Ok(())
+}
@@ -1,6 +1,9 @@
 use anyhow::Result;
 use drink::{
-    minimal::MinimalSandbox, sandbox_api::balance_api::BalanceAPI, session::Session, AccountId32,
+    minimal::MinimalSandbox,
+    sandbox_api::{balance_api::BalanceAPI, system_api::SystemAPI},
+    session::Session,
+    AccountId32,
 };
 
 use super::ACCOUNT_INITIAL_AMOUNT;
@@ -27,3 +30,11 @@ pub fn init_bob(session: &mut Session<MinimalSandbox>) -> Result<AccountId32> {
     init_acc_with_balance(session, &res)?;
     Ok(res)
 }
+
+pub fn current_block(session: &mut Session<MinimalSandbox>) -> u32 {
+    session.sandbox().block_number()
+}
+
+pub fn advance_blocks(session: &mut Session<MinimalSandbox>, blocks: u32) {
+    session.sandbox().build_blocks(blocks);
+}
@@ -4,3 +4,4 @@ pub mod psp22;
 pub mod shielder;
 
 pub const ACCOUNT_INITIAL_AMOUNT: u128 = 1_000_000_000_000_000;
+pub const CHAIN_TAG: u128 = 0;
@@ -9,6 +9,18 @@ pub struct UpdateOperation {
     pub op_priv: OpPriv,
 }
 
+impl UpdateOperation {
+    /// Restricts operation to blocks up to `block_number`
+    pub fn valid_until(mut self, block_number: u32) -> Self {
+        match &mut self.op_pub {
+            OpPub::Deposit { valid_until, .. } | OpPub::Withdraw { valid_until, .. } => {
+                *valid_until = Some(block_number)
+            }
+        }
+        self
+    }
+}
+
 pub fn deposit_op(
     psp22_address: &AccountId32,
     user: &AccountId32,
@@ -19,6 +31,7 @@ pub fn deposit_op(
             amount,
             token: Scalar::from_bytes(*((*psp22_address).as_ref())),
             user: Scalar::from_bytes(*((*user).as_ref())),
+            valid_until: None,
         },
         op_priv: OpPriv {
             user: Scalar::from_bytes(*((*user).as_ref())),
@@ -36,6 +49,7 @@ pub fn withdraw_op(
             amount,
             token: Scalar::from_bytes(*((*psp22_address).as_ref())),
             user: Scalar::from_bytes(*((*user).as_ref())),
+            valid_until: None,
         },
         op_priv: OpPriv {
             user: Scalar::from_bytes(*((*user).as_ref())),
@@ -4,6 +4,7 @@ use drink::{
     session::{bundle::ContractBundle, Session, NO_ENDOWMENT, NO_SALT},
     AccountId32,
 };
+use shielder_wallet::calls::AddNoteCall;
 
 pub fn deploy_test_token(
     session: &mut Session<MinimalSandbox>,
@@ -26,6 +27,54 @@ pub fn deploy_test_token(
     Ok(res)
 }
 
+/// Deploys token which re-enters the shielder from `transfer_from`, see `malicious_token_set_reentry`
+pub fn deploy_malicious_token(
+    session: &mut Session<MinimalSandbox>,
+    supply: u128,
+) -> Result<AccountId32> {
+    let malicious_psp22_bundle = ContractBundle::load(std::path::Path::new(
+        "../test_contracts/malicious_psp22/target/ink/malicious_psp22.contract",
+    ))?;
+    let res = session.deploy_bundle(
+        malicious_psp22_bundle,
+        "new",
+        &[format!("{}", supply)],
+        NO_SALT,
+        NO_ENDOWMENT,
+    )?;
+    Ok(res)
+}
+
+/// Makes token deployed by `deploy_malicious_token` re-enter the shielder with `call`
+pub fn malicious_token_set_reentry(
+    session: &mut Session<MinimalSandbox>,
+    token: &AccountId32,
+    call: &AddNoteCall,
+) -> Result<()> {
+    let _: () =
+        session.call_with_address(token.clone(), "set_reentry", &call.args(), NO_ENDOWMENT)??;
+    Ok(())
+}
+
+/// Deploys token which burns `fee` from every transfer
+pub fn deploy_fee_token(
+    session: &mut Session<MinimalSandbox>,
+    supply: u128,
+    fee: u128,
+) -> Result<AccountId32> {
+    let fee_psp22_bundle = ContractBundle::load(std::path::Path::new(
+        "../test_contracts/fee_psp22/target/ink/fee_psp22.contract",
+    ))?;
+    let res = session.deploy_bundle(
+        fee_psp22_bundle,
+        "new",
+        &[format!("{}", supply), format!("{}", fee)],
+        NO_SALT,
+        NO_ENDOWMENT,
+    )?;
+    Ok(res)
+}
+
 pub fn get_psp22_balance(
     session: &mut Session<MinimalSandbox>,
     token: &AccountId32,
@@ -1,31 +1,89 @@
-use anyhow::Result;
+use std::fmt::Debug;
+
+use anyhow::{anyhow, Result};
 use drink::{
     minimal::MinimalSandbox,
-    session::{Session, NO_ARGS, NO_ENDOWMENT, NO_SALT},
+    session::{
+        bundle::ContractBundle, error::SessionError, Session, NO_ARGS, NO_ENDOWMENT, NO_SALT,
+    },
     AccountId32,
 };
+use ink::{scale::Decode, MessageResult};
 use mocked_zk::{
     account::Account,
-    note::Note,
+    derivation::Seed,
+    errors::ZkpError,
     ops::{OpPriv, Operation},
     relations::ZkProof,
     traits::Hashable,
     Scalar, MERKLE_TREE_DEPTH, TOKENS_NUMBER,
 };
+use shielder_client::events::NoteAdded;
+pub use shielder_wallet::calls::{AddNoteCall, UpdateNoteCall};
+use shielder_wallet::{
+    drink::DrinkTransport,
+    state::{NoteState, WalletState},
+    Wallet, WalletError,
+};
 
-use super::ops::UpdateOperation;
-use crate::{drink_tests::BundleProvider, errors::ShielderError};
+use super::{ops::UpdateOperation, psp22::get_psp22_balance, CHAIN_TAG};
+use crate::{
+    drink_tests::BundleProvider,
+    errors::ShielderError,
+    types::{PoolState, TokenLimits},
+};
 
+/// Shielded account of a test user: seed of its wallet and the current note
+/// Copies of it build calls spending the same note more than once
 #[derive(Clone, Copy, Debug)]
 pub struct ShielderUserEnv {
-    pub proof: ZkProof,
-    pub nullifier: Scalar,
-    pub tree_leaf_id: u32,
+    pub seed: Seed,
+    pub note: NoteState,
+}
+
+impl ShielderUserEnv {
+    fn from_wallet(wallet: &Wallet) -> Result<Self> {
+        Ok(Self {
+            seed: wallet.state().seed,
+            note: *wallet.note().ok_or(anyhow!("account not created"))?,
+        })
+    }
+
+    /// Wallet holding only the current note
+    pub fn wallet(&self) -> Wallet {
+        let mut state = WalletState::new(self.seed);
+        state.notes.push(self.note);
+        Wallet::from_state(state)
+    }
+
+    /// Nullifier of the current note, bound to its position in the tree
+    pub fn nullifier(&self) -> Scalar {
+        self.wallet().nullifier().expect("wallet holds the note")
+    }
+}
+
+fn transport<'a>(
+    session: &'a mut Session<MinimalSandbox>,
+    shielder_address: &AccountId32,
+) -> DrinkTransport<'a, ShielderError> {
+    DrinkTransport::new(session, shielder_address.clone())
+}
+
+fn wallet_error<E: Debug>(err: WalletError<E>) -> anyhow::Error {
+    anyhow!("{err:?}")
 }
 
 pub fn deploy_shielder(
     session: &mut Session<MinimalSandbox>,
     token: &AccountId32,
+) -> Result<AccountId32> {
+    deploy_shielder_with_chain_tag(session, token, CHAIN_TAG.into())
+}
+
+pub fn deploy_shielder_with_chain_tag(
+    session: &mut Session<MinimalSandbox>,
+    token: &AccountId32,
+    chain_tag: Scalar,
 ) -> Result<AccountId32> {
     let shielder_bundle = BundleProvider::ShielderContract.bundle()?;
     let mut tokens: [Scalar; TOKENS_NUMBER] = [0_u128.into(); TOKENS_NUMBER];
@@ -33,102 +91,399 @@ pub fn deploy_shielder(
     let res = session.deploy_bundle(
         shielder_bundle,
         "new",
-        &[format!("{:?}", tokens)],
+        &[format!("{:?}", tokens), format!("{:?}", chain_tag)],
         NO_SALT,
         NO_ENDOWMENT,
     )?;
     Ok(res)
 }
 
-pub fn create_shielder_account(
+/// Calls shielder message, decoding `ShielderError` also from reverted calls
+pub fn shielder_call<T: Decode>(
     session: &mut Session<MinimalSandbox>,
     shielder_address: &AccountId32,
+    message: &str,
+    args: &[String],
+) -> Result<Result<T, ShielderError>> {
+    match session.call_with_address(shielder_address.clone(), message, args, NO_ENDOWMENT) {
+        Ok(res) => Ok(res?),
+        Err(SessionError::CallReverted(data)) => Ok(
+            MessageResult::<Result<T, ShielderError>>::decode(&mut data.as_slice())??,
+        ),
+        Err(err) => Err(err.into()),
+    }
+}
+
+/// Uploads shielder code with bumped storage version
+/// Returns hex-encoded code hash
+pub fn upload_shielder_next(session: &mut Session<MinimalSandbox>) -> Result<String> {
+    let shielder_next_bundle = ContractBundle::load(std::path::Path::new(
+        "target/next/ink/shielder_contract.contract",
+    ))?;
+    let code_hash = session.upload_bundle(shielder_next_bundle)?;
+    Ok(format!("{:?}", code_hash))
+}
+
+/// Deploys contract holding shielder storage of version 1, see `shielder_v1` test contract
+pub fn deploy_shielder_v1(
+    session: &mut Session<MinimalSandbox>,
     token: &AccountId32,
-    nullifier: Scalar,
-) -> Result<ShielderUserEnv> {
+) -> Result<AccountId32> {
+    let shielder_v1_bundle = ContractBundle::load(std::path::Path::new(
+        "../test_contracts/shielder_v1/target/ink/shielder_v1.contract",
+    ))?;
     let mut tokens: [Scalar; TOKENS_NUMBER] = [0_u128.into(); TOKENS_NUMBER];
     tokens[0] = Scalar::from_bytes(*((*token).as_ref()));
+    let res = session.deploy_bundle(
+        shielder_v1_bundle,
+        "new",
+        &[format!("{:?}", tokens)],
+        NO_SALT,
+        NO_ENDOWMENT,
+    )?;
+    Ok(res)
+}
 
-    let acc = Account::new(tokens);
-
-    let id = 0_u128.into();
-    let trapdoor = 0_u128.into();
-    let op_priv = OpPriv {
-        user: 0_u128.into(),
-    };
-
-    let proof = ZkProof::new(id, trapdoor, nullifier, op_priv, acc);
-
-    let h_note_new = Note::new(id, trapdoor, nullifier, acc.hash()).hash();
-
-    let leaf_id_res: Result<u32, ShielderError> = session.call_with_address(
+/// Upgrades contract deployed by `deploy_shielder_v1` to the current shielder code
+/// Messages of the upgraded contract are encoded with the current shielder metadata
+pub fn upgrade_shielder_v1(
+    session: &mut Session<MinimalSandbox>,
+    shielder_address: &AccountId32,
+) -> Result<()> {
+    let shielder_bundle = BundleProvider::ShielderContract.bundle()?;
+    let transcoder = shielder_bundle.transcoder.clone();
+    let code_hash = session.upload_bundle(shielder_bundle)?;
+    let _: () = session.call_with_address(
         shielder_address.clone(),
-        "add_note",
-        &[format!("{:?}", h_note_new), format!("{:?}", proof)],
+        "set_code",
+        &[format!("{:?}", code_hash)],
         NO_ENDOWMENT,
     )??;
+    session.set_transcoder(shielder_address.clone(), &transcoder);
+    Ok(())
+}
 
-    Ok(ShielderUserEnv {
-        proof,
-        nullifier,
-        tree_leaf_id: leaf_id_res.unwrap(),
-    })
+pub fn shielder_set_code(
+    session: &mut Session<MinimalSandbox>,
+    shielder_address: &AccountId32,
+    code_hash: &str,
+) -> Result<Result<(), ShielderError>> {
+    shielder_call(
+        session,
+        shielder_address,
+        "set_code",
+        &[code_hash.to_string()],
+    )
 }
 
-pub fn shielder_update(
+pub fn shielder_migrate(
     session: &mut Session<MinimalSandbox>,
     shielder_address: &AccountId32,
-    upd_op: UpdateOperation,
-    user_shielded_data: ShielderUserEnv,
-    nullifier: Scalar,
-) -> Result<ShielderUserEnv> {
+) -> Result<Result<u16, ShielderError>> {
+    shielder_call(session, shielder_address, "migrate", &[])
+}
+
+pub fn get_shielder_merkle_root(
+    session: &mut Session<MinimalSandbox>,
+    shielder_address: &AccountId32,
+) -> Result<Scalar> {
     let merkle_root_res: Result<Scalar, ShielderError> = session.call_with_address(
         shielder_address.clone(),
         "notes_merkle_root",
         NO_ARGS,
         NO_ENDOWMENT,
     )??;
-    let merkle_root = merkle_root_res.unwrap();
-    let merkle_proof_res: Result<[Scalar; MERKLE_TREE_DEPTH], ShielderError> = session
+    Ok(merkle_root_res.unwrap())
+}
+
+pub fn get_shielder_merkle_path(
+    session: &mut Session<MinimalSandbox>,
+    shielder_address: &AccountId32,
+    leaf_id: u32,
+) -> Result<[Scalar; MERKLE_TREE_DEPTH]> {
+    let merkle_path_res: Result<[Scalar; MERKLE_TREE_DEPTH], ShielderError> = session
         .call_with_address(
             shielder_address.clone(),
             "notes_merkle_path",
-            &[format!("{:?}", user_shielded_data.tree_leaf_id)],
+            &[format!("{:?}", leaf_id)],
             NO_ENDOWMENT,
         )??;
-    let merkle_proof = merkle_proof_res.unwrap();
-    let trapdoor_new = 1_u128.into();
-
-    let op_pub = upd_op.op_pub;
-    let op_priv = upd_op.op_priv;
-    let operation = Operation::combine(op_pub, op_priv).unwrap();
-    let (note_hash, new_proof) = user_shielded_data
-        .proof
-        .update_account(
-            operation,
-            trapdoor_new,
-            nullifier,
-            merkle_proof,
-            user_shielded_data.tree_leaf_id,
-        )
-        .unwrap();
+    Ok(merkle_path_res.unwrap())
+}
 
-    let new_leaf_id_res: Result<u32, ShielderError> = session.call_with_address(
+/// Observable state of shielder and `token` balances of `accounts`
+/// Rejected calls should leave it unchanged. Nullifiers can't be queried, tests show that
+/// the old note is still unspent by submitting the valid call afterwards.
+#[derive(Debug, PartialEq)]
+pub struct ShielderSnapshot {
+    pub merkle_root: Option<Scalar>,
+    pub pool_state: PoolState,
+    pub balances: Vec<u128>,
+}
+
+pub fn get_shielder_snapshot(
+    session: &mut Session<MinimalSandbox>,
+    shielder_address: &AccountId32,
+    token: &AccountId32,
+    accounts: &[&AccountId32],
+) -> Result<ShielderSnapshot> {
+    let merkle_root: Result<Scalar, ShielderError> =
+        shielder_call(session, shielder_address, "notes_merkle_root", &[])?;
+    let pool_state = get_shielder_pool_state(session, shielder_address, token)?;
+    let balances = accounts
+        .iter()
+        .map(|account| get_psp22_balance(session, token, account))
+        .collect::<Result<_>>()?;
+    Ok(ShielderSnapshot {
+        merkle_root: merkle_root.ok(),
+        pool_state,
+        balances,
+    })
+}
+
+pub fn get_shielder_pool_state(
+    session: &mut Session<MinimalSandbox>,
+    shielder_address: &AccountId32,
+    token: &AccountId32,
+) -> Result<PoolState> {
+    let res = session.call_with_address(
         shielder_address.clone(),
-        "update_note",
+        "pool_state",
+        &[format!("{:?}", Scalar::from_bytes(*token.as_ref()))],
+        NO_ENDOWMENT,
+    )??;
+    Ok(res)
+}
+
+pub fn shielder_check_solvency(
+    session: &mut Session<MinimalSandbox>,
+    shielder_address: &AccountId32,
+    token: &AccountId32,
+) -> Result<Result<bool, ShielderError>> {
+    shielder_call(
+        session,
+        shielder_address,
+        "check_solvency",
+        &[format!("{:?}", Scalar::from_bytes(*token.as_ref()))],
+    )
+}
+
+pub fn shielder_set_token_limits(
+    session: &mut Session<MinimalSandbox>,
+    shielder_address: &AccountId32,
+    token: &AccountId32,
+    limits: TokenLimits,
+) -> Result<Result<(), ShielderError>> {
+    shielder_call(
+        session,
+        shielder_address,
+        "set_token_limits",
         &[
-            format!("{:?}", op_pub),
-            format!("{:?}", note_hash),
-            format!("{:?}", merkle_root),
-            format!("{:?}", user_shielded_data.nullifier),
-            format!("{:?}", new_proof),
+            format!("{:?}", Scalar::from_bytes(*token.as_ref())),
+            format!("{:?}", limits),
         ],
+    )
+}
+
+pub fn get_shielder_token_limits(
+    session: &mut Session<MinimalSandbox>,
+    shielder_address: &AccountId32,
+    token: &AccountId32,
+) -> Result<TokenLimits> {
+    let res = session.call_with_address(
+        shielder_address.clone(),
+        "token_limits",
+        &[format!("{:?}", Scalar::from_bytes(*token.as_ref()))],
         NO_ENDOWMENT,
     )??;
+    Ok(res)
+}
 
-    Ok(ShielderUserEnv {
-        proof: new_proof,
-        nullifier,
-        tree_leaf_id: new_leaf_id_res.unwrap(),
-    })
+pub fn get_shielder_withdrawn_in_current_block(
+    session: &mut Session<MinimalSandbox>,
+    shielder_address: &AccountId32,
+    token: &AccountId32,
+) -> Result<u128> {
+    let res = session.call_with_address(
+        shielder_address.clone(),
+        "withdrawn_in_current_block",
+        &[format!("{:?}", Scalar::from_bytes(*token.as_ref()))],
+        NO_ENDOWMENT,
+    )??;
+    Ok(res)
+}
+
+pub fn shielder_set_psp22_gas_limit(
+    session: &mut Session<MinimalSandbox>,
+    shielder_address: &AccountId32,
+    gas_limit: u64,
+) -> Result<Result<(), ShielderError>> {
+    shielder_call(
+        session,
+        shielder_address,
+        "set_psp22_gas_limit",
+        &[format!("{}", gas_limit)],
+    )
+}
+
+pub fn get_shielder_domain(
+    session: &mut Session<MinimalSandbox>,
+    shielder_address: &AccountId32,
+) -> Result<Scalar> {
+    let res = session.call_with_address(
+        shielder_address.clone(),
+        "domain_separator",
+        NO_ARGS,
+        NO_ENDOWMENT,
+    )??;
+    Ok(res)
+}
+
+pub fn get_shielder_psp22_gas_limit(
+    session: &mut Session<MinimalSandbox>,
+    shielder_address: &AccountId32,
+) -> Result<u64> {
+    let res = session.call_with_address(
+        shielder_address.clone(),
+        "psp22_gas_limit",
+        NO_ARGS,
+        NO_ENDOWMENT,
+    )??;
+    Ok(res)
+}
+
+/// Empty account with `token` as the only supported token
+pub fn new_account(token: &AccountId32) -> Account {
+    let mut tokens: [Scalar; TOKENS_NUMBER] = [0_u128.into(); TOKENS_NUMBER];
+    tokens[0] = Scalar::from_bytes(*((*token).as_ref()));
+    Account::new(tokens)
+}
+
+/// Creates note of empty account and proof of its creation
+pub fn new_account_note(token: &AccountId32, seed: Seed, domain: Scalar) -> (Scalar, ZkProof) {
+    let acc = new_account(token);
+    let note = seed.note(0, acc.hash());
+
+    let op_priv = OpPriv {
+        user: 0_u128.into(),
+    };
+    let proof = ZkProof::new(
+        note.id(),
+        note.trapdoor(),
+        note.nullifier_key(),
+        op_priv,
+        acc,
+        domain,
+    );
+
+    (note.hash(), proof)
+}
+
+pub fn shielder_add_note(
+    session: &mut Session<MinimalSandbox>,
+    shielder_address: &AccountId32,
+    h_note_new: Scalar,
+    proof: ZkProof,
+    memo: Vec<u8>,
+) -> Result<Result<u32, ShielderError>> {
+    let call = AddNoteCall {
+        h_note_new,
+        proof,
+        memo,
+    };
+    shielder_call(session, shielder_address, "add_note", &call.args())
+}
+
+/// `NoteAdded` events emitted by the last call
+pub fn get_shielder_note_events(session: &mut Session<MinimalSandbox>) -> Vec<NoteAdded> {
+    session
+        .record()
+        .last_event_batch()
+        .contract_events()
+        .into_iter()
+        .filter_map(NoteAdded::decode_event)
+        .collect()
+}
+
+pub fn create_shielder_account(
+    session: &mut Session<MinimalSandbox>,
+    shielder_address: &AccountId32,
+    seed: Seed,
+) -> Result<ShielderUserEnv> {
+    let mut wallet = Wallet::new(seed);
+    wallet
+        .create_account(&mut transport(session, shielder_address))
+        .map_err(wallet_error)?;
+    ShielderUserEnv::from_wallet(&wallet)
+}
+
+/// Builds `update_note` call applying `upd_op` to the account
+/// If `submitter` is set, only that account can submit the call
+pub fn prepare_shielder_update(
+    session: &mut Session<MinimalSandbox>,
+    shielder_address: &AccountId32,
+    upd_op: UpdateOperation,
+    user_shielded_data: ShielderUserEnv,
+    submitter: Option<&AccountId32>,
+) -> Result<UpdateNoteCall> {
+    let submitter = submitter.map(|submitter| Scalar::from_bytes(*submitter.as_ref()));
+    user_shielded_data
+        .wallet()
+        .prepare_update(
+            &mut transport(session, shielder_address),
+            upd_op.op_pub,
+            upd_op.op_priv,
+            submitter,
+        )
+        .map_err(wallet_error)
+}
+
+/// Applies `upd_op` to the account locally, as the wallet does before building the proof
+pub fn shielder_local_update(
+    user_shielded_data: &ShielderUserEnv,
+    upd_op: UpdateOperation,
+) -> Result<(), ZkpError> {
+    let operation = Operation::combine(upd_op.op_pub, upd_op.op_priv)?;
+    user_shielded_data.note.account.update(operation)?;
+    Ok(())
+}
+
+pub fn shielder_update_note(
+    session: &mut Session<MinimalSandbox>,
+    shielder_address: &AccountId32,
+    call: &UpdateNoteCall,
+) -> Result<Result<u32, ShielderError>> {
+    shielder_call(session, shielder_address, "update_note", &call.args())
+}
+
+/// Submits `call` with `proof` given in place of the wire encoding of `call.proof`
+pub fn shielder_update_note_with_proof(
+    session: &mut Session<MinimalSandbox>,
+    shielder_address: &AccountId32,
+    call: &UpdateNoteCall,
+    proof: &[u8],
+) -> Result<Result<u32, ShielderError>> {
+    shielder_call(
+        session,
+        shielder_address,
+        "update_note",
+        &call.args_with_proof(proof),
+    )
+}
+
+pub fn shielder_update(
+    session: &mut Session<MinimalSandbox>,
+    shielder_address: &AccountId32,
+    upd_op: UpdateOperation,
+    user_shielded_data: ShielderUserEnv,
+) -> Result<ShielderUserEnv> {
+    let mut wallet = user_shielded_data.wallet();
+    wallet
+        .update(
+            &mut transport(session, shielder_address),
+            upd_op.op_pub,
+            upd_op.op_priv,
+        )
+        .map_err(wallet_error)?;
+    ShielderUserEnv::from_wallet(&wallet)
 }
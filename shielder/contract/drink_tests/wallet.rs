@@ -0,0 +1,115 @@
+use anyhow::Result;
+use drink::session::Session;
+use mocked_zk::{derivation::Seed, Scalar};
+use rand::prelude::*;
+use shielder_wallet::{
+    drink::{DrinkTransport, DrinkTransportError},
+    Wallet, WalletError,
+};
+
+use super::utils::{chain::*, ops::*, psp22::*, shielder::*};
+use crate::errors::ShielderError;
+
+type Transport<'a> = DrinkTransport<'a, ShielderError>;
+
+#[drink::test]
+fn wallet_deposit_withdraw(mut session: Session) -> Result<()> {
+    let mut rng = StdRng::seed_from_u64(23);
+
+    let alice = init_alice(&mut session)?;
+    let bob = init_bob(&mut session)?;
+
+    session = session.with_actor(alice.clone());
+
+    let psp22_address = deploy_test_token(&mut session, 100)?;
+    let shielder_address = deploy_shielder(&mut session, &psp22_address)?;
+    let token = Scalar::from_bytes(*psp22_address.as_ref());
+
+    // CREATE ACCOUNT
+    let mut wallet = Wallet::new(Seed::from_bytes(rng.gen()));
+    wallet
+        .create_account(&mut Transport::new(&mut session, shielder_address.clone()))
+        .unwrap();
+
+    // APPROVE TRANSFER
+    psp22_approve(&mut session, &psp22_address, &shielder_address, 10)?;
+
+    // DEPOSIT
+    let op = deposit_op(&psp22_address, &alice, 10);
+    wallet
+        .update(
+            &mut Transport::new(&mut session, shielder_address.clone()),
+            op.op_pub,
+            op.op_priv,
+        )
+        .unwrap();
+    assert_eq!(wallet.balance(token), 10);
+
+    // SWITCH TO bob
+    session = session.with_actor(bob.clone());
+
+    // WITHDRAW
+    let op = withdraw_op(&psp22_address, &bob, 3);
+    wallet
+        .update(
+            &mut Transport::new(&mut session, shielder_address.clone()),
+            op.op_pub,
+            op.op_priv,
+        )
+        .unwrap();
+    assert_eq!(wallet.balance(token), 7);
+
+    let bob_psp22_balance = get_psp22_balance(&mut session, &psp22_address, &bob)?;
+    assert_eq!(bob_psp22_balance, 3);
+    let shielder_psp22_balance =
+        get_psp22_balance(&mut session, &psp22_address, &shielder_address)?;
+    assert_eq!(shielder_psp22_balance, 7);
+
+    Ok(())
+}
+
+#[drink::test]
+fn wallet_keeps_note_of_rejected_call(mut session: Session) -> Result<()> {
+    let mut rng = StdRng::seed_from_u64(24);
+
+    let alice = init_alice(&mut session)?;
+    session = session.with_actor(alice.clone());
+
+    let psp22_address = deploy_test_token(&mut session, 100)?;
+    let shielder_address = deploy_shielder(&mut session, &psp22_address)?;
+    let token = Scalar::from_bytes(*psp22_address.as_ref());
+
+    let mut wallet = Wallet::new(Seed::from_bytes(rng.gen()));
+    wallet
+        .create_account(&mut Transport::new(&mut session, shielder_address.clone()))
+        .unwrap();
+
+    // DEPOSIT without allowance
+    let op = deposit_op(&psp22_address, &alice, 10);
+    let res = wallet.update(
+        &mut Transport::new(&mut session, shielder_address.clone()),
+        op.op_pub,
+        op.op_priv,
+    );
+    assert!(matches!(
+        res,
+        Err(WalletError::Transport(DrinkTransportError::Contract(
+            ShielderError::PSP22(_)
+        )))
+    ));
+    assert_eq!(wallet.balance(token), 0);
+    assert_eq!(wallet.state().notes.len(), 1);
+
+    // the old note is still spendable
+    psp22_approve(&mut session, &psp22_address, &shielder_address, 10)?;
+    wallet
+        .update(
+            &mut Transport::new(&mut session, shielder_address.clone()),
+            op.op_pub,
+            op.op_priv,
+        )
+        .unwrap();
+    assert_eq!(wallet.balance(token), 10);
+
+    Ok(())
+}
@@ -0,0 +1,185 @@
+use anyhow::Result;
+use drink::session::Session;
+use mocked_zk::{
+    derivation::Seed,
+    errors::ZkpError,
+    ops::OpPub,
+    wire::{to_scale, Proof, WIRE_VERSION},
+    Scalar, MERKLE_TREE_DEPTH, TOKENS_NUMBER,
+};
+use rand::prelude::*;
+
+use super::utils::{chain::*, ops::*, psp22::*, shielder::*};
+use crate::errors::ShielderError;
+
+fn set_amount(op_pub: &mut OpPub, new_amount: u128) {
+    match op_pub {
+        OpPub::Deposit { amount, .. } | OpPub::Withdraw { amount, .. } => *amount = new_amount,
+    }
+}
+
+#[drink::test]
+fn update_errors_carry_failed_check(mut session: Session) -> Result<()> {
+    let mut rng = StdRng::seed_from_u64(29);
+
+    let alice = init_alice(&mut session)?;
+    let bob = init_bob(&mut session)?;
+
+    session = session.with_actor(alice.clone());
+
+    let psp22_address = deploy_test_token(&mut session, 100)?;
+    let shielder_address = deploy_shielder(&mut session, &psp22_address)?;
+
+    // CREATE ACCOUNT
+    let user_shielded_data =
+        create_shielder_account(&mut session, &shielder_address, Seed::from_bytes(rng.gen()))?;
+    // root of the tree not containing the current note
+    let stale_root = get_shielder_merkle_root(&mut session, &shielder_address)?;
+
+    // APPROVE TRANSFER
+    psp22_approve(&mut session, &psp22_address, &shielder_address, 10)?;
+
+    // DEPOSIT
+    let user_shielded_data = shielder_update(
+        &mut session,
+        &shielder_address,
+        deposit_op(&psp22_address, &alice, 10),
+        user_shielded_data,
+    )?;
+
+    let withdraw = prepare_shielder_update(
+        &mut session,
+        &shielder_address,
+        withdraw_op(&psp22_address, &alice, 5),
+        user_shielded_data,
+        None,
+    )?;
+    let deposit = prepare_shielder_update(
+        &mut session,
+        &shielder_address,
+        deposit_op(&psp22_address, &alice, 5),
+        user_shielded_data,
+        None,
+    )?;
+
+    let cases: Vec<(UpdateNoteCall, ZkpError)> = vec![
+        {
+            let mut call = withdraw.clone();
+            if let OpPub::Withdraw { token, .. } = &mut call.op_pub {
+                *token = Scalar::from_bytes(*alice.as_ref());
+            }
+            (call, ZkpError::UnknownToken)
+        },
+        {
+            let mut call = withdraw.clone();
+            set_amount(&mut call.op_pub, 11);
+            (call, ZkpError::BalanceUnderflow)
+        },
+        {
+            let mut call = deposit.clone();
+            set_amount(&mut call.op_pub, u128::MAX);
+            (call, ZkpError::BalanceOverflow)
+        },
+        {
+            let mut call = withdraw.clone();
+            if let OpPub::Withdraw { user, .. } = &mut call.op_pub {
+                *user = Scalar::from_bytes(*bob.as_ref());
+            }
+            (call, ZkpError::OperationCombineError)
+        },
+        {
+            let mut call = withdraw.clone();
+            call.h_note_new = Scalar::from_bytes(rng.gen());
+            (call, ZkpError::NoteHashMismatch)
+        },
+        {
+            // claims the spent note held the account after the withdrawal
+            let mut call = withdraw.clone();
+            let mut proof = serde_json::to_value(call.proof)?;
+            proof["acc_old"] = proof["acc_new"].clone();
+            call.proof = serde_json::from_value(proof)?;
+            (call, ZkpError::AccountHashMismatch)
+        },
+        {
+            let mut call = withdraw.clone();
+            call.merkle_root = stale_root;
+            (call, ZkpError::MerklePathMismatch)
+        },
+        {
+            let mut call = withdraw.clone();
+            call.nullifier_old = Scalar::from_bytes(rng.gen());
+            (call, ZkpError::NullifierMismatch)
+        },
+    ];
+    for (call, error) in cases {
+        assert_eq!(
+            shielder_update_note(&mut session, &shielder_address, &call)?,
+            Err(ShielderError::Zkp(error))
+        );
+    }
+
+    // rejected calls didn't change the state
+    shielder_update_note(&mut session, &shielder_address, &withdraw)?.unwrap();
+    let alice_psp22_balance = get_psp22_balance(&mut session, &psp22_address, &alice)?;
+    assert_eq!(alice_psp22_balance, 95);
+
+    Ok(())
+}
+
+#[drink::test]
+fn proofs_outside_wire_format_rejected(mut session: Session) -> Result<()> {
+    let mut rng = StdRng::seed_from_u64(48);
+
+    let alice = init_alice(&mut session)?;
+
+    session = session.with_actor(alice.clone());
+
+    let psp22_address = deploy_test_token(&mut session, 100)?;
+    let shielder_address = deploy_shielder(&mut session, &psp22_address)?;
+
+    // CREATE ACCOUNT
+    let user_shielded_data =
+        create_shielder_account(&mut session, &shielder_address, Seed::from_bytes(rng.gen()))?;
+
+    // APPROVE TRANSFER
+    psp22_approve(&mut session, &psp22_address, &shielder_address, 10)?;
+
+    let deposit = prepare_shielder_update(
+        &mut session,
+        &shielder_address,
+        deposit_op(&psp22_address, &alice, 10),
+        user_shielded_data,
+        None,
+    )?;
+    let proof = deposit.proof_bytes();
+    let mut other_version = proof.clone();
+    other_version[0] = WIRE_VERSION + 1;
+
+    let cases = [
+        (vec![], ZkpError::MalformedProof),
+        (proof[..proof.len() - 1].to_vec(), ZkpError::MalformedProof),
+        ([proof.as_slice(), &[0]].concat(), ZkpError::MalformedProof),
+        (other_version, ZkpError::UnsupportedProofVersion),
+        (
+            to_scale(&Proof::<MERKLE_TREE_DEPTH, TOKENS_NUMBER>::Halo2(vec![
+                0;
+                32
+            ])),
+            ZkpError::UnsupportedProofSystem,
+        ),
+    ];
+    for (proof, error) in cases {
+        assert_eq!(
+            shielder_update_note_with_proof(&mut session, &shielder_address, &deposit, &proof)?,
+            Err(ShielderError::Zkp(error))
+        );
+    }
+
+    // rejected calls didn't spend the note
+    shielder_update_note(&mut session, &shielder_address, &deposit)?.unwrap();
+    let shielder_psp22_balance =
+        get_psp22_balance(&mut session, &psp22_address, &shielder_address)?;
+    assert_eq!(shielder_psp22_balance, 10);
+
+    Ok(())
+}
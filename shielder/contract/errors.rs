@@ -11,8 +11,20 @@ pub enum ShielderError {
     MerkleTreeLimitExceeded,
     MerkleTreeProofGenFail,
     MerkleTreeNonExistingNode,
-    ZkpVerificationFail,
+    Zkp(ZkpError),
     ArithmeticError,
+    NotOwner,
+    SetCodeHashFail,
+    StorageVersionMismatch,
+    SubmitterMismatch,
+    OperationExpired,
+    ReentrantCall,
+    PSP22CallFail,
+    DepositShortfall,
+    DepositLimitExceeded,
+    WithdrawLimitExceeded,
+    BlockWithdrawLimitExceeded,
+    MemoTooLong,
 }
 
 impl From<PSP22Error> for ShielderError {
@@ -22,7 +34,7 @@ impl From<PSP22Error> for ShielderError {
 }
 
 impl From<ZkpError> for ShielderError {
-    fn from(_inner: ZkpError) -> Self {
-        ShielderError::ZkpVerificationFail
+    fn from(inner: ZkpError) -> Self {
+        ShielderError::Zkp(inner)
     }
 }
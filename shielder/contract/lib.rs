@@ -14,66 +14,183 @@ mod types;
 #[ink::contract]
 pub mod contract {
 
-    use ink::env::{
-        call::{build_call, ExecutionInput, Selector},
-        DefaultEnvironment,
+    use ink::{
+        env::{
+            call::{build_call, ExecutionInput, Selector},
+            CallFlags, DefaultEnvironment,
+        },
+        prelude::vec::Vec,
+        storage::{Lazy, Mapping},
     };
-    use mocked_zk::{ops::OpPub, relations::ZkProof, Scalar};
+    use mocked_zk::{domain_separator, ops::OpPub, wire, Scalar};
 
-    use crate::{errors::ShielderError, merkle::MerkleTree, traits::psp22::PSP22Error, types::Set};
+    use crate::{
+        errors::ShielderError,
+        merkle::MerkleTree,
+        traits::psp22::PSP22Error,
+        types::{BlockWithdrawals, PoolState, Set, TokenLimits},
+    };
 
+    /// Depth of the notes tree, proofs have to be built for the same depth
     pub const MERKLE_TREE_DEPTH: usize = mocked_zk::MERKLE_TREE_DEPTH;
+    /// Number of tokens held by every account, proofs have to be built for the same number
     pub const TOKENS_NUMBER: usize = mocked_zk::TOKENS_NUMBER;
 
+    type Proof = wire::Proof<MERKLE_TREE_DEPTH, TOKENS_NUMBER>;
+
+    /// Version of storage layout expected by this code
+    #[cfg(not(feature = "migration-test"))]
+    pub const STORAGE_VERSION: u16 = 2;
+    /// Version of storage layout expected by this code
+    /// bumped only to test upgrades
+    #[cfg(feature = "migration-test")]
+    pub const STORAGE_VERSION: u16 = 3;
+
+    /// Default gas limit of a single PSP22 call
+    pub const DEFAULT_PSP22_GAS_LIMIT: u64 = 100_000_000_000;
+
+    /// Max length of memo attached to a note
+    pub const MAX_MEMO_LEN: usize = 1024;
+
+    /// Emitted for every note added to shielder storage
+    #[ink(event)]
+    pub struct NoteAdded {
+        #[ink(topic)]
+        leaf_id: u32,
+        note_hash: Scalar,
+        /// nullifier of the note replaced by this one, `None` for new accounts
+        nullifier_old: Option<Scalar>,
+        /// opaque data, usually note secrets encrypted for its owner
+        memo: Vec<u8>,
+    }
+
     /// Contract storage
+    ///
+    /// Version 1 holds fields up to `storage_version`, version 2 adds the rest of them.
+    /// Fields added after version 1 are kept out of the root cell, in `Lazy` or `Mapping`, so
+    /// that the root cell written by older code still decodes and `migrate` can be called.
     #[ink(storage)]
-    #[derive(Default)]
     pub struct Contract {
         nullifier_set: Set<Scalar>,
         notes: MerkleTree<{ MERKLE_TREE_DEPTH }>,
         supported_tokens: [Scalar; TOKENS_NUMBER],
+        owner: AccountId,
+        storage_version: u16,
+        chain_tag: Lazy<Scalar>,
+        psp22_gas_limit: Lazy<u64>,
+        pool_states: Mapping<Scalar, PoolState>,
+        token_limits: Mapping<Scalar, TokenLimits>,
+        block_withdrawals: Mapping<Scalar, BlockWithdrawals>,
+        /// set while `add_note` or `update_note` is executed
+        /// kept out of the root cell, so that it is written immediately
+        locked: Lazy<bool>,
     }
 
     impl Contract {
         /// Constructor
+        /// Caller becomes the owner, allowed to upgrade the contract
+        /// `chain_tag` distinguishes chains on which the same address may be deployed
         #[ink(constructor)]
-        pub fn new(supported_tokens: [Scalar; TOKENS_NUMBER]) -> Self {
-            Self {
+        pub fn new(supported_tokens: [Scalar; TOKENS_NUMBER], chain_tag: Scalar) -> Self {
+            let mut contract = Self {
+                nullifier_set: Default::default(),
+                notes: Default::default(),
                 supported_tokens,
-                ..Default::default()
-            }
+                owner: Self::env().caller(),
+                storage_version: STORAGE_VERSION,
+                chain_tag: Default::default(),
+                psp22_gas_limit: Default::default(),
+                pool_states: Default::default(),
+                token_limits: Default::default(),
+                block_withdrawals: Default::default(),
+                locked: Default::default(),
+            };
+            contract.chain_tag.set(&chain_tag);
+            contract.psp22_gas_limit.set(&DEFAULT_PSP22_GAS_LIMIT);
+            contract
         }
 
         /// Adds empty note to shielder storage
         /// Registers new account with empty balance
+        /// `memo` is emitted with the note, so that its owner can find it
+        /// `proof` is in the SCALE wire format of `mocked_zk::wire`
         /// Returns id of the note in shielder's storage
         #[ink(message)]
         pub fn add_note(
             &mut self,
             h_note_new: Scalar,
-            proof: ZkProof,
+            proof: Vec<u8>,
+            memo: Vec<u8>,
         ) -> Result<u32, ShielderError> {
-            proof.verify_creation(h_note_new, self.supported_tokens)?;
-            self.notes.add_leaf(h_note_new)
+            self.non_reentrant(|shielder| {
+                shielder.ensure_storage_version()?;
+                let proof = Proof::mocked_from_scale(&proof)?;
+                proof.verify_creation(
+                    h_note_new,
+                    shielder.supported_tokens,
+                    shielder.domain_separator(),
+                )?;
+                shielder.insert_note(h_note_new, None, memo)
+            })
         }
 
         /// Updates existing note
         /// Applies operation to private account stored in shielder
+        /// If `submitter` is set, only that account can submit the update
+        /// Operation with `valid_until` set can't be applied after that block
+        /// `memo` is emitted with the note, so that its owner can find it
+        /// `proof` is in the SCALE wire format of `mocked_zk::wire`
         /// Returns id of the note in shielder's storage
         #[ink(message)]
+        #[allow(clippy::too_many_arguments)]
         pub fn update_note(
             &mut self,
             op_pub: OpPub,
             h_note_new: Scalar,
             merkle_root: Scalar,
             nullifier_old: Scalar,
-            proof: ZkProof,
+            submitter: Option<Scalar>,
+            proof: Vec<u8>,
+            memo: Vec<u8>,
+        ) -> Result<u32, ShielderError> {
+            self.non_reentrant(|shielder| {
+                shielder.ensure_storage_version()?;
+                shielder.ensure_submitter(submitter)?;
+                shielder.ensure_not_expired(op_pub)?;
+                shielder.notes.is_historical_root(merkle_root)?;
+                shielder.nullify(nullifier_old)?;
+                let proof = Proof::mocked_from_scale(&proof)?;
+                proof.verify_update(
+                    op_pub,
+                    h_note_new,
+                    merkle_root,
+                    nullifier_old,
+                    shielder.domain_separator(),
+                    submitter,
+                )?;
+                let leaf_id = shielder.insert_note(h_note_new, Some(nullifier_old), memo)?;
+                shielder.apply_limits(op_pub)?;
+                shielder.process_operation(op_pub)?;
+                Ok(leaf_id)
+            })
+        }
+
+        fn insert_note(
+            &mut self,
+            h_note: Scalar,
+            nullifier_old: Option<Scalar>,
+            memo: Vec<u8>,
         ) -> Result<u32, ShielderError> {
-            self.notes.is_historical_root(merkle_root)?;
-            self.nullify(nullifier_old)?;
-            proof.verify_update(op_pub, h_note_new, merkle_root, nullifier_old)?;
-            let leaf_id = self.notes.add_leaf(h_note_new)?;
-            self.process_operation(op_pub)?;
+            if memo.len() > MAX_MEMO_LEN {
+                return Err(ShielderError::MemoTooLong);
+            }
+            let leaf_id = self.notes.add_leaf(h_note)?;
+            self.env().emit_event(NoteAdded {
+                leaf_id,
+                note_hash: h_note,
+                nullifier_old,
+                memo,
+            });
             Ok(leaf_id)
         }
 
@@ -83,40 +200,163 @@ pub mod contract {
                     amount,
                     token,
                     user,
-                } => build_call::<DefaultEnvironment>()
-                    .call(AccountId::from(token.bytes))
-                    .call_v1()
-                    .gas_limit(0)
-                    .transferred_value(0)
-                    .exec_input(
-                        ExecutionInput::new(Selector::new(ink::selector_bytes!(
-                            "PSP22::transfer_from"
-                        )))
-                        .push_arg(AccountId::from(user.bytes))
-                        .push_arg(self.env().account_id())
-                        .push_arg(amount)
-                        .push_arg([].to_vec() as ink::prelude::vec::Vec<u8>),
-                    )
-                    .returns::<Result<(), PSP22Error>>()
-                    .invoke()?,
+                    ..
+                } => {
+                    // token might charge fees or rebase, credit only what was received
+                    let token = AccountId::from(token.bytes);
+                    let shielder = self.env().account_id();
+                    let balance_before = self.psp22_balance_of(token, shielder)?;
+                    self.psp22_transfer_from(token, AccountId::from(user.bytes), amount)?;
+                    let balance_after = self.psp22_balance_of(token, shielder)?;
+                    let received = balance_after
+                        .checked_sub(balance_before)
+                        .ok_or(ShielderError::DepositShortfall)?;
+                    if received < amount {
+                        return Err(ShielderError::DepositShortfall);
+                    }
+                    self.update_pool_state(token, |pool| {
+                        pool.total_deposited = pool.total_deposited.checked_add(amount)?;
+                        Some(())
+                    })
+                }
                 OpPub::Withdraw {
                     amount,
                     token,
                     user,
-                } => build_call::<DefaultEnvironment>()
-                    .call(AccountId::from(token.bytes))
-                    .call_v1()
-                    .gas_limit(0)
-                    .transferred_value(0)
-                    .exec_input(
-                        ExecutionInput::new(Selector::new(ink::selector_bytes!("PSP22::transfer")))
-                            .push_arg(AccountId::from(user.bytes))
-                            .push_arg(amount)
-                            .push_arg([].to_vec() as ink::prelude::vec::Vec<u8>),
-                    )
-                    .returns::<Result<(), PSP22Error>>()
-                    .invoke()?,
-            };
+                    ..
+                } => {
+                    let token = AccountId::from(token.bytes);
+                    self.psp22_transfer(token, AccountId::from(user.bytes), amount)?;
+                    self.update_pool_state(token, |pool| {
+                        pool.total_withdrawn = pool.total_withdrawn.checked_add(amount)?;
+                        Some(())
+                    })
+                }
+            }
+        }
+
+        /// Checks operation against limits of its token
+        /// Records withdrawn amount towards limit of the current block
+        fn apply_limits(&mut self, op_pub: OpPub) -> Result<(), ShielderError> {
+            match op_pub {
+                OpPub::Deposit { amount, token, .. } => {
+                    let limits = self.token_limits(token);
+                    if limits.max_deposit.is_some_and(|max| amount > max) {
+                        return Err(ShielderError::DepositLimitExceeded);
+                    }
+                }
+                OpPub::Withdraw { amount, token, .. } => {
+                    let limits = self.token_limits(token);
+                    if limits.max_withdraw.is_some_and(|max| amount > max) {
+                        return Err(ShielderError::WithdrawLimitExceeded);
+                    }
+                    let withdrawn = self
+                        .withdrawn_in_current_block(token)
+                        .checked_add(amount)
+                        .ok_or(ShielderError::ArithmeticError)?;
+                    if limits
+                        .max_withdraw_per_block
+                        .is_some_and(|max| withdrawn > max)
+                    {
+                        return Err(ShielderError::BlockWithdrawLimitExceeded);
+                    }
+                    self.block_withdrawals.insert(
+                        token,
+                        &BlockWithdrawals {
+                            block_number: self.env().block_number(),
+                            amount: withdrawn,
+                        },
+                    );
+                }
+            }
+            Ok(())
+        }
+
+        fn update_pool_state(
+            &mut self,
+            token: AccountId,
+            update: impl FnOnce(&mut PoolState) -> Option<()>,
+        ) -> Result<(), ShielderError> {
+            let token = Scalar::from_bytes(*token.as_ref());
+            let mut pool = self.pool_state(token);
+            update(&mut pool).ok_or(ShielderError::ArithmeticError)?;
+            self.pool_states.insert(token, &pool);
+            Ok(())
+        }
+
+        fn psp22_balance_of(
+            &self,
+            token: AccountId,
+            owner: AccountId,
+        ) -> Result<u128, ShielderError> {
+            build_call::<DefaultEnvironment>()
+                .call(token)
+                .call_v1()
+                .gas_limit(self.psp22_gas_limit())
+                .call_flags(CallFlags::ALLOW_REENTRY)
+                .transferred_value(0)
+                .exec_input(
+                    ExecutionInput::new(Selector::new(ink::selector_bytes!("PSP22::balance_of")))
+                        .push_arg(owner),
+                )
+                .returns::<u128>()
+                .try_invoke()
+                .map_err(|_| ShielderError::PSP22CallFail)?
+                .map_err(|_| ShielderError::PSP22CallFail)
+        }
+
+        /// Transfers `amount` of `token` from `from` to shielder
+        fn psp22_transfer_from(
+            &self,
+            token: AccountId,
+            from: AccountId,
+            amount: u128,
+        ) -> Result<(), ShielderError> {
+            build_call::<DefaultEnvironment>()
+                .call(token)
+                .call_v1()
+                .gas_limit(self.psp22_gas_limit())
+                .call_flags(CallFlags::ALLOW_REENTRY)
+                .transferred_value(0)
+                .exec_input(
+                    ExecutionInput::new(Selector::new(ink::selector_bytes!(
+                        "PSP22::transfer_from"
+                    )))
+                    .push_arg(from)
+                    .push_arg(self.env().account_id())
+                    .push_arg(amount)
+                    .push_arg([].to_vec() as Vec<u8>),
+                )
+                .returns::<Result<(), PSP22Error>>()
+                .try_invoke()
+                .map_err(|_| ShielderError::PSP22CallFail)?
+                .map_err(|_| ShielderError::PSP22CallFail)??;
+            Ok(())
+        }
+
+        /// Transfers `amount` of `token` from shielder to `to`
+        fn psp22_transfer(
+            &self,
+            token: AccountId,
+            to: AccountId,
+            amount: u128,
+        ) -> Result<(), ShielderError> {
+            build_call::<DefaultEnvironment>()
+                .call(token)
+                .call_v1()
+                .gas_limit(self.psp22_gas_limit())
+                .call_flags(CallFlags::ALLOW_REENTRY)
+                .transferred_value(0)
+                .exec_input(
+                    ExecutionInput::new(Selector::new(ink::selector_bytes!("PSP22::transfer")))
+                        .push_arg(to)
+                        .push_arg(amount)
+                        .push_arg([].to_vec() as Vec<u8>),
+                )
+                .returns::<Result<(), PSP22Error>>()
+                .try_invoke()
+                .map_err(|_| ShielderError::PSP22CallFail)?
+                .map_err(|_| ShielderError::PSP22CallFail)??;
             Ok(())
         }
 
@@ -141,6 +381,167 @@ pub mod contract {
             self.supported_tokens
         }
 
+        /// Returns amounts of `token` deposited to and withdrawn from the shielder
+        #[ink(message)]
+        pub fn pool_state(&self, token: Scalar) -> PoolState {
+            self.pool_states.get(token).unwrap_or_default()
+        }
+
+        /// Checks if shielder holds enough `token` to cover all withdrawals
+        #[ink(message)]
+        pub fn check_solvency(&self, token: Scalar) -> Result<bool, ShielderError> {
+            let expected_balance = self
+                .pool_state(token)
+                .expected_balance()
+                .ok_or(ShielderError::ArithmeticError)?;
+            let balance =
+                self.psp22_balance_of(AccountId::from(token.bytes), self.env().account_id())?;
+            Ok(balance >= expected_balance)
+        }
+
+        #[ink(message)]
+        pub fn token_limits(&self, token: Scalar) -> TokenLimits {
+            self.token_limits.get(token).unwrap_or_default()
+        }
+
+        /// Returns amount of `token` withdrawn in the current block
+        #[ink(message)]
+        pub fn withdrawn_in_current_block(&self, token: Scalar) -> u128 {
+            self.block_withdrawals
+                .get(token)
+                .filter(|withdrawals| withdrawals.block_number == self.env().block_number())
+                .map_or(0, |withdrawals| withdrawals.amount)
+        }
+
+        #[ink(message)]
+        pub fn set_token_limits(
+            &mut self,
+            token: Scalar,
+            limits: TokenLimits,
+        ) -> Result<(), ShielderError> {
+            self.ensure_owner()?;
+            self.token_limits.insert(token, &limits);
+            Ok(())
+        }
+
+        /// Returns domain separator, proofs have to be created for it
+        #[ink(message)]
+        pub fn domain_separator(&self) -> Scalar {
+            domain_separator(
+                Scalar::from_bytes(*self.env().account_id().as_ref()),
+                self.chain_tag.get().unwrap_or_default(),
+            )
+        }
+
+        #[ink(message)]
+        pub fn owner(&self) -> AccountId {
+            self.owner
+        }
+
+        #[ink(message)]
+        pub fn storage_version(&self) -> u16 {
+            self.storage_version
+        }
+
+        #[ink(message)]
+        pub fn psp22_gas_limit(&self) -> u64 {
+            self.psp22_gas_limit.get().unwrap_or_default()
+        }
+
+        /// Sets gas limit of a single PSP22 call, 0 means no limit
+        #[ink(message)]
+        pub fn set_psp22_gas_limit(&mut self, gas_limit: u64) -> Result<(), ShielderError> {
+            self.ensure_owner()?;
+            self.psp22_gas_limit.set(&gas_limit);
+            Ok(())
+        }
+
+        /// Replaces code of the contract, keeping its storage
+        /// New code has to be followed by `migrate` call
+        #[ink(message)]
+        pub fn set_code(&mut self, code_hash: Hash) -> Result<(), ShielderError> {
+            self.ensure_owner()?;
+            self.env()
+                .set_code_hash(&code_hash)
+                .map_err(|_| ShielderError::SetCodeHashFail)
+        }
+
+        /// Migrates storage written by previous versions of the code
+        /// Returns storage version after migration
+        #[ink(message)]
+        pub fn migrate(&mut self) -> Result<u16, ShielderError> {
+            self.ensure_owner()?;
+            if self.storage_version > STORAGE_VERSION {
+                return Err(ShielderError::StorageVersionMismatch);
+            }
+            while self.storage_version < STORAGE_VERSION {
+                self.migrate_from(self.storage_version)?;
+                self.storage_version = self
+                    .storage_version
+                    .checked_add(1)
+                    .ok_or(ShielderError::ArithmeticError)?;
+            }
+            Ok(self.storage_version)
+        }
+
+        /// Migration hook, moves storage from `version` to the next one
+        /// Version 1 had no chain tag, contracts migrated from it keep the zero one
+        fn migrate_from(&mut self, version: u16) -> Result<(), ShielderError> {
+            if version == 1 {
+                self.chain_tag.set(&Scalar::default());
+                self.psp22_gas_limit.set(&DEFAULT_PSP22_GAS_LIMIT);
+            }
+            Ok(())
+        }
+
+        /// Runs `f` rejecting any nested call to `add_note` or `update_note`
+        /// Such call might come from PSP22 token during `process_operation`, PSP22 calls allow
+        /// re-entry so that tokens can query the shielder, this lock is what rejects note updates
+        fn non_reentrant<T>(
+            &mut self,
+            f: impl FnOnce(&mut Self) -> Result<T, ShielderError>,
+        ) -> Result<T, ShielderError> {
+            if self.locked.get().unwrap_or(false) {
+                return Err(ShielderError::ReentrantCall);
+            }
+            self.locked.set(&true);
+            let res = f(self);
+            self.locked.set(&false);
+            res
+        }
+
+        fn ensure_owner(&self) -> Result<(), ShielderError> {
+            (self.env().caller() == self.owner)
+                .then_some(())
+                .ok_or(ShielderError::NotOwner)
+        }
+
+        fn ensure_submitter(&self, submitter: Option<Scalar>) -> Result<(), ShielderError> {
+            match submitter {
+                Some(submitter)
+                    if submitter != Scalar::from_bytes(*self.env().caller().as_ref()) =>
+                {
+                    Err(ShielderError::SubmitterMismatch)
+                }
+                _ => Ok(()),
+            }
+        }
+
+        fn ensure_not_expired(&self, op_pub: OpPub) -> Result<(), ShielderError> {
+            match op_pub.valid_until() {
+                Some(valid_until) if self.env().block_number() > valid_until => {
+                    Err(ShielderError::OperationExpired)
+                }
+                _ => Ok(()),
+            }
+        }
+
+        fn ensure_storage_version(&self) -> Result<(), ShielderError> {
+            (self.storage_version == STORAGE_VERSION)
+                .then_some(())
+                .ok_or(ShielderError::StorageVersionMismatch)
+        }
+
         fn nullify(&mut self, nullifier: Scalar) -> Result<(), ShielderError> {
             self.nullifier_set
                 .insert(nullifier, &())
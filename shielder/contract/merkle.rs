@@ -86,9 +86,10 @@ impl<const DEPTH: usize> MerkleTree<DEPTH> {
             .ok_or(ShielderError::MerkleTreeVerificationFail)
     }
 
+    /// Path of an existing leaf, notes in the full tree still have to be spendable
     pub fn gen_proof(&self, leaf_id: u32) -> Result<[Scalar; DEPTH], ShielderError> {
         let mut res = [Scalar::from_bytes([0x0; 32]); DEPTH];
-        if self.next_leaf_idx == self.size {
+        if leaf_id >= self.next_leaf_idx {
             return Err(ShielderError::MerkleTreeProofGenFail);
         }
         let mut id = leaf_id
@@ -108,10 +109,62 @@ impl<const DEPTH: usize> MerkleTree<DEPTH> {
 
 #[cfg(test)]
 mod tests {
+    use std::sync::atomic::{AtomicU32, Ordering};
+
     use ink::primitives::AccountId;
+    use mocked_zk::merkle_root_from_path;
+    use proptest::{collection::vec, prelude::*};
 
     use super::*;
 
+    /// Empty tree in storage of a new contract, so that proptest cases don't share storage
+    fn new_tree<const DEPTH: usize>() -> MerkleTree<DEPTH> {
+        static CONTRACTS: AtomicU32 = AtomicU32::new(0);
+        let mut callee = [0xff; 32];
+        callee[..4].copy_from_slice(&CONTRACTS.fetch_add(1, Ordering::Relaxed).to_le_bytes());
+        ink::env::test::set_callee::<ink::env::DefaultEnvironment>(AccountId::from(callee));
+        MerkleTree::default()
+    }
+
+    fn leaf() -> impl Strategy<Value = Scalar> {
+        any::<[u8; 32]>().prop_map(Scalar::from_bytes)
+    }
+
+    proptest! {
+        #[test]
+        fn paths_verify_against_their_root(leaves in vec(leaf(), 1..64)) {
+            let mut merkle_tree = new_tree::<6>();
+            for (i, leaf) in leaves.iter().enumerate() {
+                prop_assert_eq!(merkle_tree.add_leaf(*leaf), Ok(i as u32));
+                let root = merkle_tree.root().unwrap();
+                prop_assert!(merkle_tree.is_historical_root(root).is_ok());
+                for (leaf_id, leaf) in leaves.iter().enumerate().take(i + 1) {
+                    let path = merkle_tree.gen_proof(leaf_id as u32).unwrap();
+                    prop_assert_eq!(merkle_root_from_path(*leaf, path, leaf_id as u32), root);
+                }
+                prop_assert!(merkle_tree.gen_proof(i as u32 + 1).is_err());
+            }
+        }
+
+        #[test]
+        fn full_tree_keeps_paths(leaves in vec(leaf(), 8)) {
+            let mut merkle_tree = new_tree::<3>();
+            for leaf in &leaves {
+                merkle_tree.add_leaf(*leaf).unwrap();
+            }
+            let root = merkle_tree.root().unwrap();
+            prop_assert_eq!(
+                merkle_tree.add_leaf(leaves[0]),
+                Err(ShielderError::MerkleTreeLimitExceeded)
+            );
+            prop_assert_eq!(merkle_tree.root(), Ok(root));
+            for (leaf_id, leaf) in leaves.iter().enumerate() {
+                let path = merkle_tree.gen_proof(leaf_id as u32).unwrap();
+                prop_assert_eq!(merkle_root_from_path(*leaf, path, leaf_id as u32), root);
+            }
+        }
+    }
+
     #[test]
     fn add_two_leaves_and_root() {
         ink::env::test::set_callee::<ink::env::DefaultEnvironment>(AccountId::from([0x1; 32]));
@@ -141,6 +194,37 @@ mod tests {
         assert!(merkle_tree.add_leaf(0_u128.into()).is_err());
     }
 
+    #[test]
+    fn gen_proof_of_used_leaves_only() {
+        ink::env::test::set_callee::<ink::env::DefaultEnvironment>(AccountId::from([0x3; 32]));
+        let mut merkle_tree = MerkleTree::<3>::default();
+        merkle_tree.add_leaf(0_u128.into()).unwrap();
+        assert_eq!(
+            merkle_tree.gen_proof(1),
+            Err(ShielderError::MerkleTreeProofGenFail)
+        );
+
+        for i in 1..8 {
+            merkle_tree.add_leaf((i as u128).into()).unwrap();
+        }
+        // the tree is full, its notes are still spendable
+        assert_eq!(
+            merkle_tree.gen_proof(7),
+            Ok([
+                6_u128.into(),
+                compute_hash(4_u128.into(), 5_u128.into()),
+                compute_hash(
+                    compute_hash(0_u128.into(), 1_u128.into()),
+                    compute_hash(2_u128.into(), 3_u128.into())
+                ),
+            ])
+        );
+        assert_eq!(
+            merkle_tree.gen_proof(8),
+            Err(ShielderError::MerkleTreeProofGenFail)
+        );
+    }
+
     #[test]
     fn historical_root() {
         ink::env::test::set_callee::<ink::env::DefaultEnvironment>(AccountId::from([0x1; 32]));
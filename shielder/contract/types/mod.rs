@@ -1,3 +1,41 @@
 use ink::storage::Mapping;
 
 pub type Set<T> = Mapping<T, ()>;
+
+/// Amounts of a single token which went through the shielder
+#[ink::scale_derive(Encode, Decode, TypeInfo)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+pub struct PoolState {
+    pub total_deposited: u128,
+    pub total_withdrawn: u128,
+}
+
+impl PoolState {
+    /// Amount the shielder should hold, `None` if more was withdrawn than deposited
+    pub fn expected_balance(&self) -> Option<u128> {
+        self.total_deposited.checked_sub(self.total_withdrawn)
+    }
+}
+
+/// Limits of amounts of a single token, `None` means no limit
+#[ink::scale_derive(Encode, Decode, TypeInfo)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+pub struct TokenLimits {
+    /// max amount of a single deposit
+    pub max_deposit: Option<u128>,
+    /// max amount of a single withdrawal
+    pub max_withdraw: Option<u128>,
+    /// max amount of all withdrawals in a single block
+    pub max_withdraw_per_block: Option<u128>,
+}
+
+/// Amount of a single token withdrawn in a block
+#[ink::scale_derive(Encode, Decode, TypeInfo)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+pub struct BlockWithdrawals {
+    pub block_number: u32,
+    pub amount: u128,
+}
@@ -0,0 +1,102 @@
+//! `update_note` statement checked by `update_note_circuit` under `MockProver`
+use halo2_base::{
+    halo2_proofs::{dev::MockProver, halo2curves::bn256::Fr},
+    utils::BigPrimeField,
+};
+use liminal_halo2_relations::{
+    merkle_proof::MerkleProof,
+    token_account::{TokenAccount, TokenOpPriv, TokenOpPub},
+    witness::UpdateNoteWitness,
+};
+use mocked_zk::{wire::UpdateNotePublic, Scalar};
+
+use crate::{mocked, Case, Witness, TOKENS, TREE_HEIGHT};
+
+/// Circuit has 2^K rows
+const K: usize = 14;
+
+type CaseAccount = TokenAccount<Fr, TOKENS>;
+type CircuitWitness = UpdateNoteWitness<Fr, TREE_HEIGHT, TOKENS>;
+
+fn field<F: BigPrimeField>(value: u128) -> F {
+    F::from_u128(value)
+}
+
+/// Wire format scalar of a field element, its little-endian representation
+fn to_scalar<F: BigPrimeField>(value: F) -> Scalar {
+    let mut bytes = [0; 32];
+    bytes.copy_from_slice(value.to_repr().as_ref());
+    Scalar::from_bytes(bytes)
+}
+
+/// Field element of a wire format scalar, `None` if it is not canonical
+fn from_scalar<F: BigPrimeField>(scalar: Scalar) -> Option<F> {
+    let mut repr = F::Repr::default();
+    repr.as_mut().copy_from_slice(&scalar.bytes);
+    F::from_repr(repr).into()
+}
+
+fn circuit_witness(w: &Witness) -> CircuitWitness {
+    let op_pub = TokenOpPub {
+        deposit: field(w.op_pub.deposit as u128),
+        amount: field(w.op_pub.amount),
+        token: field(w.op_pub.token),
+        user: field(w.op_pub.user),
+        valid_until: field(w.op_pub.valid_until.map_or(0, |block| block as u128 + 1)),
+    };
+    let op_priv = TokenOpPriv {
+        user: field(w.op_priv_user),
+    };
+    let old_account = CaseAccount {
+        tokens: w.tokens.map(field),
+        balances: w.balances.map(field),
+    };
+    let path_shape = core::array::from_fn(|level| (w.leaf_id >> level) & 1 == 0);
+
+    // new note holds the account updated even by invalid operations, the circuit rejects them
+    CircuitWitness::new(
+        op_pub,
+        op_priv,
+        field(w.domain),
+        field(w.submitter.unwrap_or(0)),
+        field(w.id),
+        (field(w.old_trapdoor), field(w.old_nullifier_key)),
+        (field(w.new_trapdoor), field(w.new_nullifier_key)),
+        MerkleProof::new(path_shape, w.merkle_path.map(field)),
+        old_account,
+    )
+}
+
+/// Whether the circuit is satisfied by the witness with public inputs of the claimed witness
+pub fn accepts(case: &Case) -> bool {
+    let (circuit, _) = circuit_witness(&case.witness).builder(K);
+    let instance = circuit_witness(&case.claimed).instance();
+    MockProver::run(K as u32, &circuit, vec![instance])
+        .map(|prover| prover.verify().is_ok())
+        .unwrap_or(false)
+}
+
+/// Whether the witness passes native validation of `UpdateNoteWitness`
+pub fn validates(witness: &Witness) -> bool {
+    circuit_witness(witness).validate().is_ok()
+}
+
+/// Public inputs of the circuit for the witness
+pub fn instance(w: &Witness) -> Vec<Fr> {
+    circuit_witness(w).instance()
+}
+
+/// `UpdateNotePublic::instance` for the witness mapped to field elements, `None` if it is not
+/// canonical. Backends hash with different functions, so hashes are computed by the circuit.
+pub fn wire_instance(w: &Witness) -> Option<Vec<Fr>> {
+    let witness = circuit_witness(w);
+    let public = UpdateNotePublic {
+        op_pub: mocked::op_pub(w),
+        h_note_new: to_scalar(witness.new_note_hash()),
+        merkle_root: to_scalar(witness.merkle_root()),
+        nullifier_old: to_scalar(witness.nullifier()),
+        domain: w.domain.into(),
+        submitter: w.submitter.map(Scalar::from),
+    };
+    public.instance()?.into_iter().map(from_scalar).collect()
+}
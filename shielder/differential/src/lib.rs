@@ -0,0 +1,59 @@
+//! Differential tests of `mocked_zk` relations against `liminal-halo2-relations` circuits
+//!
+//! Both backends should encode the same `update_note` statement. A case is given as small
+//! integers shared by both backends: the witness known to the prover and the witness from which
+//! public inputs given to the verifier are derived. Each backend hashes them with its own hash
+//! (SHA-256 for the mock, Poseidon for the circuit), so only accept/reject decisions are compared.
+pub mod halo2;
+pub mod mocked;
+#[cfg(test)]
+mod tests;
+
+pub const TREE_HEIGHT: usize = mocked_zk::MERKLE_TREE_DEPTH;
+pub const TOKENS: usize = mocked_zk::TOKENS_NUMBER;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct OpPub {
+    pub deposit: bool,
+    pub amount: u128,
+    pub token: u128,
+    pub user: u128,
+    pub valid_until: Option<u32>,
+}
+
+/// Values known to the prover of `update_note`
+/// `tokens` have to be distinct and `submitter` nonzero, as the circuit encodes `None` as zero
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Witness {
+    pub id: u128,
+    pub old_trapdoor: u128,
+    pub old_nullifier_key: u128,
+    pub new_trapdoor: u128,
+    pub new_nullifier_key: u128,
+    pub tokens: [u128; TOKENS],
+    /// balances of the account in the old note
+    pub balances: [u128; TOKENS],
+    pub merkle_path: [u128; TREE_HEIGHT],
+    pub leaf_id: u32,
+    pub op_pub: OpPub,
+    pub op_priv_user: u128,
+    pub domain: u128,
+    pub submitter: Option<u128>,
+}
+
+/// Proof built from `witness`, checked against public inputs derived from `claimed`
+/// Honest cases have both equal
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Case {
+    pub witness: Witness,
+    pub claimed: Witness,
+}
+
+impl Case {
+    pub fn honest(witness: Witness) -> Self {
+        Self {
+            witness,
+            claimed: witness,
+        }
+    }
+}
@@ -0,0 +1,143 @@
+//! `update_note` statement checked by `ZkProof::verify_update`
+use mocked_zk::{
+    account::Account,
+    derive_nullifier,
+    errors::ZkpError,
+    merkle_root_from_path, mocked_user,
+    note::Note,
+    ops::{OpPriv, OpPub, Operation},
+    relations::ZkProof,
+    traits::Hashable,
+    Scalar,
+};
+
+use crate::{Case, Witness};
+
+fn scalar(value: u128) -> Scalar {
+    value.into()
+}
+
+pub(crate) fn op_pub(w: &Witness) -> OpPub {
+    let (amount, token, user, valid_until) = (
+        w.op_pub.amount,
+        scalar(w.op_pub.token),
+        scalar(w.op_pub.user),
+        w.op_pub.valid_until,
+    );
+    if w.op_pub.deposit {
+        OpPub::Deposit {
+            amount,
+            token,
+            user,
+            valid_until,
+        }
+    } else {
+        OpPub::Withdraw {
+            amount,
+            token,
+            user,
+            valid_until,
+        }
+    }
+}
+
+/// Account of the old note, balances are set by deposits to distinct tokens
+fn account(w: &Witness) -> Account {
+    let mut account = Account::new(w.tokens.map(scalar));
+    for (token, balance) in w.tokens.into_iter().zip(w.balances) {
+        let op_pub = OpPub::Deposit {
+            amount: balance,
+            token: scalar(token),
+            user: mocked_user(),
+            valid_until: None,
+        };
+        account = account
+            .update(Operation::combine(op_pub, OpPriv::new(mocked_user())).unwrap())
+            .unwrap();
+    }
+    account
+}
+
+fn prove(w: &Witness) -> Result<ZkProof, ZkpError> {
+    let op_priv = OpPriv::new(scalar(w.op_priv_user));
+    let proof = ZkProof::new(
+        scalar(w.id),
+        scalar(w.old_trapdoor),
+        scalar(w.old_nullifier_key),
+        op_priv,
+        account(w),
+        scalar(w.domain),
+    );
+    let operation = Operation::combine(op_pub(w), op_priv)?;
+    let (_, proof) = proof.update_account(
+        operation,
+        scalar(w.new_trapdoor),
+        scalar(w.new_nullifier_key),
+        w.merkle_path.map(scalar),
+        w.leaf_id,
+        w.submitter.map(scalar),
+    )?;
+    Ok(proof)
+}
+
+struct PublicInputs {
+    op_pub: OpPub,
+    h_note_new: Scalar,
+    merkle_root: Scalar,
+    nullifier_old: Scalar,
+    domain: Scalar,
+    submitter: Option<Scalar>,
+}
+
+impl PublicInputs {
+    /// `None` if the operation can't be applied to the account
+    fn new(w: &Witness) -> Option<Self> {
+        let op_pub = op_pub(w);
+        let account_old = account(w);
+        let account_new = account_old
+            .update(Operation {
+                op_pub,
+                op_priv: OpPriv::new(scalar(w.op_pub.user)),
+            })
+            .ok()?;
+        let h_note_old = Note::new(
+            scalar(w.id),
+            scalar(w.old_trapdoor),
+            scalar(w.old_nullifier_key),
+            account_old.hash(),
+        )
+        .hash();
+        let h_note_new = Note::new(
+            scalar(w.id),
+            scalar(w.new_trapdoor),
+            scalar(w.new_nullifier_key),
+            account_new.hash(),
+        )
+        .hash();
+        Some(Self {
+            op_pub,
+            h_note_new,
+            merkle_root: merkle_root_from_path(h_note_old, w.merkle_path.map(scalar), w.leaf_id),
+            nullifier_old: derive_nullifier(scalar(w.old_nullifier_key), w.leaf_id),
+            domain: scalar(w.domain),
+            submitter: w.submitter.map(scalar),
+        })
+    }
+}
+
+/// Whether the mocked verifier accepts the case, failing to build the proof is a rejection
+pub fn accepts(case: &Case) -> bool {
+    let (Ok(proof), Some(public)) = (prove(&case.witness), PublicInputs::new(&case.claimed)) else {
+        return false;
+    };
+    proof
+        .verify_update(
+            public.op_pub,
+            public.h_note_new,
+            public.merkle_root,
+            public.nullifier_old,
+            public.domain,
+            public.submitter,
+        )
+        .is_ok()
+}
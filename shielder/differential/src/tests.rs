@@ -0,0 +1,279 @@
+use proptest::{collection::vec, option, prelude::*, sample::select};
+
+use crate::{halo2, mocked, Case, OpPub, Witness, TREE_HEIGHT};
+
+/// Mostly small amounts, so that withdrawals can succeed, sometimes overflowing ones
+fn amount() -> impl Strategy<Value = u128> {
+    prop_oneof![9 => 0..100_u128, 1 => any::<u128>()]
+}
+
+fn op_pub() -> impl Strategy<Value = OpPub> {
+    (
+        any::<bool>(),
+        amount(),
+        0..3_u128,
+        1..3_u128,
+        option::of(any::<u32>()),
+    )
+        .prop_map(|(deposit, amount, token, user, valid_until)| OpPub {
+            deposit,
+            amount,
+            token,
+            user,
+            valid_until,
+        })
+}
+
+fn witness() -> impl Strategy<Value = Witness> {
+    (
+        any::<[u128; 5]>(),
+        select(vec![[1, 0], [1, 2], [2, 1]]),
+        [amount(), amount()],
+        vec(any::<u128>(), TREE_HEIGHT),
+        0..(1_u32 << TREE_HEIGHT),
+        op_pub(),
+        prop_oneof![9 => Just(false), 1 => Just(true)],
+        any::<u128>(),
+        option::of(1..=u128::MAX),
+    )
+        .prop_map(
+            |(
+                [id, old_trapdoor, old_nullifier_key, new_trapdoor, new_nullifier_key],
+                tokens,
+                balances,
+                merkle_path,
+                leaf_id,
+                op_pub,
+                other_user,
+                domain,
+                submitter,
+            )| Witness {
+                id,
+                old_trapdoor,
+                old_nullifier_key,
+                new_trapdoor,
+                new_nullifier_key,
+                tokens,
+                balances,
+                merkle_path: merkle_path.try_into().unwrap(),
+                leaf_id,
+                op_pub,
+                op_priv_user: op_pub.user + other_user as u128,
+                domain,
+                submitter,
+            },
+        )
+}
+
+#[derive(Clone, Copy, Debug)]
+enum Field {
+    Deposit,
+    Amount,
+    Token,
+    User,
+    ValidUntil,
+    OpPrivUser,
+    NewTrapdoor,
+    NewNullifierKey,
+    OldNullifierKey,
+    Balance,
+    MerklePath,
+    LeafId,
+    Domain,
+    Submitter,
+}
+
+const FIELDS: [Field; 14] = [
+    Field::Deposit,
+    Field::Amount,
+    Field::Token,
+    Field::User,
+    Field::ValidUntil,
+    Field::OpPrivUser,
+    Field::NewTrapdoor,
+    Field::NewNullifierKey,
+    Field::OldNullifierKey,
+    Field::Balance,
+    Field::MerklePath,
+    Field::LeafId,
+    Field::Domain,
+    Field::Submitter,
+];
+
+/// Sets `field` of `w` to other value, based on `other`
+fn mutate(w: &mut Witness, field: Field, other: u128) {
+    let flip = |value: u128| value ^ (other | 1);
+    match field {
+        Field::Deposit => w.op_pub.deposit = !w.op_pub.deposit,
+        Field::Amount => w.op_pub.amount = flip(w.op_pub.amount),
+        Field::Token => w.op_pub.token = flip(w.op_pub.token),
+        Field::User => w.op_pub.user = flip(w.op_pub.user),
+        Field::ValidUntil => {
+            w.op_pub.valid_until = Some(w.op_pub.valid_until.map_or(0, |v| v.wrapping_add(1)))
+        }
+        Field::OpPrivUser => w.op_priv_user = flip(w.op_priv_user),
+        Field::NewTrapdoor => w.new_trapdoor = flip(w.new_trapdoor),
+        Field::NewNullifierKey => w.new_nullifier_key = flip(w.new_nullifier_key),
+        Field::OldNullifierKey => w.old_nullifier_key = flip(w.old_nullifier_key),
+        Field::Balance => w.balances[0] = flip(w.balances[0]),
+        Field::MerklePath => {
+            let level = other as usize % TREE_HEIGHT;
+            w.merkle_path[level] = flip(w.merkle_path[level]);
+        }
+        Field::LeafId => w.leaf_id = flip(w.leaf_id as u128) as u32 % (1 << TREE_HEIGHT),
+        Field::Domain => w.domain = flip(w.domain),
+        Field::Submitter => {
+            w.submitter = match w.submitter {
+                Some(_) => None,
+                None => Some(other | 1),
+            }
+        }
+    }
+}
+
+/// Witness accepted by both backends
+fn valid_witness() -> Witness {
+    Witness {
+        id: 1,
+        old_trapdoor: 2,
+        old_nullifier_key: 3,
+        new_trapdoor: 4,
+        new_nullifier_key: 5,
+        tokens: [1, 0],
+        balances: [100, 0],
+        merkle_path: core::array::from_fn(|level| level as u128),
+        leaf_id: 5,
+        op_pub: OpPub {
+            deposit: false,
+            amount: 10,
+            token: 1,
+            user: 6,
+            valid_until: Some(7),
+        },
+        op_priv_user: 6,
+        domain: 8,
+        submitter: Some(9),
+    }
+}
+
+fn assert_agree(case: &Case) -> bool {
+    let accepted = mocked::accepts(case);
+    assert_eq!(accepted, halo2::accepts(case), "{case:?}");
+    accepted
+}
+
+#[test]
+fn valid_update_accepted() {
+    assert!(assert_agree(&Case::honest(valid_witness())));
+}
+
+#[test]
+fn wire_instance_matches_circuit() {
+    let valid = valid_witness();
+    let never_expiring = Witness {
+        op_pub: OpPub {
+            deposit: true,
+            valid_until: None,
+            ..valid.op_pub
+        },
+        submitter: None,
+        ..valid
+    };
+    for witness in [valid, never_expiring] {
+        assert_eq!(
+            halo2::wire_instance(&witness),
+            Some(halo2::instance(&witness))
+        );
+    }
+}
+
+#[test]
+fn invalid_operations_rejected() {
+    let valid = valid_witness();
+    let underflow = Witness {
+        op_pub: OpPub {
+            amount: 101,
+            ..valid.op_pub
+        },
+        ..valid
+    };
+    let overflow = Witness {
+        op_pub: OpPub {
+            deposit: true,
+            amount: u128::MAX,
+            ..valid.op_pub
+        },
+        ..valid
+    };
+    let unknown_token = Witness {
+        op_pub: OpPub {
+            token: 2,
+            ..valid.op_pub
+        },
+        ..valid
+    };
+    let other_user = Witness {
+        op_priv_user: 7,
+        ..valid
+    };
+    for witness in [underflow, overflow, unknown_token, other_user] {
+        assert!(!assert_agree(&Case::honest(witness)));
+    }
+}
+
+#[test]
+fn mutated_fields_rejected() {
+    for field in FIELDS {
+        let mut mutated = valid_witness();
+        mutate(&mut mutated, field, 2);
+        assert!(
+            !assert_agree(&Case {
+                witness: mutated,
+                claimed: valid_witness(),
+            }),
+            "{field:?}"
+        );
+        // private user is not a public input
+        if !matches!(field, Field::OpPrivUser) {
+            assert!(
+                !assert_agree(&Case {
+                    witness: valid_witness(),
+                    claimed: mutated,
+                }),
+                "{field:?}"
+            );
+        }
+    }
+}
+
+proptest! {
+    // each case runs `MockProver`
+    #![proptest_config(ProptestConfig::with_cases(16))]
+
+    #[test]
+    fn backends_agree(
+        witness in witness(),
+        mutation in option::of((select(FIELDS.as_slice()), any::<bool>(), any::<u128>())),
+    ) {
+        let mut case = Case::honest(witness);
+        if let Some((field, claimed, other)) = mutation {
+            mutate(if claimed { &mut case.claimed } else { &mut case.witness }, field, other);
+        }
+        prop_assert_eq!(mocked::accepts(&case), halo2::accepts(&case), "{:?}", case);
+    }
+
+    #[test]
+    fn wire_instance_agrees_with_circuit(witness in witness()) {
+        prop_assert_eq!(halo2::wire_instance(&witness), Some(halo2::instance(&witness)));
+    }
+
+    #[test]
+    fn native_validation_agrees_with_circuit(witness in witness()) {
+        prop_assert_eq!(
+            halo2::validates(&witness),
+            halo2::accepts(&Case::honest(witness)),
+            "{:?}",
+            witness
+        );
+    }
+}
@@ -0,0 +1,146 @@
+//! Local index of the shielder state rebuilt from `NoteAdded` events
+//!
+//! The contract exposes only the current root and paths of single leaves. The indexer keeps
+//! all leaves and spent nullifiers in a local store, so wallets can build Merkle paths and
+//! check their notes offline. After every block its root is compared with `notes_merkle_root`.
+pub mod tree;
+
+#[cfg(test)]
+mod tests;
+
+use std::{collections::HashSet, fs, io, path::Path};
+
+use mocked_zk::{Scalar, MERKLE_TREE_DEPTH};
+use shielder_client::events::NoteAdded;
+
+use crate::tree::MirrorTree;
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum IndexerError {
+    /// Events are missing or repeated
+    UnexpectedLeafId {
+        expected: u32,
+        actual: u32,
+    },
+    TreeFull,
+    NullifierReused(Scalar),
+    /// Local tree differs from the one stored in the contract
+    RootMismatch {
+        block: u32,
+        local: Scalar,
+        contract: Scalar,
+    },
+}
+
+/// Persisted part of the index, the tree is rebuilt from `leaves`
+#[derive(Debug, Clone, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct IndexerState {
+    /// last block whose events were indexed
+    pub block: Option<u32>,
+    /// note hashes, ordered by leaf id
+    pub leaves: Vec<Scalar>,
+    pub nullifiers: HashSet<Scalar>,
+}
+
+#[derive(Debug, Default)]
+pub struct Indexer {
+    state: IndexerState,
+    tree: MirrorTree<MERKLE_TREE_DEPTH>,
+}
+
+impl Indexer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn from_state(state: IndexerState) -> Result<Self, IndexerError> {
+        let mut tree = MirrorTree::default();
+        for leaf in &state.leaves {
+            tree.add_leaf(*leaf).ok_or(IndexerError::TreeFull)?;
+        }
+        Ok(Self { state, tree })
+    }
+
+    pub fn state(&self) -> &IndexerState {
+        &self.state
+    }
+
+    pub fn load(path: &Path) -> io::Result<Self> {
+        let json = fs::read_to_string(path)?;
+        let state = serde_json::from_str(&json).map_err(io::Error::other)?;
+        Self::from_state(state)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, format!("{err:?}")))
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let json = serde_json::to_string_pretty(&self.state).map_err(io::Error::other)?;
+        fs::write(path, json)
+    }
+
+    /// Applies a single event, events have to be processed in order of emission
+    /// Rejected event leaves the index unchanged
+    pub fn process_event(&mut self, event: &NoteAdded) -> Result<(), IndexerError> {
+        let expected = self.tree.leaves_number();
+        if event.leaf_id != expected {
+            return Err(IndexerError::UnexpectedLeafId {
+                expected,
+                actual: event.leaf_id,
+            });
+        }
+        if let Some(nullifier) = event
+            .nullifier_old
+            .filter(|nullifier| self.state.nullifiers.contains(nullifier))
+        {
+            return Err(IndexerError::NullifierReused(nullifier));
+        }
+        self.tree
+            .add_leaf(event.note_hash)
+            .ok_or(IndexerError::TreeFull)?;
+        self.state.nullifiers.extend(event.nullifier_old);
+        self.state.leaves.push(event.note_hash);
+        Ok(())
+    }
+
+    /// Applies all events of `block` and checks the result against `notes_merkle_root`
+    /// On error the index is inconsistent and should be reloaded from the store
+    pub fn process_block<'a>(
+        &mut self,
+        block: u32,
+        events: impl IntoIterator<Item = &'a NoteAdded>,
+        merkle_root: Scalar,
+    ) -> Result<(), IndexerError> {
+        for event in events {
+            self.process_event(event)?;
+        }
+        if self.root() != merkle_root {
+            return Err(IndexerError::RootMismatch {
+                block,
+                local: self.root(),
+                contract: merkle_root,
+            });
+        }
+        self.state.block = Some(block);
+        Ok(())
+    }
+
+    pub fn root(&self) -> Scalar {
+        self.tree.root()
+    }
+
+    pub fn leaves_number(&self) -> u32 {
+        self.tree.leaves_number()
+    }
+
+    pub fn leaf(&self, leaf_id: u32) -> Option<Scalar> {
+        self.state.leaves.get(leaf_id as usize).copied()
+    }
+
+    /// Same as `notes_merkle_path` of the contract, `None` for leaves not indexed yet
+    pub fn merkle_path(&self, leaf_id: u32) -> Option<[Scalar; MERKLE_TREE_DEPTH]> {
+        self.tree.path(leaf_id)
+    }
+
+    pub fn is_spent(&self, nullifier: Scalar) -> bool {
+        self.state.nullifiers.contains(&nullifier)
+    }
+}
@@ -0,0 +1,121 @@
+use mocked_zk::{combine_merkle_hash, Scalar, MERKLE_TREE_DEPTH};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use shielder_client::events::NoteAdded;
+
+use crate::{Indexer, IndexerError};
+
+fn note_added(leaf_id: u32, nullifier_old: Option<Scalar>) -> NoteAdded {
+    NoteAdded {
+        leaf_id,
+        note_hash: (leaf_id as u128 + 1).into(),
+        nullifier_old,
+        memo: vec![],
+    }
+}
+
+fn root(leaves: &[Scalar]) -> Scalar {
+    let mut level: Vec<Scalar> = leaves.to_vec();
+    for _ in 0..MERKLE_TREE_DEPTH {
+        if level.len() % 2 == 1 {
+            level.push(0_u128.into());
+        }
+        level = level
+            .chunks(2)
+            .map(|pair| combine_merkle_hash(pair[0], pair[1]))
+            .collect();
+    }
+    level[0]
+}
+
+#[test]
+fn test_process_blocks() -> Result<(), IndexerError> {
+    let mut rng = StdRng::seed_from_u64(0);
+    let mut indexer = Indexer::new();
+    let mut leaves = vec![];
+    for block in 1..5 {
+        let events: Vec<_> = (0..block)
+            .map(|_| {
+                let leaf_id = leaves.len() as u32;
+                let event = note_added(leaf_id, Some(Scalar::from_bytes(rng.gen())));
+                leaves.push(event.note_hash);
+                event
+            })
+            .collect();
+        let merkle_root = root(&leaves);
+        indexer.process_block(block, &events, merkle_root)?;
+        assert_eq!(indexer.root(), merkle_root);
+        assert_eq!(indexer.state().block, Some(block));
+        for event in &events {
+            assert!(indexer.is_spent(event.nullifier_old.unwrap()));
+        }
+    }
+    assert_eq!(indexer.leaves_number(), 10);
+    assert_eq!(indexer.leaf(3), Some(leaves[3]));
+    assert!(indexer.merkle_path(9).is_some());
+    assert!(indexer.merkle_path(10).is_none());
+    Ok(())
+}
+
+#[test]
+fn test_process_block_errors() {
+    let mut indexer = Indexer::new();
+    let nullifier = 1_u128.into();
+    let events = [note_added(0, None), note_added(1, Some(nullifier))];
+    let merkle_root = root(&[events[0].note_hash, events[1].note_hash]);
+
+    assert_eq!(
+        indexer.process_block(0, &events[1..], merkle_root),
+        Err(IndexerError::UnexpectedLeafId {
+            expected: 0,
+            actual: 1
+        })
+    );
+    assert_eq!(
+        indexer.process_block(0, &events[..1], merkle_root),
+        Err(IndexerError::RootMismatch {
+            block: 0,
+            local: root(&[events[0].note_hash]),
+            contract: merkle_root,
+        })
+    );
+    indexer.process_block(1, &events[1..], merkle_root).unwrap();
+    assert_eq!(
+        indexer.process_event(&note_added(2, Some(nullifier))),
+        Err(IndexerError::NullifierReused(nullifier))
+    );
+}
+
+#[test]
+fn test_full_tree_keeps_nullifier_unspent() {
+    let mut indexer = Indexer::new();
+    let size = 1 << MERKLE_TREE_DEPTH;
+    for leaf_id in 0..size {
+        indexer.process_event(&note_added(leaf_id, None)).unwrap();
+    }
+
+    let nullifier = 1_u128.into();
+    assert_eq!(
+        indexer.process_event(&note_added(size, Some(nullifier))),
+        Err(IndexerError::TreeFull)
+    );
+    assert!(!indexer.is_spent(nullifier));
+    assert_eq!(indexer.leaves_number(), size);
+    assert_eq!(indexer.state().leaves.len(), size as usize);
+}
+
+#[test]
+fn test_persisted() -> Result<(), IndexerError> {
+    let mut indexer = Indexer::new();
+    let events = [note_added(0, None), note_added(1, Some(1_u128.into()))];
+    let merkle_root = root(&[events[0].note_hash, events[1].note_hash]);
+    indexer.process_block(3, &events, merkle_root)?;
+
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("index.json");
+    indexer.save(&path).unwrap();
+    let restored = Indexer::load(&path).unwrap();
+    assert_eq!(restored.state(), indexer.state());
+    assert_eq!(restored.root(), merkle_root);
+    assert_eq!(restored.merkle_path(1), indexer.merkle_path(1));
+    Ok(())
+}
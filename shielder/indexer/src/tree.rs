@@ -0,0 +1,95 @@
+//! Mirror of the notes Merkle tree kept by the shielder contract
+use std::collections::HashMap;
+
+use mocked_zk::{combine_merkle_hash, Scalar};
+
+/// Nodes are indexed as in the contract: root is 1, children of `i` are `2i` and `2i + 1`,
+/// leaf `j` is `2^DEPTH + j`. Nodes of empty subtrees are 0.
+#[derive(Debug, Clone, Default)]
+pub struct MirrorTree<const DEPTH: usize> {
+    nodes: HashMap<u32, Scalar>,
+    leaves_number: u32,
+}
+
+impl<const DEPTH: usize> MirrorTree<DEPTH> {
+    const SIZE: u32 = 1 << DEPTH;
+
+    fn node_value(&self, id: u32) -> Scalar {
+        self.nodes.get(&id).copied().unwrap_or(0_u128.into())
+    }
+
+    pub fn leaves_number(&self) -> u32 {
+        self.leaves_number
+    }
+
+    /// Returns id of the added leaf, `None` if the tree is full
+    pub fn add_leaf(&mut self, leaf_value: Scalar) -> Option<u32> {
+        if self.leaves_number == Self::SIZE {
+            return None;
+        }
+        let leaf_id = self.leaves_number;
+        let mut id = leaf_id + Self::SIZE;
+        self.nodes.insert(id, leaf_value);
+        id /= 2;
+        while id > 0 {
+            let hash = combine_merkle_hash(self.node_value(2 * id), self.node_value(2 * id + 1));
+            self.nodes.insert(id, hash);
+            id /= 2;
+        }
+        self.leaves_number += 1;
+        Some(leaf_id)
+    }
+
+    pub fn root(&self) -> Scalar {
+        self.node_value(1)
+    }
+
+    /// Siblings of nodes on the path from leaf to root, `None` for leaves not added yet
+    pub fn path(&self, leaf_id: u32) -> Option<[Scalar; DEPTH]> {
+        if leaf_id >= self.leaves_number {
+            return None;
+        }
+        let mut path = [0_u128.into(); DEPTH];
+        let mut id = leaf_id + Self::SIZE;
+        for node in path.iter_mut() {
+            *node = self.node_value(id ^ 1);
+            id /= 2;
+        }
+        Some(path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use mocked_zk::merkle_root_from_path;
+
+    use super::*;
+
+    #[test]
+    fn add_two_leaves_and_root() {
+        let mut tree = MirrorTree::<10>::default();
+        assert_eq!(tree.add_leaf(1_u128.into()), Some(0));
+        assert_eq!(tree.add_leaf(2_u128.into()), Some(1));
+
+        let mut hash = combine_merkle_hash(1_u128.into(), 2_u128.into());
+        for _i in 1..10 {
+            hash = combine_merkle_hash(hash, 0_u128.into());
+        }
+        assert_eq!(hash, tree.root());
+    }
+
+    #[test]
+    fn paths_lead_to_root() {
+        let mut tree = MirrorTree::<4>::default();
+        for i in 0..(1 << 4) {
+            tree.add_leaf((i as u128 + 1).into()).unwrap();
+            for leaf_id in 0..=i {
+                let path = tree.path(leaf_id).unwrap();
+                let leaf = (leaf_id as u128 + 1).into();
+                assert_eq!(merkle_root_from_path(leaf, path, leaf_id), tree.root());
+            }
+            assert_eq!(tree.path(i + 1), None);
+        }
+        assert_eq!(tree.add_leaf(0_u128.into()), None);
+    }
+}
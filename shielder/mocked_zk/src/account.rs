@@ -1,4 +1,7 @@
-use ink::env::hash::{CryptoHash, Sha2x256};
+use ink::{
+    env::hash::{CryptoHash, Sha2x256},
+    scale::Encode,
+};
 
 use crate::{
     errors::ZkpError,
@@ -7,30 +10,40 @@ use crate::{
     Scalar, TOKENS_NUMBER,
 };
 
+/// Balances of `TOKENS` tokens, the default deployment supports `TOKENS_NUMBER` tokens
 #[ink::scale_derive(Encode, Decode, TypeInfo)]
-#[derive(Debug, Default, Clone, Copy, serde::Serialize, serde::Deserialize)]
-pub struct Account {
-    balances: [(Scalar, Scalar); TOKENS_NUMBER],
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct Account<const TOKENS: usize = TOKENS_NUMBER> {
+    #[serde(with = "crate::serde_array")]
+    balances: [(Scalar, Scalar); TOKENS],
+}
+
+impl<const TOKENS: usize> Default for Account<TOKENS> {
+    fn default() -> Self {
+        Self::new([0_u128.into(); TOKENS])
+    }
 }
 
-impl Hashable for Account {
+impl<const TOKENS: usize> Hashable for Account<TOKENS> {
+    /// Binds all tokens and their balances
+    /// Notes hold this hash, code spending existing notes has to compute it the same way
     fn hash(&self) -> Scalar {
         let mut res = [0x0; 32];
-        for i in 1..TOKENS_NUMBER {
-            Sha2x256::hash(self.balances[i].1.bytes.as_slice(), &mut res);
-        }
+        Sha2x256::hash(self.balances.encode().as_slice(), &mut res);
         Scalar::from_bytes(res)
     }
 }
 
-impl Account {
-    pub fn new(tokens: [Scalar; TOKENS_NUMBER]) -> Self {
-        let mut balances: [(Scalar, Scalar); TOKENS_NUMBER] =
-            [(0_u128.into(), 0_u128.into()); TOKENS_NUMBER];
-        for i in 0..TOKENS_NUMBER {
-            balances[i] = (tokens[i], 0_u128.into());
+impl<const TOKENS: usize> Account<TOKENS> {
+    pub fn new(tokens: [Scalar; TOKENS]) -> Self {
+        Self {
+            balances: tokens.map(|token| (token, 0_u128.into())),
         }
-        Self { balances }
+    }
+
+    /// Pairs of token and its balance
+    pub fn balances(&self) -> [(Scalar, Scalar); TOKENS] {
+        self.balances
     }
 
     pub fn update(&self, operation: Operation) -> Result<Self, ZkpError> {
@@ -44,7 +57,7 @@ impl Account {
                     if token == op_token {
                         let balance_upd: Scalar = ((u128::from(balance))
                             .checked_add(op_amount)
-                            .ok_or(ZkpError::AccountUpdateError)?)
+                            .ok_or(ZkpError::BalanceOverflow)?)
                         .into();
                         let mut balances_upd = self.balances;
                         balances_upd[i] = (token, balance_upd);
@@ -53,7 +66,7 @@ impl Account {
                         });
                     }
                 }
-                Err(ZkpError::AccountUpdateError)
+                Err(ZkpError::UnknownToken)
             }
             OpPub::Withdraw {
                 amount: op_amount,
@@ -64,7 +77,7 @@ impl Account {
                     if token == op_token {
                         let balance_upd: Scalar = ((u128::from(balance))
                             .checked_sub(op_amount)
-                            .ok_or(ZkpError::AccountUpdateError)?)
+                            .ok_or(ZkpError::BalanceUnderflow)?)
                         .into();
                         let mut balances_upd = self.balances;
                         balances_upd[i] = (token, balance_upd);
@@ -73,7 +86,7 @@ impl Account {
                         });
                     }
                 }
-                Err(ZkpError::AccountUpdateError)
+                Err(ZkpError::UnknownToken)
             }
         }
     }
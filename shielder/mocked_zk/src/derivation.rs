@@ -0,0 +1,103 @@
+//! Derivation of note secrets from a single wallet seed
+//!
+//! Account `id` is derived from the seed, `trapdoor` and `nullifier_key` of the n-th note of the
+//! account are derived from the seed and counter `n`. Only the seed has to be backed up:
+//! wallet recomputes secrets of its consecutive notes and, knowing the account state
+//! (e.g. from memos), finds their hashes among the tree leaves.
+use ink::env::hash::{CryptoHash, Sha2x256};
+
+use crate::{note::Note, Scalar};
+
+const ID_TAG: u8 = 0;
+const TRAPDOOR_TAG: u8 = 1;
+const NULLIFIER_TAG: u8 = 2;
+
+/// Master secret of a wallet
+#[ink::scale_derive(Encode, Decode, TypeInfo)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct Seed {
+    bytes: [u8; 32],
+}
+
+impl Seed {
+    pub const fn from_bytes(bytes: [u8; 32]) -> Self {
+        Self { bytes }
+    }
+
+    pub fn to_bytes(&self) -> [u8; 32] {
+        self.bytes
+    }
+
+    /// Id of the account, the same for all its notes
+    pub fn id(&self) -> Scalar {
+        self.derive(&[ID_TAG])
+    }
+
+    pub fn trapdoor(&self, counter: u32) -> Scalar {
+        self.derive(&[&[TRAPDOOR_TAG][..], &counter.to_le_bytes()].concat())
+    }
+
+    pub fn nullifier_key(&self, counter: u32) -> Scalar {
+        self.derive(&[&[NULLIFIER_TAG][..], &counter.to_le_bytes()].concat())
+    }
+
+    /// Note number `counter` of the account in state with `account_hash`
+    pub fn note(&self, counter: u32, account_hash: Scalar) -> Note {
+        Note::new(
+            self.id(),
+            self.trapdoor(counter),
+            self.nullifier_key(counter),
+            account_hash,
+        )
+    }
+
+    fn derive(&self, input: &[u8]) -> Scalar {
+        let mut res = [0x0; 32];
+        Sha2x256::hash([&self.bytes[..], input].concat().as_slice(), &mut res);
+        Scalar::from_bytes(res)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use crate::{derivation::Seed, traits::Hashable};
+
+    const COUNTERS: u32 = 1000;
+
+    #[test]
+    fn test_derivation_is_deterministic() {
+        let seed = Seed::from_bytes([0x1; 32]);
+        let restored = Seed::from_bytes(seed.to_bytes());
+        assert_eq!(seed.id(), restored.id());
+        for counter in 0..COUNTERS {
+            assert_eq!(seed.trapdoor(counter), restored.trapdoor(counter));
+            assert_eq!(seed.nullifier_key(counter), restored.nullifier_key(counter));
+        }
+    }
+
+    #[test]
+    fn test_derived_values_never_collide() {
+        let seeds = [[0x0; 32], [0x1; 32], [0x2; 32]].map(Seed::from_bytes);
+        let mut values = HashSet::new();
+        for seed in seeds {
+            assert!(values.insert(seed.id().bytes));
+            for counter in 0..COUNTERS {
+                assert!(values.insert(seed.trapdoor(counter).bytes));
+                assert!(values.insert(seed.nullifier_key(counter).bytes));
+            }
+        }
+        assert_eq!(values.len(), seeds.len() * (1 + 2 * COUNTERS as usize));
+    }
+
+    #[test]
+    fn test_derived_notes_never_collide() {
+        let seed = Seed::from_bytes([0x1; 32]);
+        let account_hash = 0_u128.into();
+        let mut hashes = HashSet::new();
+        for counter in 0..COUNTERS {
+            assert!(hashes.insert(seed.note(counter, account_hash).hash().bytes));
+        }
+    }
+}
@@ -1,7 +1,26 @@
 #[ink::scale_derive(Encode, Decode, TypeInfo)]
-#[derive(PartialEq, Debug)]
+#[derive(PartialEq, Debug, Clone, Copy)]
 pub enum ZkpError {
-    AccountUpdateError,
+    /// public and private parts of operation refer to different users
     OperationCombineError,
-    VerificationError,
+    /// token of operation is not held by the account
+    UnknownToken,
+    /// deposit overflows the balance
+    BalanceOverflow,
+    /// withdrawal exceeds the balance
+    BalanceUnderflow,
+    NoteHashMismatch,
+    AccountHashMismatch,
+    OperationHashMismatch,
+    /// note is not a leaf of the tree with given root
+    MerklePathMismatch,
+    NullifierMismatch,
+    DomainMismatch,
+    SubmitterMismatch,
+    /// proof is not in the wire format
+    MalformedProof,
+    /// proof is in other version of the wire format
+    UnsupportedProofVersion,
+    /// proof was made by a backend not supported by the verifier
+    UnsupportedProofSystem,
 }
@@ -1,19 +1,26 @@
 #![cfg_attr(not(feature = "std"), no_std, no_main)]
 pub mod account;
+pub mod derivation;
 pub mod errors;
 pub mod note;
 pub mod ops;
+#[cfg(test)]
+mod proptests;
 pub mod relations;
 mod scalar;
+pub mod serde_array;
 #[cfg(test)]
 mod tests;
 pub mod traits;
+pub mod wire;
 
 use ink::env::hash::{CryptoHash, Sha2x256};
 
 pub type Scalar = scalar::Scalar;
 
+/// Depth of the notes tree of the default deployment, see `relations::ZkProof`
 pub const MERKLE_TREE_DEPTH: usize = 10;
+/// Number of tokens of accounts of the default deployment, see `account::Account`
 pub const TOKENS_NUMBER: usize = 2;
 pub const MOCKED_TOKEN: Scalar = Scalar::from_bytes([228_u8; 32]);
 
@@ -21,8 +28,55 @@ pub fn mocked_user() -> Scalar {
     1_u128.into()
 }
 
+/// Identifies shielder deployment, proofs are valid only for the domain they were created for
+///
+/// The digest is truncated to 253 bits, below the order of the BN254 scalar field, so that
+/// the domain is a public input of halo2 circuits as it is.
+pub fn domain_separator(contract: Scalar, chain_tag: Scalar) -> Scalar {
+    let mut res = [0x0; 32];
+    Sha2x256::hash(
+        [contract.bytes, chain_tag.bytes].concat().as_slice(),
+        &mut res,
+    );
+    res[31] &= 0x1f;
+    Scalar::from_bytes(res)
+}
+
+/// Nullifier of the note with `nullifier_key` placed at `leaf_id` of the tree
+/// The same note at other position has other nullifier
+pub fn derive_nullifier(nullifier_key: Scalar, leaf_id: u32) -> Scalar {
+    let mut res = [0x0; 32];
+    Sha2x256::hash(
+        [&nullifier_key.bytes[..], &leaf_id.to_le_bytes()]
+            .concat()
+            .as_slice(),
+        &mut res,
+    );
+    Scalar::from_bytes(res)
+}
+
 pub fn combine_merkle_hash(first: Scalar, second: Scalar) -> Scalar {
     let mut res = [0x0; 32];
     Sha2x256::hash([first.bytes, second.bytes].concat().as_slice(), &mut res);
     Scalar::from_bytes(res)
 }
+
+/// Root of the tree with `leaf` at `leaf_id`, `path` holds siblings from the bottom level up
+///
+/// Bits of `leaf_id` above `DEPTH` are ignored.
+pub fn merkle_root_from_path<const DEPTH: usize>(
+    leaf: Scalar,
+    path: [Scalar; DEPTH],
+    leaf_id: u32,
+) -> Scalar {
+    let mut id = leaf_id;
+    path.into_iter().fold(leaf, |node, sibling| {
+        let parent = if id % 2 == 0 {
+            combine_merkle_hash(node, sibling)
+        } else {
+            combine_merkle_hash(sibling, node)
+        };
+        id /= 2;
+        parent
+    })
+}
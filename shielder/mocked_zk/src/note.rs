@@ -3,23 +3,40 @@ use ink::env::hash::{CryptoHash, Sha2x256};
 use crate::{traits::Hashable, Scalar};
 
 #[ink::scale_derive(Encode, Decode, TypeInfo)]
-#[derive(Clone, Copy, serde::Serialize, serde::Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
 pub struct Note {
     id: Scalar,
     trapdoor: Scalar,
-    nullifier: Scalar,
+    /// secret from which nullifier of the note is derived
+    nullifier_key: Scalar,
     account_hash: Scalar,
 }
 
 impl Note {
-    pub fn new(id: Scalar, trapdoor: Scalar, nullifier: Scalar, account_hash: Scalar) -> Self {
+    pub fn new(id: Scalar, trapdoor: Scalar, nullifier_key: Scalar, account_hash: Scalar) -> Self {
         Self {
             id,
             trapdoor,
-            nullifier,
+            nullifier_key,
             account_hash,
         }
     }
+
+    pub fn id(&self) -> Scalar {
+        self.id
+    }
+
+    pub fn trapdoor(&self) -> Scalar {
+        self.trapdoor
+    }
+
+    pub fn nullifier_key(&self) -> Scalar {
+        self.nullifier_key
+    }
+
+    pub fn account_hash(&self) -> Scalar {
+        self.account_hash
+    }
 }
 
 impl Hashable for Note {
@@ -29,7 +46,7 @@ impl Hashable for Note {
             [
                 self.id.bytes,
                 self.trapdoor.bytes,
-                self.nullifier.bytes,
+                self.nullifier_key.bytes,
                 self.account_hash.bytes,
             ]
             .concat()
@@ -1,8 +1,13 @@
-use crate::{errors::ZkpError, Scalar};
+use ink::{
+    env::hash::{CryptoHash, Sha2x256},
+    scale::Encode,
+};
+
+use crate::{errors::ZkpError, traits::Hashable, Scalar};
 
 /// Enum
 #[ink::scale_derive(Encode, Decode, TypeInfo)]
-#[derive(Debug, Clone, Copy, serde::Serialize, serde::Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
 pub enum OpPub {
     /// Deposit PSP-22 token
     Deposit {
@@ -12,6 +17,8 @@ pub enum OpPub {
         token: Scalar,
         /// User address, from whom tokens are transferred
         user: Scalar,
+        /// Last block number in which operation can be applied
+        valid_until: Option<u32>,
     },
     /// Withdraw PSP-22 token
     Withdraw {
@@ -21,9 +28,29 @@ pub enum OpPub {
         token: Scalar,
         /// User address, to who the tokens are transferred
         user: Scalar,
+        /// Last block number in which operation can be applied
+        valid_until: Option<u32>,
     },
 }
 
+impl OpPub {
+    pub fn valid_until(&self) -> Option<u32> {
+        match self {
+            OpPub::Deposit { valid_until, .. } | OpPub::Withdraw { valid_until, .. } => {
+                *valid_until
+            }
+        }
+    }
+}
+
+impl Hashable for OpPub {
+    fn hash(&self) -> Scalar {
+        let mut res = [0x0; 32];
+        Sha2x256::hash(self.encode().as_slice(), &mut res);
+        Scalar::from_bytes(res)
+    }
+}
+
 /// empty private operation
 #[ink::scale_derive(Encode, Decode, TypeInfo)]
 #[derive(Debug, Clone, Copy, serde::Serialize, serde::Deserialize)]
@@ -0,0 +1,331 @@
+//! Property-based tests of accounts and mocked relations on random operation sequences
+use proptest::{collection::vec, option, prelude::*, sample::select};
+
+use crate::{
+    account::Account,
+    derive_nullifier,
+    errors::ZkpError,
+    merkle_root_from_path, mocked_user,
+    note::Note,
+    ops::{OpPriv, OpPub, Operation},
+    relations::ZkProof,
+    tests::{deposit, supported_tokens},
+    traits::Hashable,
+    Scalar, MERKLE_TREE_DEPTH, MOCKED_TOKEN,
+};
+
+const OTHER_TOKEN: Scalar = Scalar::from_bytes([0x3; 32]);
+
+fn scalar() -> impl Strategy<Value = Scalar> {
+    any::<[u8; 32]>().prop_map(Scalar::from_bytes)
+}
+
+/// Mostly small amounts, so that withdrawals can succeed, sometimes overflowing ones
+fn amount() -> impl Strategy<Value = u128> {
+    prop_oneof![9 => 0..100_u128, 1 => any::<u128>()]
+}
+
+fn op_pub() -> impl Strategy<Value = OpPub> {
+    (
+        any::<bool>(),
+        amount(),
+        prop_oneof![4 => Just(MOCKED_TOKEN), 1 => Just(OTHER_TOKEN)],
+        option::of(any::<u32>()),
+    )
+        .prop_map(|(is_deposit, amount, token, valid_until)| {
+            let user = mocked_user();
+            if is_deposit {
+                OpPub::Deposit {
+                    amount,
+                    token,
+                    user,
+                    valid_until,
+                }
+            } else {
+                OpPub::Withdraw {
+                    amount,
+                    token,
+                    user,
+                    valid_until,
+                }
+            }
+        })
+}
+
+fn operation(op_pub: OpPub) -> Operation {
+    Operation::combine(
+        op_pub,
+        OpPriv {
+            user: mocked_user(),
+        },
+    )
+    .unwrap()
+}
+
+/// Secrets of the new note and position of the old one
+#[derive(Debug, Clone)]
+struct Secrets {
+    trapdoor: Scalar,
+    nullifier_key: Scalar,
+    merkle_proof: [Scalar; MERKLE_TREE_DEPTH],
+    leaf_id: u32,
+    submitter: Option<Scalar>,
+}
+
+fn secrets() -> impl Strategy<Value = Secrets> {
+    (
+        scalar(),
+        scalar(),
+        vec(scalar(), MERKLE_TREE_DEPTH),
+        0..(1_u32 << MERKLE_TREE_DEPTH),
+        option::of(scalar()),
+    )
+        .prop_map(
+            |(trapdoor, nullifier_key, merkle_proof, leaf_id, submitter)| Secrets {
+                trapdoor,
+                nullifier_key,
+                merkle_proof: merkle_proof.try_into().unwrap(),
+                leaf_id,
+                submitter,
+            },
+        )
+}
+
+/// Balance of `MOCKED_TOKEN` after `op_pub`, as the account should compute it
+fn model_update(balance: u128, op_pub: OpPub) -> Result<u128, ZkpError> {
+    match op_pub {
+        OpPub::Deposit { amount, token, .. } if token == MOCKED_TOKEN => {
+            balance.checked_add(amount).ok_or(ZkpError::BalanceOverflow)
+        }
+        OpPub::Withdraw { amount, token, .. } if token == MOCKED_TOKEN => balance
+            .checked_sub(amount)
+            .ok_or(ZkpError::BalanceUnderflow),
+        _ => Err(ZkpError::UnknownToken),
+    }
+}
+
+/// Public inputs of `update_note` together with the proof
+#[derive(Debug, Clone)]
+struct Update {
+    proof: ZkProof,
+    op_pub: OpPub,
+    h_note_new: Scalar,
+    merkle_root: Scalar,
+    nullifier_old: Scalar,
+    domain: Scalar,
+    submitter: Option<Scalar>,
+}
+
+impl Update {
+    /// Updates note `h_note` placed at `secrets.leaf_id`, whose proof is `proof`
+    fn new(
+        proof: ZkProof,
+        h_note: Scalar,
+        nullifier_key: Scalar,
+        op_pub: OpPub,
+        secrets: &Secrets,
+        domain: Scalar,
+    ) -> Result<Self, ZkpError> {
+        let (h_note_new, proof) = proof.update_account(
+            operation(op_pub),
+            secrets.trapdoor,
+            secrets.nullifier_key,
+            secrets.merkle_proof,
+            secrets.leaf_id,
+            secrets.submitter,
+        )?;
+        Ok(Self {
+            proof,
+            op_pub,
+            h_note_new,
+            merkle_root: merkle_root_from_path(h_note, secrets.merkle_proof, secrets.leaf_id),
+            nullifier_old: derive_nullifier(nullifier_key, secrets.leaf_id),
+            domain,
+            submitter: secrets.submitter,
+        })
+    }
+
+    fn verify(&self) -> Result<(), ZkpError> {
+        self.proof.verify_update(
+            self.op_pub,
+            self.h_note_new,
+            self.merkle_root,
+            self.nullifier_old,
+            self.domain,
+            self.submitter,
+        )
+    }
+}
+
+/// Note of a new account holding `balance` of `MOCKED_TOKEN`
+fn new_note(id: Scalar, balance: u128, secrets: &Secrets, domain: Scalar) -> (Scalar, ZkProof) {
+    let account = Account::new(supported_tokens())
+        .update(deposit(balance))
+        .unwrap();
+    let note = Note::new(id, secrets.trapdoor, secrets.nullifier_key, account.hash());
+    let proof = ZkProof::new(
+        id,
+        secrets.trapdoor,
+        secrets.nullifier_key,
+        OpPriv {
+            user: mocked_user(),
+        },
+        account,
+        domain,
+    );
+    (note.hash(), proof)
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Field {
+    Amount,
+    Token,
+    User,
+    ValidUntil,
+    NoteHash,
+    MerkleRoot,
+    Nullifier,
+    Domain,
+    Submitter,
+}
+
+const FIELDS: [Field; 9] = [
+    Field::Amount,
+    Field::Token,
+    Field::User,
+    Field::ValidUntil,
+    Field::NoteHash,
+    Field::MerkleRoot,
+    Field::Nullifier,
+    Field::Domain,
+    Field::Submitter,
+];
+
+fn op_pub_fields(op_pub: &mut OpPub) -> (&mut u128, &mut Scalar, &mut Scalar, &mut Option<u32>) {
+    match op_pub {
+        OpPub::Deposit {
+            amount,
+            token,
+            user,
+            valid_until,
+        }
+        | OpPub::Withdraw {
+            amount,
+            token,
+            user,
+            valid_until,
+        } => (amount, token, user, valid_until),
+    }
+}
+
+/// Sets `field` of `update` to other value, based on `other`
+fn mutate(update: &mut Update, field: Field, other: Scalar) {
+    let (amount, token, user, valid_until) = op_pub_fields(&mut update.op_pub);
+    let other_scalar = |value: Scalar| {
+        if other == value {
+            Scalar::from_bytes(other.bytes.map(|byte| !byte))
+        } else {
+            other
+        }
+    };
+    match field {
+        Field::Amount => *amount = amount.wrapping_add(u128::from(other) | 1),
+        Field::Token => *token = other_scalar(*token),
+        Field::User => *user = other_scalar(*user),
+        Field::ValidUntil => *valid_until = Some(valid_until.map_or(0, |v| v.wrapping_add(1))),
+        Field::NoteHash => update.h_note_new = other_scalar(update.h_note_new),
+        Field::MerkleRoot => update.merkle_root = other_scalar(update.merkle_root),
+        Field::Nullifier => update.nullifier_old = other_scalar(update.nullifier_old),
+        Field::Domain => update.domain = other_scalar(update.domain),
+        Field::Submitter => {
+            update.submitter = match update.submitter {
+                Some(_) => None,
+                None => Some(other),
+            }
+        }
+    }
+}
+
+proptest! {
+    #[test]
+    fn account_update_matches_model(ops in vec(op_pub(), 0..64)) {
+        let mut account = Account::new(supported_tokens());
+        let mut balance = 0;
+        for op_pub in ops {
+            let expected = model_update(balance, op_pub);
+            let updated = account.update(operation(op_pub));
+            prop_assert_eq!(
+                updated.map(|account| u128::from(account.balances()[0].1)),
+                expected
+            );
+            if let (Ok(updated), Ok(expected)) = (updated, expected) {
+                account = updated;
+                balance = expected;
+            }
+            prop_assert_eq!(account.balances()[0].0, MOCKED_TOKEN);
+            prop_assert_eq!(account.balances()[1], (0_u128.into(), 0_u128.into()));
+        }
+    }
+
+    #[test]
+    fn account_hash_binds_tokens_and_balances(
+        first in amount(),
+        second in amount(),
+        token in prop_oneof![Just(MOCKED_TOKEN), scalar()],
+    ) {
+        let first_account = Account::new(supported_tokens()).update(deposit(first)).unwrap();
+        let second_account = Account::new(supported_tokens()).update(deposit(second)).unwrap();
+        prop_assert_eq!(first_account.hash() == second_account.hash(), first == second);
+
+        let mut tokens = supported_tokens();
+        tokens[0] = token;
+        prop_assert_eq!(
+            Account::new(tokens).hash() == Account::new(supported_tokens()).hash(),
+            token == MOCKED_TOKEN
+        );
+    }
+
+    #[test]
+    fn updates_verify_along_random_history(
+        id in scalar(),
+        domain in scalar(),
+        initial in secrets(),
+        steps in vec((op_pub(), secrets()), 1..16),
+    ) {
+        let (mut h_note, mut proof) = new_note(id, 0, &initial, domain);
+        let mut nullifier_key = initial.nullifier_key;
+        let mut balance = 0;
+        for (op_pub, secrets) in steps {
+            let expected = model_update(balance, op_pub);
+            let update = Update::new(proof, h_note, nullifier_key, op_pub, &secrets, domain);
+            prop_assert_eq!(update.as_ref().err(), expected.as_ref().err());
+            if let (Ok(update), Ok(expected)) = (update, expected) {
+                prop_assert_eq!(update.verify(), Ok(()));
+                h_note = update.h_note_new;
+                proof = update.proof;
+                nullifier_key = secrets.nullifier_key;
+                balance = expected;
+            }
+        }
+    }
+
+    #[test]
+    fn verification_fails_for_any_mutation(
+        id in scalar(),
+        domain in scalar(),
+        initial in secrets(),
+        op_pub in op_pub(),
+        secrets in secrets(),
+        field in select(FIELDS.as_slice()),
+        other in scalar(),
+    ) {
+        let (h_note, proof) = new_note(id, 100, &initial, domain);
+        let update = Update::new(proof, h_note, initial.nullifier_key, op_pub, &secrets, domain);
+        prop_assume!(update.is_ok());
+        let mut update = update.unwrap();
+        prop_assert_eq!(update.verify(), Ok(()));
+
+        mutate(&mut update, field, other);
+        prop_assert!(update.verify().is_err());
+    }
+}
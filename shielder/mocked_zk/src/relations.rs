@@ -1,7 +1,8 @@
 use crate::{
     account::Account,
-    combine_merkle_hash,
+    derive_nullifier,
     errors::ZkpError,
+    merkle_root_from_path,
     note::Note,
     ops::{OpPriv, OpPub, Operation},
     traits::Hashable,
@@ -11,88 +12,119 @@ use crate::{
 /// mocked proof of knowledge, not ZK
 /// you can imagine ZkProof object as someone's "knowledge"
 /// functions starting with verify_ are mocks of relation
+/// `DEPTH` is the depth of the notes tree, at most 32 as leaf ids are `u32`,
+/// and `TOKENS` the number of tokens of accounts
+/// proofs are valid only for a deployment with the same parameters
 #[ink::scale_derive(Encode, Decode, TypeInfo)]
 #[derive(Debug, Clone, Copy, serde::Serialize, serde::Deserialize)]
-pub struct ZkProof {
+pub struct ZkProof<const DEPTH: usize = MERKLE_TREE_DEPTH, const TOKENS: usize = TOKENS_NUMBER> {
     id: Scalar,
     trapdoor_new: Scalar,
     trapdoor_old: Scalar,
-    nullifier_new: Scalar,
-    acc_old: Account,
-    acc_new: Account,
+    nullifier_key_new: Scalar,
+    nullifier_key_old: Scalar,
+    acc_old: Account<TOKENS>,
+    /// hash of the account held by the old note, `acc_old` has to match it
+    h_acc_old: Scalar,
+    acc_new: Account<TOKENS>,
     op_priv: OpPriv,
-    merkle_proof: [Scalar; MERKLE_TREE_DEPTH],
+    #[serde(with = "crate::serde_array")]
+    merkle_proof: [Scalar; DEPTH],
     merkle_proof_leaf_id: u32,
+    domain: Scalar,
+    submitter: Option<Scalar>,
+    h_op_pub: Scalar,
 }
 
-pub fn verify_hash<T: Hashable>(to_hash: T, hash: Scalar) -> Result<Scalar, ZkpError> {
+/// Fails with `error` if `to_hash` doesn't hash to `hash`
+pub fn verify_hash<T: Hashable>(
+    to_hash: T,
+    hash: Scalar,
+    error: ZkpError,
+) -> Result<Scalar, ZkpError> {
     let real_hash = to_hash.hash();
     if real_hash != hash {
-        return Err(ZkpError::VerificationError);
+        return Err(error);
     }
     Ok(real_hash)
 }
 
-impl ZkProof {
+impl<const DEPTH: usize, const TOKENS: usize> ZkProof<DEPTH, TOKENS> {
     pub fn new(
         id: Scalar,
         trapdoor: Scalar,
-        nullifier: Scalar,
+        nullifier_key: Scalar,
         op_priv: OpPriv,
-        acc: Account,
+        acc: Account<TOKENS>,
+        domain: Scalar,
     ) -> Self {
         Self {
             id,
             trapdoor_new: trapdoor,
-            nullifier_new: nullifier,
+            nullifier_key_new: nullifier_key,
             acc_new: acc,
             trapdoor_old: 0_u128.into(),
+            nullifier_key_old: 0_u128.into(),
             acc_old: acc,
+            h_acc_old: acc.hash(),
             op_priv,
-            merkle_proof: [0_u128.into(); MERKLE_TREE_DEPTH],
+            merkle_proof: [0_u128.into(); DEPTH],
             merkle_proof_leaf_id: 0,
+            domain,
+            submitter: None,
+            h_op_pub: 0_u128.into(),
         }
     }
 
+    #[allow(clippy::too_many_arguments)]
     fn transition(
         &self,
         trapdoor: Scalar,
-        nullifier: Scalar,
-        acc: Account,
-        op_priv: OpPriv,
-        merkle_proof: [Scalar; MERKLE_TREE_DEPTH],
+        nullifier_key: Scalar,
+        acc: Account<TOKENS>,
+        operation: Operation,
+        merkle_proof: [Scalar; DEPTH],
         merkle_proof_leaf_id: u32,
+        submitter: Option<Scalar>,
     ) -> Self {
         Self {
             id: self.id,
             trapdoor_new: trapdoor,
             trapdoor_old: self.trapdoor_new,
-            nullifier_new: nullifier,
+            nullifier_key_new: nullifier_key,
+            nullifier_key_old: self.nullifier_key_new,
             acc_new: acc,
             acc_old: self.acc_new,
-            op_priv,
+            h_acc_old: self.acc_new.hash(),
+            op_priv: operation.op_priv,
             merkle_proof,
             merkle_proof_leaf_id,
+            domain: self.domain,
+            submitter,
+            h_op_pub: operation.op_pub.hash(),
         }
     }
 
+    /// `submitter` restricts who can submit the update, `None` allows anyone
     pub fn update_account(
         &self,
         operation: Operation,
         trapdoor: Scalar,
-        nullifier: Scalar,
-        merkle_proof: [Scalar; MERKLE_TREE_DEPTH],
+        nullifier_key: Scalar,
+        merkle_proof: [Scalar; DEPTH],
         merkle_proof_leaf_id: u32,
+        submitter: Option<Scalar>,
     ) -> Result<(Scalar, Self), ZkpError> {
         let acc_updated = self.acc_new.update(operation)?;
-        let note = Note::new(self.id, trapdoor, nullifier, acc_updated.hash());
+        let note = Note::new(self.id, trapdoor, nullifier_key, acc_updated.hash());
         let new_proof = self.transition(
             trapdoor,
-            nullifier,
+            nullifier_key,
             acc_updated,
-            operation.op_priv,
+            operation,
             merkle_proof,
             merkle_proof_leaf_id,
+            submitter,
         );
         Ok((note.hash(), new_proof))
     }
@@ -101,25 +133,42 @@ impl ZkProof {
         &self,
         op: Operation,
         h_acc_old: Scalar,
-    ) -> Result<Account, ZkpError> {
+    ) -> Result<Account<TOKENS>, ZkpError> {
         let acc_new = self.acc_old.update(op)?;
-        verify_hash(self.acc_old, h_acc_old)?;
+        verify_hash(self.acc_old, h_acc_old, ZkpError::AccountHashMismatch)?;
         Ok(acc_new)
     }
 
     fn verify_merkle_proof(&self, h_note_old: Scalar, merkle_root: Scalar) -> Result<(), ZkpError> {
-        let mut id = self.merkle_proof_leaf_id;
-        let mut scalar = h_note_old;
-        for node in self.merkle_proof {
-            if id % 2 == 0 {
-                scalar = combine_merkle_hash(scalar, node);
-            } else {
-                scalar = combine_merkle_hash(node, scalar);
-            }
-            id /= 2;
+        let root = merkle_root_from_path(h_note_old, self.merkle_proof, self.merkle_proof_leaf_id);
+        // leaf id has to be a position in the tree, as it determines nullifier
+        let outside_tree = self
+            .merkle_proof_leaf_id
+            .checked_shr(DEPTH as u32)
+            .is_some_and(|id| id != 0);
+        if outside_tree || root != merkle_root {
+            return Err(ZkpError::MerklePathMismatch);
         }
-        if scalar != merkle_root {
-            return Err(ZkpError::VerificationError);
+        Ok(())
+    }
+
+    fn verify_nullifier(&self, nullifier: Scalar) -> Result<(), ZkpError> {
+        if derive_nullifier(self.nullifier_key_old, self.merkle_proof_leaf_id) != nullifier {
+            return Err(ZkpError::NullifierMismatch);
+        }
+        Ok(())
+    }
+
+    fn verify_domain(&self, domain: Scalar) -> Result<(), ZkpError> {
+        if self.domain != domain {
+            return Err(ZkpError::DomainMismatch);
+        }
+        Ok(())
+    }
+
+    fn verify_submitter(&self, submitter: Option<Scalar>) -> Result<(), ZkpError> {
+        if self.submitter != submitter {
+            return Err(ZkpError::SubmitterMismatch);
         }
         Ok(())
     }
@@ -127,11 +176,18 @@ impl ZkProof {
     pub fn verify_creation(
         &self,
         h_note_new: Scalar,
-        tokens_list: [Scalar; TOKENS_NUMBER],
+        tokens_list: [Scalar; TOKENS],
+        domain: Scalar,
     ) -> Result<(), ZkpError> {
+        self.verify_domain(domain)?;
         let h_acc_new = Account::new(tokens_list).hash();
-        let note_new = Note::new(self.id, self.trapdoor_new, self.nullifier_new, h_acc_new);
-        verify_hash(note_new, h_note_new)?;
+        let note_new = Note::new(
+            self.id,
+            self.trapdoor_new,
+            self.nullifier_key_new,
+            h_acc_new,
+        );
+        verify_hash(note_new, h_note_new, ZkpError::NoteHashMismatch)?;
         Ok(())
     }
 
@@ -141,16 +197,31 @@ impl ZkProof {
         h_note_new: Scalar,
         merkle_root: Scalar,
         nullifier_old: Scalar,
+        domain: Scalar,
+        submitter: Option<Scalar>,
     ) -> Result<(), ZkpError> {
-        let h_acc_old = self.acc_old.hash();
+        self.verify_domain(domain)?;
+        self.verify_submitter(submitter)?;
         let op = Operation::combine(op_pub, self.op_priv)?;
-        let acc_new = self.verify_acccount_update(op, h_acc_old)?;
+        let acc_new = self.verify_acccount_update(op, self.h_acc_old)?;
         let h_acc_new = acc_new.hash();
-        let note_new = Note::new(self.id, self.trapdoor_new, self.nullifier_new, h_acc_new);
-        verify_hash(note_new, h_note_new)?;
-        let note_old = Note::new(self.id, self.trapdoor_old, nullifier_old, h_acc_old);
+        let note_new = Note::new(
+            self.id,
+            self.trapdoor_new,
+            self.nullifier_key_new,
+            h_acc_new,
+        );
+        verify_hash(note_new, h_note_new, ZkpError::NoteHashMismatch)?;
+        let note_old = Note::new(
+            self.id,
+            self.trapdoor_old,
+            self.nullifier_key_old,
+            self.h_acc_old,
+        );
         let h_note_old = note_old.hash();
         self.verify_merkle_proof(h_note_old, merkle_root)?;
+        self.verify_nullifier(nullifier_old)?;
+        verify_hash(op_pub, self.h_op_pub, ZkpError::OperationHashMismatch)?;
         Ok(())
     }
 }
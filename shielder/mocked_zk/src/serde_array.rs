@@ -0,0 +1,50 @@
+//! Serde of arrays of any length, in the same format serde uses for arrays up to 32 elements
+//! Used as `#[serde(with = "crate::serde_array")]`, shared with `liminal-halo2-relations`
+use core::{fmt, marker::PhantomData};
+
+use serde::{
+    de::{Error, SeqAccess, Visitor},
+    ser::SerializeTuple,
+    Deserialize, Deserializer, Serialize, Serializer,
+};
+
+pub fn serialize<S: Serializer, T: Serialize, const N: usize>(
+    array: &[T; N],
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    let mut tuple = serializer.serialize_tuple(N)?;
+    for element in array {
+        tuple.serialize_element(element)?;
+    }
+    tuple.end()
+}
+
+struct ArrayVisitor<T, const N: usize>(PhantomData<T>);
+
+impl<'de, T: Deserialize<'de>, const N: usize> Visitor<'de> for ArrayVisitor<T, N> {
+    type Value = [T; N];
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter, "an array of length {N}")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        let mut elements = ink::prelude::vec::Vec::with_capacity(N);
+        while let Some(element) = seq.next_element()? {
+            if elements.len() == N {
+                return Err(A::Error::invalid_length(N + 1, &self));
+            }
+            elements.push(element);
+        }
+        let len = elements.len();
+        elements
+            .try_into()
+            .map_err(|_| A::Error::invalid_length(len, &self))
+    }
+}
+
+pub fn deserialize<'de, D: Deserializer<'de>, T: Deserialize<'de>, const N: usize>(
+    deserializer: D,
+) -> Result<[T; N], D::Error> {
+    deserializer.deserialize_tuple(N, ArrayVisitor(PhantomData))
+}
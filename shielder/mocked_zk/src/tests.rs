@@ -1,51 +1,526 @@
+use ink::scale::{Decode, Encode};
+
 use crate::{
-    account::Account, errors::ZkpError, mocked_user, note::Note, ops::OpPriv, relations::ZkProof,
-    traits::Hashable, Scalar, MOCKED_TOKEN, TOKENS_NUMBER,
+    account::Account,
+    derive_nullifier, domain_separator,
+    errors::ZkpError,
+    merkle_root_from_path, mocked_user,
+    note::Note,
+    ops::{OpPriv, OpPub, Operation},
+    relations::ZkProof,
+    traits::Hashable,
+    Scalar, MERKLE_TREE_DEPTH, MOCKED_TOKEN, TOKENS_NUMBER,
 };
 
-fn supported_tokens() -> [Scalar; TOKENS_NUMBER] {
+pub(crate) fn supported_tokens() -> [Scalar; TOKENS_NUMBER] {
     let mut tokens: [Scalar; TOKENS_NUMBER] = [0_u128.into(); TOKENS_NUMBER];
     tokens[0] = MOCKED_TOKEN;
     tokens
 }
 
-fn create_empty_note_proof(id: Scalar, nullifier: Scalar, trapdoor: Scalar) -> (Scalar, ZkProof) {
+fn domain() -> Scalar {
+    domain_separator(Scalar::from_bytes([0x1; 32]), 0_u128.into())
+}
+
+fn other_domain() -> Scalar {
+    domain_separator(Scalar::from_bytes([0x2; 32]), 0_u128.into())
+}
+
+fn create_empty_note_proof(
+    id: Scalar,
+    nullifier_key: Scalar,
+    trapdoor: Scalar,
+) -> (Scalar, ZkProof) {
     let acc_new = Account::new(supported_tokens());
-    let note = Note::new(id, trapdoor, nullifier, acc_new.hash());
+    let note = Note::new(id, trapdoor, nullifier_key, acc_new.hash());
     let proof = ZkProof::new(
         id,
         trapdoor,
-        nullifier,
+        nullifier_key,
         OpPriv {
             user: mocked_user(),
         },
         acc_new,
+        domain(),
     );
     (note.hash(), proof)
 }
 
+pub(crate) fn deposit(amount: u128) -> Operation {
+    let op_pub = OpPub::Deposit {
+        amount,
+        token: MOCKED_TOKEN,
+        user: mocked_user(),
+        valid_until: None,
+    };
+    Operation::combine(
+        op_pub,
+        OpPriv {
+            user: mocked_user(),
+        },
+    )
+    .unwrap()
+}
+
 #[test]
 fn test_create_note() -> Result<(), ZkpError> {
     let id = 0_u128.into();
-    let nullifier = 0_u128.into();
+    let nullifier_key = 0_u128.into();
     let trapdoor = 0_u128.into();
-    let (h_new_note, proof) = create_empty_note_proof(id, nullifier, trapdoor);
-    proof.verify_creation(h_new_note, supported_tokens())?;
+    let (h_new_note, proof) = create_empty_note_proof(id, nullifier_key, trapdoor);
+    proof.verify_creation(h_new_note, supported_tokens(), domain())?;
     Ok(())
 }
 
 #[test]
 fn test_create_note_fails() -> Result<(), ZkpError> {
     let id = 0_u128.into();
-    let nullifier = 0_u128.into();
+    let nullifier_key = 0_u128.into();
+    let trapdoor = 0_u128.into();
+    let (_, proof) = create_empty_note_proof(id, nullifier_key, trapdoor);
+    let (h_new_note, _) = create_empty_note_proof(1_u128.into(), nullifier_key, trapdoor);
+    assert_eq!(
+        ZkpError::NoteHashMismatch,
+        proof
+            .verify_creation(h_new_note, supported_tokens(), domain())
+            .unwrap_err()
+    );
+    Ok(())
+}
+
+#[test]
+fn test_create_note_fails_for_other_domain() -> Result<(), ZkpError> {
+    let id = 0_u128.into();
+    let nullifier_key = 0_u128.into();
     let trapdoor = 0_u128.into();
-    let (_, proof) = create_empty_note_proof(id, nullifier, trapdoor);
-    let (h_new_note, _) = create_empty_note_proof(1_u128.into(), nullifier, trapdoor);
+    let (h_new_note, proof) = create_empty_note_proof(id, nullifier_key, trapdoor);
     assert_eq!(
-        ZkpError::VerificationError,
+        ZkpError::DomainMismatch,
         proof
-            .verify_creation(h_new_note, supported_tokens())
+            .verify_creation(h_new_note, supported_tokens(), other_domain())
             .unwrap_err()
     );
     Ok(())
 }
+
+#[test]
+fn test_update_note() -> Result<(), ZkpError> {
+    let id = 0_u128.into();
+    let nullifier_key = 0_u128.into();
+    let trapdoor = 0_u128.into();
+    let (h_note, proof) = create_empty_note_proof(id, nullifier_key, trapdoor);
+    let merkle_proof = [0_u128.into(); MERKLE_TREE_DEPTH];
+    let operation = deposit(10);
+    let (h_note_new, proof) = proof.update_account(
+        operation,
+        1_u128.into(),
+        1_u128.into(),
+        merkle_proof,
+        0,
+        None,
+    )?;
+    proof.verify_update(
+        operation.op_pub,
+        h_note_new,
+        merkle_root_from_path(h_note, merkle_proof, 0),
+        derive_nullifier(nullifier_key, 0),
+        domain(),
+        None,
+    )?;
+    Ok(())
+}
+
+#[test]
+fn test_update_note_fails_for_other_domain() -> Result<(), ZkpError> {
+    let id = 0_u128.into();
+    let nullifier_key = 0_u128.into();
+    let trapdoor = 0_u128.into();
+    let (h_note, proof) = create_empty_note_proof(id, nullifier_key, trapdoor);
+    let merkle_proof = [0_u128.into(); MERKLE_TREE_DEPTH];
+    let operation = deposit(10);
+    let (h_note_new, proof) = proof.update_account(
+        operation,
+        1_u128.into(),
+        1_u128.into(),
+        merkle_proof,
+        0,
+        None,
+    )?;
+    assert_eq!(
+        ZkpError::DomainMismatch,
+        proof
+            .verify_update(
+                operation.op_pub,
+                h_note_new,
+                merkle_root_from_path(h_note, merkle_proof, 0),
+                derive_nullifier(nullifier_key, 0),
+                other_domain(),
+                None,
+            )
+            .unwrap_err()
+    );
+    Ok(())
+}
+
+#[test]
+fn test_update_note_fails_for_account_not_in_old_note() -> Result<(), ZkpError> {
+    let id = 0_u128.into();
+    let nullifier_key = 0_u128.into();
+    let trapdoor = 0_u128.into();
+    let (h_note, proof) = create_empty_note_proof(id, nullifier_key, trapdoor);
+    let merkle_proof = [0_u128.into(); MERKLE_TREE_DEPTH];
+    let operation = deposit(10);
+    let (h_note_new, proof) = proof.update_account(
+        operation,
+        1_u128.into(),
+        1_u128.into(),
+        merkle_proof,
+        0,
+        None,
+    )?;
+    // claims the old note held the updated account
+    let mut json = serde_json::to_value(proof).unwrap();
+    json["acc_old"] = json["acc_new"].clone();
+    let proof: ZkProof = serde_json::from_value(json).unwrap();
+    assert_eq!(
+        ZkpError::AccountHashMismatch,
+        proof
+            .verify_update(
+                operation.op_pub,
+                h_note_new,
+                merkle_root_from_path(h_note, merkle_proof, 0),
+                derive_nullifier(nullifier_key, 0),
+                domain(),
+                None,
+            )
+            .unwrap_err()
+    );
+    Ok(())
+}
+
+#[test]
+fn test_update_note_fails_for_other_submitter() -> Result<(), ZkpError> {
+    let id = 0_u128.into();
+    let nullifier_key = 0_u128.into();
+    let trapdoor = 0_u128.into();
+    let (h_note, proof) = create_empty_note_proof(id, nullifier_key, trapdoor);
+    let merkle_proof = [0_u128.into(); MERKLE_TREE_DEPTH];
+    let operation = deposit(10);
+    let submitter = Some(2_u128.into());
+    let (h_note_new, proof) = proof.update_account(
+        operation,
+        1_u128.into(),
+        1_u128.into(),
+        merkle_proof,
+        0,
+        submitter,
+    )?;
+    let merkle_root = merkle_root_from_path(h_note, merkle_proof, 0);
+    for other_submitter in [None, Some(3_u128.into())] {
+        assert_eq!(
+            ZkpError::SubmitterMismatch,
+            proof
+                .verify_update(
+                    operation.op_pub,
+                    h_note_new,
+                    merkle_root,
+                    derive_nullifier(nullifier_key, 0),
+                    domain(),
+                    other_submitter,
+                )
+                .unwrap_err()
+        );
+    }
+    proof.verify_update(
+        operation.op_pub,
+        h_note_new,
+        merkle_root,
+        derive_nullifier(nullifier_key, 0),
+        domain(),
+        submitter,
+    )?;
+    Ok(())
+}
+
+#[test]
+fn test_update_note_fails_for_other_deadline() -> Result<(), ZkpError> {
+    let id = 0_u128.into();
+    let nullifier_key = 0_u128.into();
+    let trapdoor = 0_u128.into();
+    let (h_note, proof) = create_empty_note_proof(id, nullifier_key, trapdoor);
+    let merkle_proof = [0_u128.into(); MERKLE_TREE_DEPTH];
+    let operation = deposit(10);
+    let (h_note_new, proof) = proof.update_account(
+        operation,
+        1_u128.into(),
+        1_u128.into(),
+        merkle_proof,
+        0,
+        None,
+    )?;
+    let op_pub = OpPub::Deposit {
+        amount: 10,
+        token: MOCKED_TOKEN,
+        user: mocked_user(),
+        valid_until: Some(100),
+    };
+    assert_eq!(
+        ZkpError::OperationHashMismatch,
+        proof
+            .verify_update(
+                op_pub,
+                h_note_new,
+                merkle_root_from_path(h_note, merkle_proof, 0),
+                derive_nullifier(nullifier_key, 0),
+                domain(),
+                None,
+            )
+            .unwrap_err()
+    );
+    Ok(())
+}
+
+#[test]
+fn test_update_note_nullifier_depends_on_leaf_id() -> Result<(), ZkpError> {
+    let id = 0_u128.into();
+    let nullifier_key = 0_u128.into();
+    let trapdoor = 0_u128.into();
+    let (h_note, proof) = create_empty_note_proof(id, nullifier_key, trapdoor);
+    let operation = deposit(10);
+    assert_ne!(
+        derive_nullifier(nullifier_key, 0),
+        derive_nullifier(nullifier_key, 1)
+    );
+
+    // the same note placed at leaf 0 and at leaf 1
+    let sibling = 1_u128.into();
+    let mut merkle_proof = [0_u128.into(); MERKLE_TREE_DEPTH];
+    merkle_proof[0] = sibling;
+    for (leaf_id, other_leaf_id) in [(0, 1), (1, 0)] {
+        let (h_note_new, proof) = proof.update_account(
+            operation,
+            1_u128.into(),
+            1_u128.into(),
+            merkle_proof,
+            leaf_id,
+            None,
+        )?;
+        let merkle_root = merkle_root_from_path(h_note, merkle_proof, leaf_id);
+        assert_eq!(
+            ZkpError::NullifierMismatch,
+            proof
+                .verify_update(
+                    operation.op_pub,
+                    h_note_new,
+                    merkle_root,
+                    derive_nullifier(nullifier_key, other_leaf_id),
+                    domain(),
+                    None,
+                )
+                .unwrap_err()
+        );
+        proof.verify_update(
+            operation.op_pub,
+            h_note_new,
+            merkle_root,
+            derive_nullifier(nullifier_key, leaf_id),
+            domain(),
+            None,
+        )?;
+    }
+    Ok(())
+}
+
+#[test]
+fn test_update_note_fails_for_leaf_id_outside_tree() -> Result<(), ZkpError> {
+    let id = 0_u128.into();
+    let nullifier_key = 0_u128.into();
+    let trapdoor = 0_u128.into();
+    let (h_note, proof) = create_empty_note_proof(id, nullifier_key, trapdoor);
+    let merkle_proof = [0_u128.into(); MERKLE_TREE_DEPTH];
+    let operation = deposit(10);
+    // the same path as leaf 0, but other nullifier
+    let leaf_id = 1 << MERKLE_TREE_DEPTH;
+    let (h_note_new, proof) = proof.update_account(
+        operation,
+        1_u128.into(),
+        1_u128.into(),
+        merkle_proof,
+        leaf_id,
+        None,
+    )?;
+    assert_eq!(
+        ZkpError::MerklePathMismatch,
+        proof
+            .verify_update(
+                operation.op_pub,
+                h_note_new,
+                merkle_root_from_path(h_note, merkle_proof, leaf_id),
+                derive_nullifier(nullifier_key, leaf_id),
+                domain(),
+                None,
+            )
+            .unwrap_err()
+    );
+    Ok(())
+}
+
+#[test]
+fn test_account_update_errors() -> Result<(), ZkpError> {
+    let account = Account::new(supported_tokens()).update(deposit(10))?;
+    let other_token = Scalar::from_bytes([0x3; 32]);
+    let op = |op_pub| {
+        Operation::combine(
+            op_pub,
+            OpPriv {
+                user: mocked_user(),
+            },
+        )
+    };
+    let withdraw = |amount, token| OpPub::Withdraw {
+        amount,
+        token,
+        user: mocked_user(),
+        valid_until: None,
+    };
+
+    assert_eq!(
+        account.update(op(withdraw(1, other_token))?),
+        Err(ZkpError::UnknownToken)
+    );
+    assert_eq!(
+        account.update(op(withdraw(11, MOCKED_TOKEN))?),
+        Err(ZkpError::BalanceUnderflow)
+    );
+    assert_eq!(
+        account.update(deposit(u128::MAX)),
+        Err(ZkpError::BalanceOverflow)
+    );
+    assert_eq!(
+        Operation::combine(withdraw(1, MOCKED_TOKEN), OpPriv { user: other_token }).err(),
+        Some(ZkpError::OperationCombineError)
+    );
+    Ok(())
+}
+
+#[test]
+fn test_account_hash_binds_tokens_and_balances() -> Result<(), ZkpError> {
+    let empty = Account::new(supported_tokens());
+    let mut other_tokens = supported_tokens();
+    other_tokens[1] = Scalar::from_bytes([0x3; 32]);
+
+    // deposits go to the first token, not the last one
+    assert_ne!(empty.update(deposit(10))?.hash(), empty.hash());
+    assert_ne!(
+        empty.update(deposit(10))?.hash(),
+        empty.update(deposit(11))?.hash()
+    );
+    assert_ne!(Account::new(other_tokens).hash(), empty.hash());
+    Ok(())
+}
+
+/// Creates an account holding `TOKENS` tokens, placed at the last leaf of a tree of depth `DEPTH`,
+/// and updates it with a deposit and a withdrawal of the last token
+fn update_note_with_params<const DEPTH: usize, const TOKENS: usize>() -> Result<(), ZkpError> {
+    let tokens: [Scalar; TOKENS] = core::array::from_fn(|i| Scalar::from_bytes([i as u8 + 1; 32]));
+    let token = tokens[TOKENS - 1];
+    let user = mocked_user();
+    let op = |op_pub| Operation::combine(op_pub, OpPriv { user });
+
+    let account = Account::new(tokens);
+    let note = Note::new(0_u128.into(), 0_u128.into(), 0_u128.into(), account.hash());
+    let mut proof = ZkProof::<DEPTH, TOKENS>::new(
+        0_u128.into(),
+        0_u128.into(),
+        0_u128.into(),
+        OpPriv { user },
+        account,
+        domain(),
+    );
+    proof.verify_creation(note.hash(), tokens, domain())?;
+
+    let leaf_id = u32::MAX >> (32 - DEPTH);
+    let merkle_proof: [Scalar; DEPTH] = core::array::from_fn(|level| (level as u128).into());
+    let mut h_note = note.hash();
+    let mut nullifier_key = 0_u128.into();
+    for (i, op_pub) in [
+        OpPub::Deposit {
+            amount: 10,
+            token,
+            user,
+            valid_until: None,
+        },
+        OpPub::Withdraw {
+            amount: 10,
+            token,
+            user,
+            valid_until: None,
+        },
+    ]
+    .into_iter()
+    .enumerate()
+    {
+        let nullifier_key_new = (i as u128 + 1).into();
+        let (h_note_new, proof_new) = proof.update_account(
+            op(op_pub)?,
+            nullifier_key_new,
+            nullifier_key_new,
+            merkle_proof,
+            leaf_id,
+            None,
+        )?;
+        let merkle_root = merkle_root_from_path(h_note, merkle_proof, leaf_id);
+        proof_new.verify_update(
+            op_pub,
+            h_note_new,
+            merkle_root,
+            derive_nullifier(nullifier_key, leaf_id),
+            domain(),
+            None,
+        )?;
+        (proof, h_note, nullifier_key) = (proof_new, h_note_new, nullifier_key_new);
+    }
+
+    // the first token is still empty
+    assert_eq!(
+        Account::new(tokens).update(op(OpPub::Withdraw {
+            amount: 1,
+            token: tokens[0],
+            user,
+            valid_until: None,
+        })?),
+        Err(ZkpError::BalanceUnderflow)
+    );
+
+    let encoded = proof.encode();
+    assert_eq!(
+        ZkProof::<DEPTH, TOKENS>::decode(&mut encoded.as_slice())
+            .unwrap()
+            .encode(),
+        encoded
+    );
+    let json = serde_json::to_string(&proof).unwrap();
+    let decoded: ZkProof<DEPTH, TOKENS> = serde_json::from_str(&json).unwrap();
+    assert_eq!(serde_json::to_string(&decoded).unwrap(), json);
+    Ok(())
+}
+
+#[test]
+fn test_update_note_smallest_params() -> Result<(), ZkpError> {
+    update_note_with_params::<1, 1>()
+}
+
+#[test]
+fn test_update_note_default_params() -> Result<(), ZkpError> {
+    update_note_with_params::<MERKLE_TREE_DEPTH, TOKENS_NUMBER>()
+}
+
+#[test]
+fn test_update_note_deep_tree_many_tokens() -> Result<(), ZkpError> {
+    update_note_with_params::<20, 8>()
+}
+
+#[test]
+fn test_update_note_params_beyond_serde_arrays() -> Result<(), ZkpError> {
+    update_note_with_params::<32, 40>()
+}
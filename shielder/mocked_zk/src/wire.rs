@@ -0,0 +1,442 @@
+//! Versioned wire format of proofs and public inputs
+//!
+//! Values are exchanged in two encodings of the same data model:
+//! - SCALE, used on chain: a single version byte followed by the SCALE encoding of the value
+//! - JSON, used by tooling: `{"version": <version>, "value": <serde encoding of the value>}`
+//!
+//! Version 1 covers:
+//! - `Proof`: either `Mocked` proof of this crate or opaque `Halo2` proof transcript
+//! - `UpdateNotePublic`: public inputs of `update_note`, for halo2 proofs every scalar is
+//!   the little-endian representation of a field element, see `UpdateNotePublic::instance`
+//!
+//! Any change of the encoding of these types, including types they are built of, has to bump
+//! `WIRE_VERSION`. Golden files in `golden/` pin the encodings of the current version.
+use ink::{
+    prelude::vec::Vec,
+    scale::{Decode, Encode},
+};
+
+use crate::{
+    errors::ZkpError, ops::OpPub, relations::ZkProof, Scalar, MERKLE_TREE_DEPTH, TOKENS_NUMBER,
+};
+
+pub const WIRE_VERSION: u8 = 1;
+
+/// Order of the BN254 scalar field of halo2 circuits, little-endian
+const BN254_MODULUS: [u8; 32] = [
+    0x01, 0x00, 0x00, 0xf0, 0x93, 0xf5, 0xe1, 0x43, 0x91, 0x70, 0xb9, 0x79, 0x48, 0xe8, 0x33, 0x28,
+    0x5d, 0x58, 0x81, 0x81, 0xb6, 0x45, 0x50, 0xb8, 0x29, 0xa0, 0x31, 0xe1, 0x72, 0x4e, 0x64, 0x30,
+];
+
+/// Whether `scalar` is the canonical representation of a BN254 scalar field element
+fn is_canonical(scalar: &Scalar) -> bool {
+    scalar.bytes.iter().rev().lt(BN254_MODULUS.iter().rev())
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WireError {
+    /// encoded with other version of the format
+    UnsupportedVersion(u8),
+    /// not an encoding of the expected type
+    Malformed,
+}
+
+impl From<WireError> for ZkpError {
+    fn from(err: WireError) -> Self {
+        match err {
+            WireError::UnsupportedVersion(_) => ZkpError::UnsupportedProofVersion,
+            WireError::Malformed => ZkpError::MalformedProof,
+        }
+    }
+}
+
+/// Proof of any backend, `DEPTH` and `TOKENS` are parameters of mocked proofs
+#[ink::scale_derive(Encode, Decode, TypeInfo)]
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Proof<const DEPTH: usize = MERKLE_TREE_DEPTH, const TOKENS: usize = TOKENS_NUMBER> {
+    Mocked(ZkProof<DEPTH, TOKENS>),
+    /// proof transcript of `update_note_circuit` or `new_account_circuit`
+    Halo2(Vec<u8>),
+}
+
+impl<const DEPTH: usize, const TOKENS: usize> Proof<DEPTH, TOKENS> {
+    /// Decodes SCALE wire encoding of a proof which has to be a mocked one
+    pub fn mocked_from_scale(bytes: &[u8]) -> Result<ZkProof<DEPTH, TOKENS>, ZkpError> {
+        match from_scale(bytes)? {
+            Self::Mocked(proof) => Ok(proof),
+            Self::Halo2(_) => Err(ZkpError::UnsupportedProofSystem),
+        }
+    }
+}
+
+/// Public inputs of `update_note`
+#[ink::scale_derive(Encode, Decode, TypeInfo)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct UpdateNotePublic {
+    pub op_pub: OpPub,
+    pub h_note_new: Scalar,
+    pub merkle_root: Scalar,
+    pub nullifier_old: Scalar,
+    pub domain: Scalar,
+    pub submitter: Option<Scalar>,
+}
+
+impl UpdateNotePublic {
+    /// Public inputs in the order of the instance column of `update_note_circuit`
+    /// `valid_until` is zero if operation doesn't expire and the last valid block plus one
+    /// otherwise, missing `submitter` is zero
+    ///
+    /// `None` if a scalar is not a canonical BN254 field element, e.g. `token` or `user` given
+    /// by an account id above the field order. Such statement can't be proven by a circuit and
+    /// is rejected rather than reduced, as reduction would map two accounts to the same element.
+    pub fn instance(&self) -> Option<Vec<Scalar>> {
+        let (deposit, amount, token, user, valid_until) = match self.op_pub {
+            OpPub::Deposit {
+                amount,
+                token,
+                user,
+                valid_until,
+            } => (1, amount, token, user, valid_until),
+            OpPub::Withdraw {
+                amount,
+                token,
+                user,
+                valid_until,
+            } => (0, amount, token, user, valid_until),
+        };
+        let valid_until = valid_until.map_or(0, |block| block as u128 + 1);
+        let instance = ink::prelude::vec![
+            deposit.into(),
+            amount.into(),
+            token,
+            user,
+            valid_until.into(),
+            self.h_note_new,
+            self.merkle_root,
+            self.nullifier_old,
+            self.domain,
+            self.submitter.unwrap_or(0_u128.into()),
+        ];
+        instance.iter().all(is_canonical).then_some(instance)
+    }
+}
+
+/// SCALE wire encoding of `value`
+pub fn to_scale<T: Encode>(value: &T) -> Vec<u8> {
+    let mut bytes = ink::prelude::vec![WIRE_VERSION];
+    value.encode_to(&mut bytes);
+    bytes
+}
+
+/// Decodes SCALE wire encoding, trailing bytes are rejected
+pub fn from_scale<T: Decode>(bytes: &[u8]) -> Result<T, WireError> {
+    let (&version, mut data) = bytes.split_first().ok_or(WireError::Malformed)?;
+    if version != WIRE_VERSION {
+        return Err(WireError::UnsupportedVersion(version));
+    }
+    let value = T::decode(&mut data).map_err(|_| WireError::Malformed)?;
+    if !data.is_empty() {
+        return Err(WireError::Malformed);
+    }
+    Ok(value)
+}
+
+/// JSON wire encoding of `value`
+#[cfg(feature = "std")]
+pub fn to_json<T: serde::Serialize>(value: &T) -> serde_json::Value {
+    serde_json::json!({
+        "version": WIRE_VERSION,
+        "value": value,
+    })
+}
+
+/// Decodes JSON wire encoding, version is checked before the value is parsed
+#[cfg(feature = "std")]
+pub fn from_json<T: serde::de::DeserializeOwned>(json: &serde_json::Value) -> Result<T, WireError> {
+    let version = json
+        .get("version")
+        .and_then(serde_json::Value::as_u64)
+        .ok_or(WireError::Malformed)?;
+    if version != WIRE_VERSION as u64 {
+        return Err(WireError::UnsupportedVersion(
+            version.try_into().unwrap_or(u8::MAX),
+        ));
+    }
+    let value = json.get("value").ok_or(WireError::Malformed)?;
+    serde_json::from_value(value.clone()).map_err(|_| WireError::Malformed)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, path::PathBuf};
+
+    use super::*;
+    use crate::{
+        account::Account,
+        combine_merkle_hash, derive_nullifier, domain_separator,
+        note::Note,
+        ops::{OpPriv, Operation},
+        traits::Hashable,
+    };
+
+    /// Compares `actual` with the golden file, which is rewritten if `UPDATE_GOLDEN` is set
+    fn check_golden(name: &str, actual: &str) {
+        let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("golden")
+            .join(name);
+        if std::env::var_os("UPDATE_GOLDEN").is_some() {
+            fs::write(&path, actual).unwrap();
+        }
+        let expected = fs::read_to_string(&path).unwrap();
+        assert_eq!(
+            actual, expected,
+            "encoding of {name} changed, bump WIRE_VERSION or rerun with UPDATE_GOLDEN=1"
+        );
+    }
+
+    fn scalar(byte: u8) -> Scalar {
+        Scalar::from_bytes([byte; 32])
+    }
+
+    fn op_pub() -> OpPub {
+        OpPub::Withdraw {
+            amount: 10,
+            token: scalar(0x11),
+            user: scalar(0x12),
+            valid_until: Some(100),
+        }
+    }
+
+    fn public() -> UpdateNotePublic {
+        UpdateNotePublic {
+            op_pub: op_pub(),
+            h_note_new: scalar(0x21),
+            merkle_root: scalar(0x22),
+            nullifier_old: scalar(0x23),
+            domain: scalar(0x24),
+            submitter: Some(scalar(0x25)),
+        }
+    }
+
+    /// Mocked proof together with public inputs it is valid for
+    /// Parameters are small to keep golden files short
+    fn mocked_update() -> (Proof<2, 2>, UpdateNotePublic) {
+        let op_priv = OpPriv::new(scalar(0x12));
+        let deposit = OpPub::Deposit {
+            amount: 50,
+            token: scalar(0x11),
+            user: scalar(0x12),
+            valid_until: None,
+        };
+        let account = Account::new([scalar(0x11), scalar(0x13)])
+            .update(Operation::combine(deposit, op_priv).unwrap())
+            .unwrap();
+        let (id, trapdoor, nullifier_key, domain) =
+            (scalar(0x31), scalar(0x32), scalar(0x33), scalar(0x24));
+        let h_note = Note::new(id, trapdoor, nullifier_key, account.hash()).hash();
+        let proof = ZkProof::new(id, trapdoor, nullifier_key, op_priv, account, domain);
+
+        let merkle_proof = [scalar(0x36), scalar(0x37)];
+        let submitter = Some(scalar(0x25));
+        let (h_note_new, proof) = proof
+            .update_account(
+                Operation::combine(op_pub(), op_priv).unwrap(),
+                scalar(0x34),
+                scalar(0x35),
+                merkle_proof,
+                1,
+                submitter,
+            )
+            .unwrap();
+        let public = UpdateNotePublic {
+            op_pub: op_pub(),
+            h_note_new,
+            merkle_root: combine_merkle_hash(
+                combine_merkle_hash(merkle_proof[0], h_note),
+                merkle_proof[1],
+            ),
+            nullifier_old: derive_nullifier(nullifier_key, 1),
+            domain,
+            submitter,
+        };
+        (Proof::Mocked(proof), public)
+    }
+
+    fn halo2_proof() -> Proof<2, 2> {
+        Proof::Halo2((0..16).collect())
+    }
+
+    fn check_proof(name: &str, proof: Proof<2, 2>) {
+        let scale = to_scale(&proof);
+        check_golden(
+            &format!("{name}.scale.hex"),
+            &format!("0x{}\n", hex::encode(&scale)),
+        );
+        let decoded: Proof<2, 2> = from_scale(&scale).unwrap();
+        assert_eq!(to_scale(&decoded), scale);
+
+        let json = to_json(&proof);
+        check_golden(
+            &format!("{name}.json"),
+            &(serde_json::to_string(&json).unwrap() + "\n"),
+        );
+        let decoded: Proof<2, 2> = from_json(&json).unwrap();
+        assert_eq!(to_json(&decoded), json);
+    }
+
+    #[test]
+    fn mocked_proof_matches_golden() {
+        check_proof("mocked_proof_v1", mocked_update().0);
+    }
+
+    #[test]
+    fn halo2_proof_matches_golden() {
+        check_proof("halo2_proof_v1", halo2_proof());
+    }
+
+    #[test]
+    fn update_note_public_matches_golden() {
+        let scale = to_scale(&public());
+        check_golden(
+            "update_note_public_v1.scale.hex",
+            &format!("0x{}\n", hex::encode(&scale)),
+        );
+        assert_eq!(from_scale(&scale), Ok(public()));
+
+        let json = to_json(&public());
+        check_golden(
+            "update_note_public_v1.json",
+            &(serde_json::to_string(&json).unwrap() + "\n"),
+        );
+        assert_eq!(from_json(&json), Ok(public()));
+    }
+
+    #[test]
+    fn mocked_proof_verifies_after_round_trip() {
+        let (proof, public) = mocked_update();
+        let proof = Proof::<2, 2>::mocked_from_scale(&to_scale(&proof)).unwrap();
+        let public: UpdateNotePublic = from_json(&to_json(&public)).unwrap();
+        assert_eq!(
+            proof.verify_update(
+                public.op_pub,
+                public.h_note_new,
+                public.merkle_root,
+                public.nullifier_old,
+                public.domain,
+                public.submitter,
+            ),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn instance_follows_circuit_order() {
+        let instance = public().instance();
+        assert_eq!(
+            instance.as_deref(),
+            Some(
+                &[
+                    0_u128.into(),
+                    10_u128.into(),
+                    scalar(0x11),
+                    scalar(0x12),
+                    101_u128.into(),
+                    scalar(0x21),
+                    scalar(0x22),
+                    scalar(0x23),
+                    scalar(0x24),
+                    scalar(0x25),
+                ][..]
+            )
+        );
+    }
+
+    #[test]
+    fn non_canonical_instance_rejected() {
+        let modulus = Scalar::from_bytes(BN254_MODULUS);
+        let mut below_modulus = modulus;
+        below_modulus.bytes[0] -= 1;
+        let with_user = |user| UpdateNotePublic {
+            op_pub: OpPub::Deposit {
+                amount: 10,
+                token: scalar(0x11),
+                user,
+                valid_until: None,
+            },
+            ..public()
+        };
+
+        assert!(with_user(below_modulus).instance().is_some());
+        assert_eq!(with_user(modulus).instance(), None);
+        assert_eq!(with_user(scalar(0xff)).instance(), None);
+        assert_eq!(
+            UpdateNotePublic {
+                domain: scalar(0x31),
+                ..public()
+            }
+            .instance(),
+            None
+        );
+    }
+
+    #[test]
+    fn domain_of_account_id_is_instance() {
+        // account id of the well-known development account Alice
+        let mut alice = [0; 32];
+        hex::decode_to_slice(
+            "d43593c715fdd31c61141abd04a99fd6822c8558854ccde39a5684e7a56da27d",
+            &mut alice,
+        )
+        .unwrap();
+        let contracts = (0..=u8::MAX).map(|byte| [byte; 32]).chain([alice]);
+        for contract in contracts {
+            let public = UpdateNotePublic {
+                domain: domain_separator(Scalar::from_bytes(contract), 1_u128.into()),
+                ..public()
+            };
+            assert!(public.instance().is_some(), "{contract:?}");
+        }
+    }
+
+    #[test]
+    fn other_versions_rejected() {
+        let mut scale = to_scale(&public());
+        scale[0] = WIRE_VERSION + 1;
+        assert_eq!(
+            from_scale::<UpdateNotePublic>(&scale),
+            Err(WireError::UnsupportedVersion(WIRE_VERSION + 1))
+        );
+        let mut json = to_json(&public());
+        json["version"] = (WIRE_VERSION + 1).into();
+        assert_eq!(
+            from_json::<UpdateNotePublic>(&json),
+            Err(WireError::UnsupportedVersion(WIRE_VERSION + 1))
+        );
+    }
+
+    #[test]
+    fn malformed_encodings_rejected() {
+        let scale = to_scale(&public());
+        assert_eq!(
+            from_scale::<UpdateNotePublic>(&[]),
+            Err(WireError::Malformed)
+        );
+        assert_eq!(
+            from_scale::<UpdateNotePublic>(&scale[..scale.len() - 1]),
+            Err(WireError::Malformed)
+        );
+        assert_eq!(
+            from_scale::<UpdateNotePublic>(&[scale.as_slice(), &[0]].concat()),
+            Err(WireError::Malformed)
+        );
+        assert_eq!(
+            Proof::<2, 2>::mocked_from_scale(&to_scale(&halo2_proof())).unwrap_err(),
+            ZkpError::UnsupportedProofSystem
+        );
+        assert_eq!(
+            Proof::<2, 2>::mocked_from_scale(&scale).unwrap_err(),
+            ZkpError::MalformedProof
+        );
+    }
+}
@@ -0,0 +1,69 @@
+//! Sizes of relations, printed as a markdown table by `cargo bench --bench circuit_stats`
+mod common;
+
+use common::{update_account_builder, witness, K};
+use halo2_base::{
+    gates::circuit::{builder::BaseCircuitBuilder, BaseCircuitParams},
+    halo2_proofs::halo2curves::bn256::Fr,
+};
+use liminal_halo2_relations::witness::MINIMUM_ROWS;
+
+fn print_row(
+    relation: &str,
+    tree_height: &str,
+    tokens: usize,
+    (builder, config): (BaseCircuitBuilder<Fr>, BaseCircuitParams),
+) {
+    let statistics = builder.statistics();
+    let advice_cells: usize = statistics.gate.total_advice_per_phase.iter().sum();
+    let lookup_cells: usize = statistics.total_lookup_advice_per_phase.iter().sum();
+    let fixed_cells = statistics.gate.total_fixed;
+    let advice_columns: usize = config.num_advice_per_phase.iter().sum();
+    //columns are filled up to the usable rows before the next one is started, so the first
+    //column of every kind is the tallest one
+    let usable_rows = (1 << config.k) - MINIMUM_ROWS;
+    let rows = statistics
+        .gate
+        .total_advice_per_phase
+        .iter()
+        .chain(&statistics.total_lookup_advice_per_phase)
+        .chain([&fixed_cells])
+        .map(|cells| (*cells).min(usable_rows))
+        .max()
+        .unwrap_or(0);
+    println!(
+        "| {relation} | {tree_height} | {tokens} | {advice_cells} | {lookup_cells} | {fixed_cells} | {advice_columns} | {rows} |"
+    );
+}
+
+fn update_note<const TREE_HEIGHT: usize, const TOKENS: usize>() {
+    print_row(
+        "update_note",
+        &TREE_HEIGHT.to_string(),
+        TOKENS,
+        witness::<TREE_HEIGHT, TOKENS>().builder(K),
+    );
+}
+
+fn update_account<const TOKENS: usize>() {
+    print_row(
+        "update_account",
+        "-",
+        TOKENS,
+        update_account_builder(&witness::<1, TOKENS>()),
+    );
+}
+
+fn main() {
+    println!("Circuits of 2^{K} rows\n");
+    println!("| relation | tree height | tokens | advice cells | lookup cells | fixed cells | advice columns | rows |");
+    println!("|---|---|---|---|---|---|---|---|");
+    update_account::<2>();
+    update_account::<8>();
+    update_account::<32>();
+    update_note::<10, 2>();
+    update_note::<20, 2>();
+    update_note::<32, 2>();
+    update_note::<10, 8>();
+    update_note::<10, 32>();
+}
@@ -0,0 +1,169 @@
+//! Circuits shared by benchmarks, built for a valid deposit to an account of `TOKENS` tokens
+//! kept at the last leaf of a tree of height `TREE_HEIGHT`
+#![allow(dead_code)]
+
+use halo2_base::{
+    gates::{
+        circuit::{builder::BaseCircuitBuilder, BaseCircuitParams},
+        flex_gate::MultiPhaseThreadBreakPoints,
+    },
+    halo2_proofs::{
+        halo2curves::bn256::{Bn256, Fr, G1Affine},
+        plonk::{create_proof, keygen_pk, keygen_vk, verify_proof, ProvingKey, VerifyingKey},
+        poly::{
+            commitment::ParamsProver,
+            kzg::{
+                commitment::{KZGCommitmentScheme, ParamsKZG},
+                multiopen::{ProverSHPLONK, VerifierSHPLONK},
+                strategy::SingleStrategy,
+            },
+            VerificationStrategy,
+        },
+        transcript::{
+            Blake2bRead, Blake2bWrite, Challenge255, TranscriptReadBuffer, TranscriptWriterBuffer,
+        },
+    },
+};
+use liminal_halo2_relations::{
+    account::Account,
+    merkle_proof::MerkleProof,
+    relations::update_account::{update_account_circuit, UpdateAccountInput},
+    token_account::{CircuitTokenOperation, TokenAccount, TokenOpPriv, TokenOpPub},
+    witness::{UpdateNoteWitness, MINIMUM_ROWS},
+};
+use rand::{rngs::StdRng, SeedableRng};
+
+/// Circuits have 2^K rows, larger circuits use more advice columns
+pub const K: usize = 14;
+
+pub fn witness<const TREE_HEIGHT: usize, const TOKENS: usize>(
+) -> UpdateNoteWitness<Fr, TREE_HEIGHT, TOKENS> {
+    let op_pub = TokenOpPub {
+        deposit: Fr::from(1),
+        amount: Fr::from(10),
+        token: Fr::from(TOKENS as u64),
+        user: Fr::from(100),
+        valid_until: Fr::from(0),
+    };
+    let op_priv = TokenOpPriv {
+        user: Fr::from(100),
+    };
+    let old_account = TokenAccount {
+        tokens: core::array::from_fn(|i| Fr::from(i as u64 + 1)),
+        balances: [Fr::from(1000); TOKENS],
+    };
+    UpdateNoteWitness::new(
+        op_pub,
+        op_priv,
+        Fr::from(101),
+        Fr::from(0),
+        Fr::from(1),
+        (Fr::from(2), Fr::from(3)),
+        (Fr::from(4), Fr::from(5)),
+        MerkleProof::new(
+            [false; TREE_HEIGHT],
+            core::array::from_fn(|level| Fr::from(level as u64)),
+        ),
+        old_account,
+    )
+}
+
+/// `update_account_circuit` for the account update of `witness`, with its configuration
+pub fn update_account_builder<const TREE_HEIGHT: usize, const TOKENS: usize>(
+    witness: &UpdateNoteWitness<Fr, TREE_HEIGHT, TOKENS>,
+) -> (BaseCircuitBuilder<Fr>, BaseCircuitParams) {
+    let mut builder = BaseCircuitBuilder::new(false).use_k(K);
+    let ctx = builder.main(0);
+    let mut load =
+        |values: Vec<Fr>| -> Vec<_> { values.into_iter().map(|x| ctx.load_witness(x)).collect() };
+    let operation = CircuitTokenOperation {
+        op_pub: load(witness.op_pub.into()).into(),
+        op_priv: load(witness.op_priv.into()).into(),
+    };
+    let old_account_hash = ctx.load_witness(witness.old_note.account_hash);
+    let new_account_hash = ctx.load_witness(witness.new_note.account_hash);
+    let old_account = witness.old_account.load(ctx);
+    update_account_circuit(
+        ctx,
+        UpdateAccountInput::new(old_account_hash, new_account_hash, operation, old_account),
+    );
+    let config = builder.calculate_params(Some(MINIMUM_ROWS));
+    (builder, config)
+}
+
+/// Trusted setup, deterministic so that runs are comparable
+pub fn params() -> ParamsKZG<Bn256> {
+    ParamsKZG::setup(K as u32, StdRng::seed_from_u64(50))
+}
+
+pub struct Keys {
+    pub pk: ProvingKey<G1Affine>,
+    pub config: BaseCircuitParams,
+    pub break_points: MultiPhaseThreadBreakPoints,
+}
+
+pub fn keygen<const TREE_HEIGHT: usize, const TOKENS: usize>(
+    params: &ParamsKZG<Bn256>,
+    witness: &UpdateNoteWitness<Fr, TREE_HEIGHT, TOKENS>,
+) -> Keys {
+    let (builder, config) = witness.builder(K);
+    let vk = keygen_vk(params, &builder).expect("circuit fits in 2^K rows");
+    let pk = keygen_pk(params, vk, &builder).expect("circuit fits in 2^K rows");
+    Keys {
+        pk,
+        config,
+        break_points: builder.break_points(),
+    }
+}
+
+pub fn prove<const TREE_HEIGHT: usize, const TOKENS: usize>(
+    params: &ParamsKZG<Bn256>,
+    keys: &Keys,
+    witness: &UpdateNoteWitness<Fr, TREE_HEIGHT, TOKENS>,
+) -> Vec<u8> {
+    let mut builder = BaseCircuitBuilder::prover(keys.config.clone(), keys.break_points.clone());
+    witness.assign(&mut builder);
+    let instance = witness.instance();
+
+    let mut transcript = Blake2bWrite::<_, G1Affine, Challenge255<_>>::init(vec![]);
+    create_proof::<
+        KZGCommitmentScheme<Bn256>,
+        ProverSHPLONK<'_, Bn256>,
+        Challenge255<G1Affine>,
+        _,
+        Blake2bWrite<Vec<u8>, G1Affine, Challenge255<G1Affine>>,
+        _,
+    >(
+        params,
+        &keys.pk,
+        &[builder],
+        &[&[instance.as_slice()]],
+        StdRng::seed_from_u64(0),
+        &mut transcript,
+    )
+    .expect("witness is valid");
+    transcript.finalize()
+}
+
+pub fn verify(
+    params: &ParamsKZG<Bn256>,
+    vk: &VerifyingKey<G1Affine>,
+    proof: &[u8],
+    instance: &[Fr],
+) -> bool {
+    let mut transcript = Blake2bRead::<_, G1Affine, Challenge255<_>>::init(proof);
+    verify_proof::<
+        KZGCommitmentScheme<Bn256>,
+        VerifierSHPLONK<'_, Bn256>,
+        Challenge255<G1Affine>,
+        Blake2bRead<&[u8], G1Affine, Challenge255<G1Affine>>,
+        SingleStrategy<'_, Bn256>,
+    >(
+        params.verifier_params(),
+        vk,
+        SingleStrategy::new(params),
+        &[&[instance]],
+        &mut transcript,
+    )
+    .is_ok()
+}
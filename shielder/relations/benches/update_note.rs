@@ -0,0 +1,42 @@
+//! Keygen, proving and verification time of `update_note_circuit`
+mod common;
+
+use common::{keygen, params, prove, verify, witness};
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+
+fn bench<const TREE_HEIGHT: usize, const TOKENS: usize>(c: &mut Criterion) {
+    let params = params();
+    let witness = witness::<TREE_HEIGHT, TOKENS>();
+    let instance = witness.instance();
+    let parameters = format!("height {TREE_HEIGHT}, tokens {TOKENS}");
+
+    let mut group = c.benchmark_group("update_note");
+    //proving takes seconds
+    group.sample_size(10);
+    group.bench_function(BenchmarkId::new("keygen", &parameters), |b| {
+        b.iter(|| keygen(&params, &witness))
+    });
+    let keys = keygen(&params, &witness);
+    group.bench_function(BenchmarkId::new("prove", &parameters), |b| {
+        b.iter(|| prove(&params, &keys, &witness))
+    });
+    let proof = prove(&params, &keys, &witness);
+    group.bench_function(BenchmarkId::new("verify", &parameters), |b| {
+        b.iter(|| assert!(verify(&params, keys.pk.get_vk(), &proof, &instance)))
+    });
+    group.finish();
+}
+
+fn tree_heights(c: &mut Criterion) {
+    bench::<10, 2>(c);
+    bench::<20, 2>(c);
+    bench::<32, 2>(c);
+}
+
+fn account_sizes(c: &mut Criterion) {
+    bench::<10, 8>(c);
+    bench::<10, 32>(c);
+}
+
+criterion_group!(benches, tree_heights, account_sizes);
+criterion_main!(benches);
@@ -3,6 +3,9 @@ pub mod merkle_proof;
 pub mod note;
 pub mod operation;
 pub mod relations;
+pub mod serde_field;
+pub mod token_account;
+pub mod witness;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum Token {
@@ -3,13 +3,19 @@ use halo2_base::{
     poseidon::hasher::PoseidonHasher,
     utils::BigPrimeField,
     AssignedValue, Context,
+    QuantumCell::Constant,
 };
+use serde::{Deserialize, Serialize};
 
 use crate::poseidon_consts::{RATE, T_WIDTH};
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(bound = "")]
 pub struct MerkleProof<F: BigPrimeField, const TREE_HEIGHT: usize> {
+    //path_shape[i] is set when node at level i is the left child
+    #[serde(with = "mocked_zk::serde_array")]
     pub path_shape: [bool; TREE_HEIGHT],
+    #[serde(with = "crate::serde_field::array")]
     pub path: [F; TREE_HEIGHT],
 }
 
@@ -35,6 +41,20 @@ impl<F: BigPrimeField, const TREE_HEIGHT: usize> MerkleProof<F, TREE_HEIGHT> {
 }
 
 impl<F: BigPrimeField, const TREE_HEIGHT: usize> CircuitMerkleProof<F, TREE_HEIGHT> {
+    /// Index of the leaf reconstructed from `path_shape`, constrains shape to be binary
+    pub fn leaf_index(&self, ctx: &mut Context<F>, gate: &GateChip<F>) -> AssignedValue<F> {
+        let bits = self
+            .path_shape
+            .iter()
+            .map(|shape| {
+                gate.assert_bit(ctx, *shape);
+                gate.not(ctx, *shape)
+            })
+            .collect::<Vec<_>>();
+        let powers = (0..TREE_HEIGHT).map(|i| Constant(F::from(1_u64 << i)));
+        gate.inner_product(ctx, bits, powers)
+    }
+
     pub fn verify(
         &self,
         ctx: &mut Context<F>,
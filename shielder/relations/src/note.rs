@@ -1,21 +1,36 @@
-use halo2_base::{utils::ScalarField, AssignedValue, Context};
+use halo2_base::{
+    gates::GateChip,
+    poseidon::hasher::PoseidonHasher,
+    utils::{BigPrimeField, ScalarField},
+    AssignedValue, Context,
+};
+use serde::{Deserialize, Serialize};
 
-use crate::CloneToVec;
+use crate::{
+    poseidon_consts::{RATE, T_WIDTH},
+    CloneToVec,
+};
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(bound = "")]
 pub struct Note<F: ScalarField> {
+    #[serde(with = "crate::serde_field")]
     pub zk_id: F,
+    #[serde(with = "crate::serde_field")]
     pub trapdoor: F,
-    pub nullifier: F,
+    //secret from which nullifier of the note is derived
+    #[serde(with = "crate::serde_field")]
+    pub nullifier_key: F,
+    #[serde(with = "crate::serde_field")]
     pub account_hash: F,
 }
 
 impl<F: ScalarField> Note<F> {
-    pub fn new(note_id: F, trapdoor: F, nullifier: F, account_hash: F) -> Self {
+    pub fn new(note_id: F, trapdoor: F, nullifier_key: F, account_hash: F) -> Self {
         Self {
             zk_id: note_id,
             trapdoor,
-            nullifier,
+            nullifier_key,
             account_hash,
         }
     }
@@ -24,7 +39,7 @@ impl<F: ScalarField> Note<F> {
         CircuitNote {
             zk_id: ctx.load_witness(self.zk_id),
             trapdoor: ctx.load_witness(self.trapdoor),
-            nullifier: ctx.load_witness(self.nullifier),
+            nullifier_key: ctx.load_witness(self.nullifier_key),
             account_hash: ctx.load_witness(self.account_hash),
         }
     }
@@ -32,7 +47,12 @@ impl<F: ScalarField> Note<F> {
 
 impl<F: ScalarField> CloneToVec<F> for Note<F> {
     fn clone_to_vec(&self) -> Vec<F> {
-        vec![self.zk_id, self.trapdoor, self.nullifier, self.account_hash]
+        vec![
+            self.zk_id,
+            self.trapdoor,
+            self.nullifier_key,
+            self.account_hash,
+        ]
     }
 }
 
@@ -40,12 +60,84 @@ impl<F: ScalarField> CloneToVec<F> for Note<F> {
 pub struct CircuitNote<F: ScalarField> {
     pub zk_id: AssignedValue<F>,
     pub trapdoor: AssignedValue<F>,
-    pub nullifier: AssignedValue<F>,
+    pub nullifier_key: AssignedValue<F>,
     pub account_hash: AssignedValue<F>,
 }
 
+impl<F: BigPrimeField> CircuitNote<F> {
+    /// Nullifier of the note placed at `leaf_index` of the tree
+    /// The same note at other position has other nullifier
+    pub fn nullifier(
+        &self,
+        ctx: &mut Context<F>,
+        gate: &GateChip<F>,
+        poseidon: &mut PoseidonHasher<F, T_WIDTH, RATE>,
+        leaf_index: AssignedValue<F>,
+    ) -> AssignedValue<F> {
+        poseidon.hash_fix_len_array(ctx, gate, &[self.nullifier_key, leaf_index])
+    }
+}
+
 impl<F: ScalarField> CloneToVec<AssignedValue<F>> for CircuitNote<F> {
     fn clone_to_vec(&self) -> Vec<AssignedValue<F>> {
-        vec![self.zk_id, self.trapdoor, self.nullifier, self.account_hash]
+        vec![
+            self.zk_id,
+            self.trapdoor,
+            self.nullifier_key,
+            self.account_hash,
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use halo2_base::{
+        gates::{circuit::builder::BaseCircuitBuilder, GateChip},
+        halo2_proofs::halo2curves::bn256::Fr,
+        poseidon::hasher::{spec::OptimizedPoseidonSpec, PoseidonHasher},
+    };
+
+    use crate::{
+        merkle_proof::MerkleProof,
+        note::Note,
+        poseidon_consts::{RATE, R_F, R_P, T_WIDTH},
+    };
+
+    const TREE_HEIGHT: usize = 4;
+
+    fn nullifier_at(note: Note<Fr>, path_shape: [bool; TREE_HEIGHT]) -> Fr {
+        let mut builder = BaseCircuitBuilder::<Fr>::new(false);
+        let ctx = builder.main(0);
+        let gate = GateChip::<Fr>::default();
+        let mut poseidon =
+            PoseidonHasher::<Fr, T_WIDTH, RATE>::new(OptimizedPoseidonSpec::new::<R_F, R_P, 0>());
+        poseidon.initialize_consts(ctx, &gate);
+
+        let note = note.load(ctx);
+        let merkle_proof =
+            MerkleProof::<Fr, TREE_HEIGHT>::new(path_shape, [Fr::from(0); TREE_HEIGHT]).load(ctx);
+        let leaf_index = merkle_proof.leaf_index(ctx, &gate);
+        *note
+            .nullifier(ctx, &gate, &mut poseidon, leaf_index)
+            .value()
+    }
+
+    #[test]
+    fn test_nullifier_depends_on_leaf_index() {
+        let note = Note::new(Fr::from(1), Fr::from(2), Fr::from(3), Fr::from(4));
+        let first = [true, false, true, true];
+        let second = [false, true, true, true];
+
+        assert_eq!(nullifier_at(note, first), nullifier_at(note, first));
+        assert_ne!(nullifier_at(note, first), nullifier_at(note, second));
+    }
+
+    #[test]
+    fn test_nullifier_depends_on_key() {
+        let note = Note::new(Fr::from(1), Fr::from(2), Fr::from(3), Fr::from(4));
+        let other_key = Note::new(Fr::from(1), Fr::from(2), Fr::from(5), Fr::from(4));
+        let shape = [true; TREE_HEIGHT];
+
+        assert_ne!(nullifier_at(note, shape), nullifier_at(other_key, shape));
     }
 }
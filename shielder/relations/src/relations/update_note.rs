@@ -26,7 +26,11 @@ where
     pub op_pub: <OpFor<A, F> as CircuitOperation<F>>::OpPub,
     pub new_note_hash: AssignedValue<F>,
     pub merkle_root: AssignedValue<F>,
-    //old_note.nullifier is also a public input
+    //identifies shielder deployment (contract address and chain tag), binds proof to it
+    pub domain: AssignedValue<F>,
+    //address allowed to submit the proof, zero if anyone can submit it
+    pub submitter: AssignedValue<F>,
+    //nullifier of old_note, derived from its nullifier_key and leaf index, is also a public input
 
     //witnesses
     pub new_note: CircuitNote<F>,
@@ -49,6 +53,8 @@ where
         op_pub: <A::Op as Operation<F>>::OpPub,
         new_note_hash: F,
         merkle_root: F,
+        domain: F,
+        submitter: F,
         new_note: Note<F>,
         old_note: Note<F>,
         merkle_proof: MerkleProof<F, TREE_HEIGHT>,
@@ -63,6 +69,8 @@ where
             .into();
         let new_note_hash = ctx.load_witness(new_note_hash);
         let merkle_root = ctx.load_witness(merkle_root);
+        let domain = ctx.load_witness(domain);
+        let submitter = ctx.load_witness(submitter);
         let new_note = new_note.load(ctx);
         let old_note = old_note.load(ctx);
         let merkle_proof = merkle_proof.load(ctx);
@@ -79,6 +87,8 @@ where
             op_pub,
             new_note_hash,
             merkle_root,
+            domain,
+            submitter,
             new_note,
             old_note,
             merkle_proof,
@@ -122,16 +132,24 @@ pub fn update_note_circuit<F, A, const TREE_HEIGHT: usize>(
 
     let new_note_hash = input.new_note_hash;
     let merkle_root = input.merkle_root;
-    let old_nullifier = input.old_note.nullifier;
-
-    make_public.extend([new_note_hash, merkle_root, old_nullifier]);
+    let merkle_proof = input.merkle_proof;
+    let leaf_index = merkle_proof.leaf_index(ctx, &gate);
+    let old_nullifier = input
+        .old_note
+        .nullifier(ctx, &gate, &mut poseidon, leaf_index);
+
+    make_public.extend([
+        new_note_hash,
+        merkle_root,
+        old_nullifier,
+        input.domain,
+        input.submitter,
+    ]);
 
     verify_note_circuit(ctx, &gate, &mut poseidon, &input.new_note, new_note_hash);
 
     let old_note_hash = poseidon.hash_fix_len_array(ctx, &gate, &input.old_note.clone_to_vec());
 
-    let merkle_proof = input.merkle_proof;
-
     merkle_proof.verify(ctx, &gate, &mut poseidon, merkle_root, old_note_hash);
 
     let op_priv = input.op_priv;
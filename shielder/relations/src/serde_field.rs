@@ -0,0 +1,56 @@
+//! Serde of field elements as `0x`-prefixed hex of their little-endian representation,
+//! the same representation `mocked_zk::wire` uses for scalars of halo2 proofs
+//! Used as `#[serde(with = "crate::serde_field")]`, for arrays with `crate::serde_field::array`
+use halo2_base::utils::ScalarField;
+use serde::{de::Error, Deserialize, Deserializer, Serializer};
+
+pub fn to_hex<F: ScalarField>(value: &F) -> String {
+    format!("0x{}", hex::encode(value.to_repr()))
+}
+
+/// `None` unless `hex` is the canonical representation of a field element
+pub fn from_hex<F: ScalarField>(hex: &str) -> Option<F> {
+    let mut repr = F::Repr::default();
+    hex::decode_to_slice(hex.strip_prefix("0x")?, repr.as_mut()).ok()?;
+    F::from_repr(repr).into()
+}
+
+fn parse<'de, F: ScalarField, D: Deserializer<'de>>(hex: &str) -> Result<F, D::Error> {
+    from_hex(hex).ok_or_else(|| D::Error::custom(format!("{hex} is not a field element")))
+}
+
+pub fn serialize<F: ScalarField, S: Serializer>(
+    value: &F,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&to_hex(value))
+}
+
+pub fn deserialize<'de, F: ScalarField, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<F, D::Error> {
+    parse::<F, D>(&String::deserialize(deserializer)?)
+}
+
+pub mod array {
+    use halo2_base::utils::ScalarField;
+    use serde::{Deserializer, Serializer};
+
+    pub fn serialize<F: ScalarField, S: Serializer, const N: usize>(
+        values: &[F; N],
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        mocked_zk::serde_array::serialize(&values.map(|value| super::to_hex(&value)), serializer)
+    }
+
+    pub fn deserialize<'de, F: ScalarField, D: Deserializer<'de>, const N: usize>(
+        deserializer: D,
+    ) -> Result<[F; N], D::Error> {
+        let hexes: [String; N] = mocked_zk::serde_array::deserialize(deserializer)?;
+        let mut values = [F::ZERO; N];
+        for (value, hex) in values.iter_mut().zip(&hexes) {
+            *value = super::parse::<F, D>(hex)?;
+        }
+        Ok(values)
+    }
+}
@@ -0,0 +1,248 @@
+use halo2_base::{
+    gates::{GateChip, GateInstructions},
+    utils::BigPrimeField,
+    AssignedValue, Context,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    account::{Account, CircuitAccount},
+    operation::{CircuitOperation, Operation},
+    CloneToVec,
+};
+
+/// Balances and amounts are `u128`, larger values are overflows or underflows
+pub const BALANCE_BITS: usize = 128;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(bound = "")]
+pub struct TokenOpPub<F: BigPrimeField> {
+    //one for deposit, zero for withdrawal
+    #[serde(with = "crate::serde_field")]
+    pub deposit: F,
+    #[serde(with = "crate::serde_field")]
+    pub amount: F,
+    #[serde(with = "crate::serde_field")]
+    pub token: F,
+    //depositor or recipient
+    #[serde(with = "crate::serde_field")]
+    pub user: F,
+    //zero if operation doesn't expire, last valid block plus one otherwise
+    #[serde(with = "crate::serde_field")]
+    pub valid_until: F,
+}
+
+impl<F: BigPrimeField> From<TokenOpPub<F>> for Vec<F> {
+    fn from(op_pub: TokenOpPub<F>) -> Self {
+        vec![
+            op_pub.deposit,
+            op_pub.amount,
+            op_pub.token,
+            op_pub.user,
+            op_pub.valid_until,
+        ]
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(bound = "")]
+pub struct TokenOpPriv<F: BigPrimeField> {
+    #[serde(with = "crate::serde_field")]
+    pub user: F,
+}
+
+impl<F: BigPrimeField> From<TokenOpPriv<F>> for Vec<F> {
+    fn from(op_priv: TokenOpPriv<F>) -> Self {
+        vec![op_priv.user]
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct TokenOperation<F: BigPrimeField> {
+    pub op_pub: TokenOpPub<F>,
+    pub op_priv: TokenOpPriv<F>,
+}
+
+impl<F: BigPrimeField> Operation<F> for TokenOperation<F> {
+    type OpPriv = TokenOpPriv<F>;
+    type OpPub = TokenOpPub<F>;
+
+    fn combine(op_priv: Self::OpPriv, op_pub: Self::OpPub) -> Option<Self> {
+        (op_priv.user == op_pub.user).then_some(Self { op_pub, op_priv })
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct CircuitTokenOpPub<F: BigPrimeField> {
+    pub deposit: AssignedValue<F>,
+    pub amount: AssignedValue<F>,
+    pub token: AssignedValue<F>,
+    pub user: AssignedValue<F>,
+    pub valid_until: AssignedValue<F>,
+}
+
+impl<F: BigPrimeField> From<Vec<AssignedValue<F>>> for CircuitTokenOpPub<F> {
+    fn from(values: Vec<AssignedValue<F>>) -> Self {
+        let [deposit, amount, token, user, valid_until] = values
+            .try_into()
+            .unwrap_or_else(|_| panic!("public operation consists of 5 values"));
+        Self {
+            deposit,
+            amount,
+            token,
+            user,
+            valid_until,
+        }
+    }
+}
+
+impl<F: BigPrimeField> From<CircuitTokenOpPub<F>> for Vec<AssignedValue<F>> {
+    fn from(op_pub: CircuitTokenOpPub<F>) -> Self {
+        vec![
+            op_pub.deposit,
+            op_pub.amount,
+            op_pub.token,
+            op_pub.user,
+            op_pub.valid_until,
+        ]
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct CircuitTokenOpPriv<F: BigPrimeField> {
+    pub user: AssignedValue<F>,
+}
+
+impl<F: BigPrimeField> From<Vec<AssignedValue<F>>> for CircuitTokenOpPriv<F> {
+    fn from(values: Vec<AssignedValue<F>>) -> Self {
+        let [user] = values
+            .try_into()
+            .unwrap_or_else(|_| panic!("private operation consists of 1 value"));
+        Self { user }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct CircuitTokenOperation<F: BigPrimeField> {
+    pub op_pub: CircuitTokenOpPub<F>,
+    pub op_priv: CircuitTokenOpPriv<F>,
+}
+
+impl<F: BigPrimeField> CircuitOperation<F> for CircuitTokenOperation<F> {
+    type OpPriv = CircuitTokenOpPriv<F>;
+    type OpPub = CircuitTokenOpPub<F>;
+
+    /// Users can't be compared without a context, they are constrained by the account update
+    fn combine(op_priv: Self::OpPriv, op_pub: Self::OpPub) -> Option<Self> {
+        Some(Self { op_pub, op_priv })
+    }
+}
+
+/// Account holding balances of `TOKENS` tokens, the same as `mocked_zk::account::Account`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(bound = "")]
+pub struct TokenAccount<F: BigPrimeField, const TOKENS: usize> {
+    #[serde(with = "crate::serde_field::array")]
+    pub tokens: [F; TOKENS],
+    #[serde(with = "crate::serde_field::array")]
+    pub balances: [F; TOKENS],
+}
+
+impl<F: BigPrimeField, const TOKENS: usize> TokenAccount<F, TOKENS> {
+    /// Empty account
+    pub fn new(tokens: [F; TOKENS]) -> Self {
+        Self {
+            tokens,
+            balances: [F::ZERO; TOKENS],
+        }
+    }
+}
+
+impl<F: BigPrimeField, const TOKENS: usize> CloneToVec<F> for TokenAccount<F, TOKENS> {
+    fn clone_to_vec(&self) -> Vec<F> {
+        self.tokens
+            .iter()
+            .zip(self.balances.iter())
+            .flat_map(|(token, balance)| [*token, *balance])
+            .collect()
+    }
+}
+
+impl<F: BigPrimeField, const TOKENS: usize> Account<F> for TokenAccount<F, TOKENS> {
+    type CircuitAccount = CircuitTokenAccount<F, TOKENS>;
+    type Op = TokenOperation<F>;
+
+    /// Updates the first slot holding the token, balances wrap around the field
+    /// Such accounts are rejected by the circuit
+    fn update(&self, op: &Self::Op) -> Self {
+        let op_pub = op.op_pub;
+        let mut account = *self;
+        if let Some(i) = self.tokens.iter().position(|token| *token == op_pub.token) {
+            if op_pub.deposit == F::ONE {
+                account.balances[i] += op_pub.amount;
+            } else {
+                account.balances[i] -= op_pub.amount;
+            }
+        }
+        account
+    }
+
+    fn load(&self, ctx: &mut Context<F>) -> Self::CircuitAccount {
+        CircuitTokenAccount {
+            tokens: self.tokens.map(|token| ctx.load_witness(token)),
+            balances: self.balances.map(|balance| ctx.load_witness(balance)),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct CircuitTokenAccount<F: BigPrimeField, const TOKENS: usize> {
+    pub tokens: [AssignedValue<F>; TOKENS],
+    pub balances: [AssignedValue<F>; TOKENS],
+}
+
+impl<F: BigPrimeField, const TOKENS: usize> CloneToVec<AssignedValue<F>>
+    for CircuitTokenAccount<F, TOKENS>
+{
+    fn clone_to_vec(&self) -> Vec<AssignedValue<F>> {
+        self.tokens
+            .iter()
+            .zip(self.balances.iter())
+            .flat_map(|(token, balance)| [*token, *balance])
+            .collect()
+    }
+}
+
+impl<F: BigPrimeField, const TOKENS: usize> CircuitAccount<F> for CircuitTokenAccount<F, TOKENS> {
+    type Op = CircuitTokenOperation<F>;
+
+    fn update(&self, op: Self::Op, ctx: &mut Context<F>, gate: &GateChip<F>) -> Self {
+        let op_pub = op.op_pub;
+        ctx.constrain_equal(&op_pub.user, &op.op_priv.user);
+        gate.assert_bit(ctx, op_pub.deposit);
+
+        let mut found = ctx.load_zero();
+        let mut balances = self.balances;
+        for (token, balance) in self.tokens.iter().zip(balances.iter_mut()) {
+            //only the first slot holding the token is updated
+            let is_token = gate.is_equal(ctx, *token, op_pub.token);
+            let not_found = gate.not(ctx, found);
+            let is_updated = gate.mul(ctx, is_token, not_found);
+            found = gate.add(ctx, found, is_updated);
+
+            let delta = gate.mul(ctx, is_updated, op_pub.amount);
+            let deposited = gate.add(ctx, *balance, delta);
+            let withdrawn = gate.sub(ctx, *balance, delta);
+            *balance = gate.select(ctx, deposited, withdrawn, op_pub.deposit);
+            //overflows and underflows wrap around the field, far outside of the range
+            let _ = gate.num_to_bits(ctx, *balance, BALANCE_BITS);
+        }
+        //operation on a token not held by the account
+        gate.assert_is_const(ctx, &found, &F::ONE);
+
+        Self {
+            tokens: self.tokens,
+            balances,
+        }
+    }
+}
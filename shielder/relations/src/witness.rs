@@ -0,0 +1,378 @@
+//! Witness of `update_note_circuit` which can be exchanged as JSON, so that proofs can be built
+//! by a process other than the wallet
+//!
+//! Public inputs derived from the witness (hash of the new note, Merkle root and nullifier of the
+//! old note) are not a part of it, they are computed natively by `instance`.
+use halo2_base::{
+    gates::{
+        circuit::{builder::BaseCircuitBuilder, BaseCircuitParams},
+        GateChip,
+    },
+    poseidon::hasher::{spec::OptimizedPoseidonSpec, PoseidonHasher},
+    utils::BigPrimeField,
+    Context,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    account::Account,
+    merkle_proof::MerkleProof,
+    note::Note,
+    operation::Operation,
+    poseidon_consts::{RATE, R_F, R_P, T_WIDTH},
+    relations::update_note::{update_note_circuit, UpdateNoteInput},
+    token_account::{TokenAccount, TokenOpPriv, TokenOpPub, TokenOperation, BALANCE_BITS},
+    CloneToVec,
+};
+
+/// Rows of a circuit reserved for blinding factors
+pub const MINIMUM_ROWS: usize = 9;
+
+/// Poseidon hash computed natively, the same as `PoseidonHasher::hash_fix_len_array` in circuits
+pub fn native_poseidon<F: BigPrimeField>(inputs: &[F]) -> F {
+    let mut builder = BaseCircuitBuilder::<F>::new(false);
+    let ctx = builder.main(0);
+    let gate = GateChip::<F>::default();
+    let mut poseidon =
+        PoseidonHasher::<F, T_WIDTH, RATE>::new(OptimizedPoseidonSpec::new::<R_F, R_P, 0>());
+    poseidon.initialize_consts(ctx, &gate);
+    let inputs = inputs
+        .iter()
+        .map(|x| ctx.load_witness(*x))
+        .collect::<Vec<_>>();
+    *poseidon.hash_fix_len_array(ctx, &gate, &inputs).value()
+}
+
+/// Constraint of `update_note_circuit` not satisfied by a witness
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WitnessError {
+    /// public and private parts of operation refer to different users
+    OperationCombineError,
+    /// `deposit` of operation is neither zero nor one
+    NonBinaryDeposit,
+    /// token of operation is not held by the account
+    UnknownToken,
+    /// a balance of the updated account doesn't fit in `BALANCE_BITS`
+    BalanceOutOfRange,
+    /// old note doesn't hold the hash of the old account
+    OldAccountHashMismatch,
+    /// new note doesn't hold the hash of the updated account
+    NewAccountHashMismatch,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(bound = "")]
+pub struct UpdateNoteWitness<F: BigPrimeField, const TREE_HEIGHT: usize, const TOKENS: usize> {
+    //public inputs
+    pub op_pub: TokenOpPub<F>,
+    #[serde(with = "crate::serde_field")]
+    pub domain: F,
+    //zero if anyone can submit the proof
+    #[serde(with = "crate::serde_field")]
+    pub submitter: F,
+
+    //witnesses
+    pub new_note: Note<F>,
+    pub old_note: Note<F>,
+    pub merkle_proof: MerkleProof<F, TREE_HEIGHT>,
+    pub op_priv: TokenOpPriv<F>,
+    pub old_account: TokenAccount<F, TOKENS>,
+}
+
+impl<F: BigPrimeField, const TREE_HEIGHT: usize, const TOKENS: usize>
+    UpdateNoteWitness<F, TREE_HEIGHT, TOKENS>
+{
+    /// Witness of the operation on `old_account` of `zk_id`, notes are given as
+    /// `(trapdoor, nullifier_key)` and hold hashes of `old_account` and of the updated account
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        op_pub: TokenOpPub<F>,
+        op_priv: TokenOpPriv<F>,
+        domain: F,
+        submitter: F,
+        zk_id: F,
+        (old_trapdoor, old_nullifier_key): (F, F),
+        (new_trapdoor, new_nullifier_key): (F, F),
+        merkle_proof: MerkleProof<F, TREE_HEIGHT>,
+        old_account: TokenAccount<F, TOKENS>,
+    ) -> Self {
+        let new_account = old_account.update(&TokenOperation { op_pub, op_priv });
+        Self {
+            op_pub,
+            domain,
+            submitter,
+            new_note: Note::new(
+                zk_id,
+                new_trapdoor,
+                new_nullifier_key,
+                native_poseidon(&new_account.clone_to_vec()),
+            ),
+            old_note: Note::new(
+                zk_id,
+                old_trapdoor,
+                old_nullifier_key,
+                native_poseidon(&old_account.clone_to_vec()),
+            ),
+            merkle_proof,
+            op_priv,
+            old_account,
+        }
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, serde_json::Error> {
+        serde_json::from_str(json)
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("witness consists of strings and booleans")
+    }
+
+    /// Checks natively the constraints of `update_note_circuit`, the circuit is satisfied by
+    /// the witness together with public inputs given by `instance` if and only if it passes
+    pub fn validate(&self) -> Result<(), WitnessError> {
+        let operation = TokenOperation::combine(self.op_priv, self.op_pub)
+            .ok_or(WitnessError::OperationCombineError)?;
+        if self.op_pub.deposit != F::ZERO && self.op_pub.deposit != F::ONE {
+            return Err(WitnessError::NonBinaryDeposit);
+        }
+        if !self.old_account.tokens.contains(&self.op_pub.token) {
+            return Err(WitnessError::UnknownToken);
+        }
+        if native_poseidon(&self.old_account.clone_to_vec()) != self.old_note.account_hash {
+            return Err(WitnessError::OldAccountHashMismatch);
+        }
+
+        //overflows and underflows wrap around the field, far outside of the range
+        let new_account = self.old_account.update(&operation);
+        let in_range = |balance: &F| {
+            let repr = balance.to_repr();
+            repr.as_ref()[BALANCE_BITS / 8..]
+                .iter()
+                .all(|byte| *byte == 0)
+        };
+        if !new_account.balances.iter().all(in_range) {
+            return Err(WitnessError::BalanceOutOfRange);
+        }
+        if native_poseidon(&new_account.clone_to_vec()) != self.new_note.account_hash {
+            return Err(WitnessError::NewAccountHashMismatch);
+        }
+        Ok(())
+    }
+
+    pub fn new_note_hash(&self) -> F {
+        native_poseidon(&self.new_note.clone_to_vec())
+    }
+
+    /// Root of the tree in which the old note is placed at the leaf given by `merkle_proof`
+    pub fn merkle_root(&self) -> F {
+        let mut node = native_poseidon(&self.old_note.clone_to_vec());
+        for (is_left, sibling) in self
+            .merkle_proof
+            .path_shape
+            .iter()
+            .zip(self.merkle_proof.path)
+        {
+            node = if *is_left {
+                native_poseidon(&[node, sibling])
+            } else {
+                native_poseidon(&[sibling, node])
+            };
+        }
+        node
+    }
+
+    pub fn nullifier(&self) -> F {
+        let leaf_index = self
+            .merkle_proof
+            .path_shape
+            .iter()
+            .enumerate()
+            .filter(|(_, is_left)| !**is_left)
+            .map(|(level, _)| 1_u64 << level)
+            .sum::<u64>();
+        native_poseidon(&[self.old_note.nullifier_key, F::from(leaf_index)])
+    }
+
+    /// Public inputs in the order of the instance column of `update_note_circuit`
+    pub fn instance(&self) -> Vec<F> {
+        let mut instance: Vec<F> = self.op_pub.into();
+        instance.extend([
+            self.new_note_hash(),
+            self.merkle_root(),
+            self.nullifier(),
+            self.domain,
+            self.submitter,
+        ]);
+        instance
+    }
+
+    /// Input of `update_note_circuit`, public inputs derived from the witness are computed natively
+    pub fn load(
+        &self,
+        ctx: &mut Context<F>,
+    ) -> UpdateNoteInput<F, TokenAccount<F, TOKENS>, TREE_HEIGHT> {
+        UpdateNoteInput::new(
+            ctx,
+            self.op_pub,
+            self.new_note_hash(),
+            self.merkle_root(),
+            self.domain,
+            self.submitter,
+            self.new_note,
+            self.old_note,
+            self.merkle_proof.clone(),
+            self.op_priv,
+            self.old_account,
+        )
+    }
+
+    /// Assigns `update_note_circuit` in `builder`, public inputs go to its first instance column
+    pub fn assign(&self, builder: &mut BaseCircuitBuilder<F>) {
+        let ctx = builder.main(0);
+        let input = self.load(ctx);
+        let mut make_public = vec![];
+        update_note_circuit(ctx, input, &mut make_public);
+        builder.assigned_instances[0].extend(make_public);
+    }
+
+    /// `update_note_circuit` of `2^k` rows ready for `MockProver` or keygen, with its configuration
+    pub fn builder(&self, k: usize) -> (BaseCircuitBuilder<F>, BaseCircuitParams) {
+        let mut builder = BaseCircuitBuilder::new(false)
+            .use_k(k)
+            .use_instance_columns(1);
+        self.assign(&mut builder);
+        let config = builder.calculate_params(Some(MINIMUM_ROWS));
+        (builder, config)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use halo2_base::halo2_proofs::{dev::MockProver, halo2curves::bn256::Fr};
+
+    use super::{UpdateNoteWitness, WitnessError};
+    use crate::{
+        merkle_proof::MerkleProof,
+        token_account::{TokenAccount, TokenOpPriv, TokenOpPub},
+    };
+
+    const TREE_HEIGHT: usize = 4;
+    const TOKENS: usize = 2;
+    /// Circuit has 2^K rows
+    const K: usize = 14;
+
+    type Witness = UpdateNoteWitness<Fr, TREE_HEIGHT, TOKENS>;
+
+    /// Withdrawal of 10 of the first token from the balance of 100
+    fn witness() -> Witness {
+        let op_pub = TokenOpPub {
+            deposit: Fr::from(0),
+            amount: Fr::from(10),
+            token: Fr::from(1),
+            user: Fr::from(6),
+            valid_until: Fr::from(0),
+        };
+        let op_priv = TokenOpPriv { user: Fr::from(6) };
+        let old_account = TokenAccount {
+            tokens: [Fr::from(1), Fr::from(2)],
+            balances: [Fr::from(100), Fr::from(0)],
+        };
+        Witness::new(
+            op_pub,
+            op_priv,
+            Fr::from(8),
+            Fr::from(9),
+            Fr::from(1),
+            (Fr::from(2), Fr::from(3)),
+            (Fr::from(4), Fr::from(5)),
+            MerkleProof::new([true, false, true, false], [1_u64, 2, 3, 4].map(Fr::from)),
+            old_account,
+        )
+    }
+
+    fn accepted(witness: &Witness, instance: Vec<Fr>) -> bool {
+        let (builder, _) = witness.builder(K);
+        MockProver::run(K as u32, &builder, vec![instance])
+            .map(|prover| prover.verify().is_ok())
+            .unwrap_or(false)
+    }
+
+    #[test]
+    fn test_valid_witness_satisfies_circuit() {
+        let witness = witness();
+
+        assert_eq!(witness.validate(), Ok(()));
+        assert!(accepted(&witness, witness.instance()));
+    }
+
+    #[test]
+    fn test_invalid_witnesses_rejected() {
+        let valid = witness();
+        let mutations: [(fn(&mut Witness), WitnessError); 6] = [
+            (
+                |w| w.op_priv.user = Fr::from(7),
+                WitnessError::OperationCombineError,
+            ),
+            (
+                |w| w.op_pub.deposit = Fr::from(2),
+                WitnessError::NonBinaryDeposit,
+            ),
+            (|w| w.op_pub.token = Fr::from(3), WitnessError::UnknownToken),
+            (
+                |w| w.op_pub.amount = Fr::from(101),
+                WitnessError::BalanceOutOfRange,
+            ),
+            (
+                |w| w.old_note.account_hash = Fr::from(0),
+                WitnessError::OldAccountHashMismatch,
+            ),
+            (
+                |w| w.new_note.account_hash = Fr::from(0),
+                WitnessError::NewAccountHashMismatch,
+            ),
+        ];
+        for (mutate, error) in mutations {
+            let mut witness = valid.clone();
+            mutate(&mut witness);
+
+            assert_eq!(witness.validate(), Err(error));
+            assert!(!accepted(&witness, witness.instance()), "{error:?}");
+        }
+    }
+
+    #[test]
+    fn test_json_round_trip() {
+        let witness = witness();
+        let decoded = Witness::from_json(&witness.to_json()).unwrap();
+
+        assert_eq!(decoded, witness);
+        assert_eq!(decoded.instance(), witness.instance());
+    }
+
+    #[test]
+    fn test_field_elements_are_little_endian_hex() {
+        let json: serde_json::Value = serde_json::from_str(&witness().to_json()).unwrap();
+
+        assert_eq!(json["domain"], format!("0x08{}", "00".repeat(31)));
+        assert_eq!(json["merkle_proof"]["path_shape"][1], false);
+    }
+
+    #[test]
+    fn test_malformed_json_rejected() {
+        let json: serde_json::Value = serde_json::from_str(&witness().to_json()).unwrap();
+        let with_domain = |domain: &str| {
+            let mut json = json.clone();
+            json["domain"] = domain.into();
+            json.to_string()
+        };
+
+        //not reduced modulo the field order
+        assert!(Witness::from_json(&with_domain(&format!("0x{}", "ff".repeat(32)))).is_err());
+        assert!(Witness::from_json(&with_domain("0x08")).is_err());
+        assert!(Witness::from_json(&with_domain(&format!("08{}", "00".repeat(31)))).is_err());
+        assert!(Witness::from_json(&with_domain(&format!("0x+8{}", "00".repeat(31)))).is_err());
+        //path of other height
+        assert!(UpdateNoteWitness::<Fr, 3, TOKENS>::from_json(&json.to_string()).is_err());
+        assert!(UpdateNoteWitness::<Fr, TREE_HEIGHT, 3>::from_json(&json.to_string()).is_err());
+    }
+}
@@ -0,0 +1,48 @@
+//! Minimal client of the relayer HTTP API, used by wallets
+use std::{
+    io::{self, Read, Write},
+    net::{SocketAddr, TcpStream},
+};
+
+use mocked_zk::wire;
+use serde::de::DeserializeOwned;
+use shielder_wallet::calls::UpdateNoteCall;
+
+use crate::http::{RelayResult, RelayerInfo};
+
+pub(crate) fn request<T: DeserializeOwned>(
+    addr: SocketAddr,
+    method: &str,
+    path: &str,
+    body: &[u8],
+) -> io::Result<T> {
+    let mut stream = TcpStream::connect(addr)?;
+    write!(
+        stream,
+        "{method} {path} HTTP/1.1\r\nHost: {addr}\r\nContent-Type: application/json\r\n\
+         Content-Length: {}\r\nConnection: close\r\n\r\n",
+        body.len()
+    )?;
+    stream.write_all(body)?;
+
+    let mut response = vec![];
+    stream.read_to_end(&mut response)?;
+    let body_start = response
+        .windows(4)
+        .position(|window| window == b"\r\n\r\n")
+        .ok_or(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "malformed response",
+        ))?;
+    serde_json::from_slice(&response[body_start + 4..]).map_err(io::Error::other)
+}
+
+pub fn info(addr: SocketAddr) -> io::Result<RelayerInfo> {
+    request(addr, "GET", "/info", &[])
+}
+
+/// Sends the call to the relayer, returns id of the added leaf or the relayer error
+pub fn relay(addr: SocketAddr, call: &UpdateNoteCall) -> io::Result<RelayResult> {
+    let body = serde_json::to_vec(&wire::to_json(call)).map_err(io::Error::other)?;
+    request(addr, "POST", "/update_note", &body)
+}
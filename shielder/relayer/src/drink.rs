@@ -0,0 +1,75 @@
+//! Backend driving shielder deployed in drink `Session`
+use std::fmt::Debug;
+
+use drink::{
+    pallet_contracts::Determinism, sandbox_api::contracts_api::ContractAPI, Sandbox,
+    DEFAULT_GAS_LIMIT,
+};
+use ink::{
+    scale::{self, Decode, Input},
+    MessageResult,
+};
+use mocked_zk::Scalar;
+use shielder_wallet::{
+    calls::UpdateNoteCall,
+    drink::{DrinkTransport, DrinkTransportError},
+    transport::Transport,
+};
+
+use crate::Backend;
+
+/// Error of shielder messages kept SCALE-encoded, for relayers not linking the contract crate
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EncodedError(pub Vec<u8>);
+
+impl Decode for EncodedError {
+    fn decode<I: Input>(input: &mut I) -> Result<Self, scale::Error> {
+        let len = input
+            .remaining_len()?
+            .ok_or("length of the error is unknown")?;
+        let mut bytes = vec![0; len];
+        input.read(&mut bytes)?;
+        Ok(Self(bytes))
+    }
+}
+
+/// Calls are submitted by the actor of the session
+impl<E: Decode + Debug> Backend for DrinkTransport<'_, E> {
+    type Error = DrinkTransportError<E>;
+
+    fn address(&mut self) -> Scalar {
+        Scalar::from_bytes(*self.session().get_actor().as_ref())
+    }
+
+    fn domain_separator(&mut self) -> Result<Scalar, Self::Error> {
+        Transport::domain_separator(self)
+    }
+
+    fn simulate(&mut self, call: &UpdateNoteCall) -> Result<u32, Self::Error> {
+        let shielder_address = self.shielder_address().clone();
+        let session = self.session();
+        let actor = session.get_actor();
+        let result = session.sandbox().dry_run(|sandbox| {
+            sandbox.call_contract(
+                shielder_address,
+                0,
+                call.payload(),
+                actor,
+                DEFAULT_GAS_LIMIT,
+                None,
+                Determinism::Enforced,
+            )
+        });
+        let exec = result
+            .result
+            .map_err(|err| DrinkTransportError::Dispatch(format!("{err:?}")))?;
+        MessageResult::<Result<u32, E>>::decode(&mut exec.data.as_slice())
+            .map_err(DrinkTransportError::Decode)?
+            .map_err(DrinkTransportError::Lang)?
+            .map_err(DrinkTransportError::Contract)
+    }
+
+    fn submit(&mut self, call: &UpdateNoteCall) -> Result<u32, Self::Error> {
+        self.update_note(call)
+    }
+}
@@ -0,0 +1,104 @@
+//! Local HTTP API of the relayer
+//!
+//! - `GET /info` returns `RelayerInfo`
+//! - `POST /update_note` takes `UpdateNoteCall` in the JSON wire format of `mocked_zk::wire`
+//!   and returns `RelayResult`
+//!
+//! Errors of single requests are answered with `RelayResult::Err` or logged, the server keeps
+//! serving other requests.
+use std::io::{self, Read};
+
+use mocked_zk::{wire, Scalar};
+use shielder_wallet::calls::UpdateNoteCall;
+use tiny_http::{Header, Method, Request, Response, Server};
+
+use crate::{Backend, Relayer};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct RelayerInfo {
+    /// to be used as `submitter` of relayed calls
+    pub address: Scalar,
+}
+
+/// Id of the added leaf or description of the error
+pub type RelayResult = Result<u32, String>;
+
+/// Longest accepted request body, calls are a few kilobytes
+pub const MAX_BODY: usize = 256 * 1024;
+
+fn json_response(status: u16, body: &impl serde::Serialize) -> Response<io::Cursor<Vec<u8>>> {
+    let body = serde_json::to_vec(body).expect("responses are serializable");
+    let content_type =
+        Header::from_bytes("Content-Type", "application/json").expect("header is valid");
+    Response::from_data(body)
+        .with_status_code(status)
+        .with_header(content_type)
+}
+
+fn error_response(status: u16, err: impl ToString) -> Response<io::Cursor<Vec<u8>>> {
+    json_response(status, &RelayResult::Err(err.to_string()))
+}
+
+/// Body of the request, or the error response if it can't be read or exceeds `MAX_BODY`
+fn read_body(request: &mut Request) -> Result<Vec<u8>, Response<io::Cursor<Vec<u8>>>> {
+    let mut body = vec![];
+    request
+        .as_reader()
+        .take(MAX_BODY as u64 + 1)
+        .read_to_end(&mut body)
+        .map_err(|err| error_response(400, err))?;
+    if body.len() > MAX_BODY {
+        return Err(error_response(
+            413,
+            format!("body exceeds {MAX_BODY} bytes"),
+        ));
+    }
+    Ok(body)
+}
+
+fn relay<B: Backend>(relayer: &mut Relayer<B>, body: &[u8]) -> Response<io::Cursor<Vec<u8>>> {
+    let call = match serde_json::from_slice(body) {
+        Ok(json) => match wire::from_json::<UpdateNoteCall>(&json) {
+            Ok(call) => call,
+            Err(err) => return error_response(400, format!("{err:?}")),
+        },
+        Err(err) => return error_response(400, err),
+    };
+    match relayer.relay(&call) {
+        Ok(leaf_id) => json_response(200, &RelayResult::Ok(leaf_id)),
+        Err(err) => error_response(422, format!("{err:?}")),
+    }
+}
+
+/// Handles a single request, calls are relayed one by one
+///
+/// Returns error only if the response can't be sent.
+pub fn handle_request<B: Backend>(
+    relayer: &mut Relayer<B>,
+    mut request: Request,
+) -> io::Result<()> {
+    let response = match (request.method(), request.url()) {
+        (Method::Get, "/info") => json_response(
+            200,
+            &RelayerInfo {
+                address: relayer.address(),
+            },
+        ),
+        (Method::Post, "/update_note") => match read_body(&mut request) {
+            Ok(body) => relay(relayer, &body),
+            Err(response) => response,
+        },
+        _ => error_response(404, "not found"),
+    };
+    request.respond(response)
+}
+
+/// Serves requests until the server is closed, failures to respond are logged to stderr
+pub fn serve<B: Backend>(server: &Server, relayer: &mut Relayer<B>) {
+    for request in server.incoming_requests() {
+        let remote = request.remote_addr().copied();
+        if let Err(err) = handle_request(relayer, request) {
+            eprintln!("failed to respond to {remote:?}: {err}");
+        }
+    }
+}
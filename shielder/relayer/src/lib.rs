@@ -0,0 +1,86 @@
+//! Relayer submitting `update_note` calls on behalf of users
+//!
+//! Users withdrawing to fresh accounts have no funds to pay fees, so they send the call to
+//! a relayer instead. The relayer checks the proof locally, simulates the call and submits it
+//! through its `Backend`. Calls are exposed over a local HTTP API, see `http`.
+//!
+//! The `shielder-relayer` binary (feature `drink`) serves a shielder deployed in a drink sandbox.
+pub mod client;
+#[cfg(feature = "drink")]
+pub mod drink;
+pub mod http;
+#[cfg(test)]
+mod tests;
+
+use std::fmt::Debug;
+
+use mocked_zk::{errors::ZkpError, Scalar};
+use shielder_wallet::calls::UpdateNoteCall;
+
+#[derive(Debug, PartialEq)]
+pub enum RelayerError<E> {
+    /// Call can be submitted only by other account
+    WrongSubmitter,
+    InvalidProof(ZkpError),
+    /// Call would be rejected by the contract
+    SimulationFail(E),
+    Backend(E),
+}
+
+/// Chain access of the relayer
+pub trait Backend {
+    type Error: Debug;
+
+    /// Account submitting calls, users can bind their calls to it with `submitter`
+    fn address(&mut self) -> Scalar;
+
+    fn domain_separator(&mut self) -> Result<Scalar, Self::Error>;
+
+    /// Runs the call without changing the state, returns id of the leaf the note would get
+    fn simulate(&mut self, call: &UpdateNoteCall) -> Result<u32, Self::Error>;
+
+    /// Returns id of the added leaf
+    fn submit(&mut self, call: &UpdateNoteCall) -> Result<u32, Self::Error>;
+}
+
+pub struct Relayer<B> {
+    backend: B,
+}
+
+impl<B: Backend> Relayer<B> {
+    pub fn new(backend: B) -> Self {
+        Self { backend }
+    }
+
+    pub fn address(&mut self) -> Scalar {
+        self.backend.address()
+    }
+
+    /// Submits the call if it passes all checks, returns id of the added leaf
+    pub fn relay(&mut self, call: &UpdateNoteCall) -> Result<u32, RelayerError<B::Error>> {
+        if call
+            .submitter
+            .is_some_and(|submitter| submitter != self.backend.address())
+        {
+            return Err(RelayerError::WrongSubmitter);
+        }
+        let domain = self
+            .backend
+            .domain_separator()
+            .map_err(RelayerError::Backend)?;
+        call.proof
+            .verify_update(
+                call.op_pub,
+                call.h_note_new,
+                call.merkle_root,
+                call.nullifier_old,
+                domain,
+                call.submitter,
+            )
+            .map_err(RelayerError::InvalidProof)?;
+        self.backend
+            .simulate(call)
+            .map_err(RelayerError::SimulationFail)?;
+        self.backend.submit(call).map_err(RelayerError::Backend)
+    }
+}
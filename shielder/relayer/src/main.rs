@@ -0,0 +1,71 @@
+//! Relayer serving a shielder deployed in an in-process drink sandbox
+//!
+//! There is no node backend yet, so the binary is meant for local development of wallets
+//! against the HTTP API. The shielder is deployed from `--shielder-bundle` at start and calls
+//! are submitted by the default actor of the sandbox.
+use std::{error::Error, net::SocketAddr, path::PathBuf};
+
+use clap::Parser;
+use drink::{
+    minimal::MinimalSandbox,
+    session::{bundle::ContractBundle, Session, NO_ENDOWMENT, NO_SALT},
+};
+use mocked_zk::{Scalar, TOKENS_NUMBER};
+use shielder_relayer::{drink::EncodedError, http, Relayer};
+use shielder_wallet::drink::DrinkTransport;
+use tiny_http::Server;
+
+#[derive(Debug, Parser)]
+struct Args {
+    #[arg(long, default_value = "127.0.0.1:8000")]
+    listen: SocketAddr,
+    /// `.contract` bundle of the shielder
+    #[arg(long)]
+    shielder_bundle: PathBuf,
+    /// hex-encoded addresses of supported tokens, missing ones are zero
+    #[arg(long = "token", value_parser = parse_scalar)]
+    tokens: Vec<Scalar>,
+    #[arg(long, default_value_t = 0)]
+    chain_tag: u128,
+}
+
+fn parse_scalar(s: &str) -> Result<Scalar, String> {
+    let bytes = hex::decode(s.trim_start_matches("0x")).map_err(|err| err.to_string())?;
+    bytes
+        .try_into()
+        .map(Scalar::from_bytes)
+        .map_err(|bytes: Vec<u8>| format!("expected 32 bytes, got {}", bytes.len()))
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let args = Args::parse();
+    if args.tokens.len() > TOKENS_NUMBER {
+        return Err(format!("at most {TOKENS_NUMBER} tokens are supported").into());
+    }
+    let mut tokens = [Scalar::from(0); TOKENS_NUMBER];
+    tokens[..args.tokens.len()].copy_from_slice(&args.tokens);
+
+    let mut session = Session::<MinimalSandbox>::default();
+    let shielder_address = session.deploy_bundle(
+        ContractBundle::load(&args.shielder_bundle)?,
+        "new",
+        &[
+            format!("{tokens:?}"),
+            format!("{:?}", Scalar::from(args.chain_tag)),
+        ],
+        NO_SALT,
+        NO_ENDOWMENT,
+    )?;
+
+    let server = Server::http(args.listen)?;
+    println!(
+        "relaying calls to shielder {shielder_address} on {}",
+        args.listen
+    );
+    let mut relayer = Relayer::new(DrinkTransport::<EncodedError>::new(
+        &mut session,
+        shielder_address,
+    ));
+    http::serve(&server, &mut relayer);
+    Ok(())
+}
@@ -0,0 +1,221 @@
+use std::thread;
+
+use mocked_zk::{
+    account::Account,
+    derive_nullifier, domain_separator,
+    errors::ZkpError,
+    note::Note,
+    ops::{OpPriv, OpPub, Operation},
+    relations::ZkProof,
+    traits::Hashable,
+    wire::{self, WIRE_VERSION},
+    Scalar, MERKLE_TREE_DEPTH, MOCKED_TOKEN, TOKENS_NUMBER,
+};
+use shielder_wallet::calls::UpdateNoteCall;
+use tiny_http::Server;
+
+use crate::{
+    client,
+    http::{handle_request, RelayResult, RelayerInfo, MAX_BODY},
+    Backend, Relayer, RelayerError,
+};
+
+#[derive(Default)]
+struct MockBackend {
+    reject: bool,
+    submitted: Vec<UpdateNoteCall>,
+}
+
+fn relayer_address() -> Scalar {
+    Scalar::from_bytes([0x5; 32])
+}
+
+fn domain() -> Scalar {
+    domain_separator(Scalar::from_bytes([0x1; 32]), 0_u128.into())
+}
+
+impl Backend for MockBackend {
+    type Error = &'static str;
+
+    fn address(&mut self) -> Scalar {
+        relayer_address()
+    }
+
+    fn domain_separator(&mut self) -> Result<Scalar, Self::Error> {
+        Ok(domain())
+    }
+
+    fn simulate(&mut self, _call: &UpdateNoteCall) -> Result<u32, Self::Error> {
+        match self.reject {
+            true => Err("rejected"),
+            false => Ok(self.submitted.len() as u32),
+        }
+    }
+
+    fn submit(&mut self, call: &UpdateNoteCall) -> Result<u32, Self::Error> {
+        self.submitted.push(call.clone());
+        Ok(self.submitted.len() as u32 - 1)
+    }
+}
+
+/// Withdrawal from a note at leaf 0 of otherwise empty tree
+fn withdraw_call(submitter: Option<Scalar>) -> UpdateNoteCall {
+    let mut tokens = [0_u128.into(); TOKENS_NUMBER];
+    tokens[0] = MOCKED_TOKEN;
+    let (id, trapdoor, nullifier_key) = (1_u128.into(), 2_u128.into(), 3_u128.into());
+    let account = Account::new(tokens);
+    let user = Scalar::from_bytes([0x7; 32]);
+    let deposit = Operation::combine(
+        OpPub::Deposit {
+            amount: 10,
+            token: MOCKED_TOKEN,
+            user,
+            valid_until: None,
+        },
+        OpPriv { user },
+    )
+    .unwrap();
+    let account = account.update(deposit).unwrap();
+    let h_note = Note::new(id, trapdoor, nullifier_key, account.hash()).hash();
+    let merkle_proof = [0_u128.into(); MERKLE_TREE_DEPTH];
+    let merkle_root = mocked_zk::merkle_root_from_path(h_note, merkle_proof, 0);
+
+    let op_pub = OpPub::Withdraw {
+        amount: 5,
+        token: MOCKED_TOKEN,
+        user,
+        valid_until: None,
+    };
+    let (h_note_new, proof) = ZkProof::new(
+        id,
+        trapdoor,
+        nullifier_key,
+        OpPriv { user },
+        account,
+        domain(),
+    )
+    .update_account(
+        Operation::combine(op_pub, OpPriv { user }).unwrap(),
+        4_u128.into(),
+        5_u128.into(),
+        merkle_proof,
+        0,
+        submitter,
+    )
+    .unwrap();
+    UpdateNoteCall {
+        op_pub,
+        h_note_new,
+        merkle_root,
+        nullifier_old: derive_nullifier(nullifier_key, 0),
+        submitter,
+        proof,
+        memo: vec![],
+    }
+}
+
+#[test]
+fn test_relay() {
+    let mut relayer = Relayer::new(MockBackend::default());
+    assert_eq!(relayer.relay(&withdraw_call(None)), Ok(0));
+    assert_eq!(
+        relayer.relay(&withdraw_call(Some(relayer_address()))),
+        Ok(1)
+    );
+    assert_eq!(relayer.backend.submitted.len(), 2);
+}
+
+#[test]
+fn test_relay_rejected() {
+    let mut relayer = Relayer::new(MockBackend::default());
+    assert_eq!(
+        relayer.relay(&withdraw_call(Some(1_u128.into()))),
+        Err(RelayerError::WrongSubmitter)
+    );
+
+    let mut call = withdraw_call(None);
+    call.h_note_new = 0_u128.into();
+    assert_eq!(
+        relayer.relay(&call),
+        Err(RelayerError::InvalidProof(ZkpError::NoteHashMismatch))
+    );
+
+    relayer.backend.reject = true;
+    assert_eq!(
+        relayer.relay(&withdraw_call(None)),
+        Err(RelayerError::SimulationFail("rejected"))
+    );
+    assert!(relayer.backend.submitted.is_empty());
+}
+
+#[test]
+fn test_http() {
+    let mut relayer = Relayer::new(MockBackend::default());
+    let server = Server::http("127.0.0.1:0").unwrap();
+    let addr = server.server_addr().to_ip().unwrap();
+
+    let client = thread::spawn(move || {
+        let info = client::info(addr).unwrap();
+        let mut call = withdraw_call(Some(info.address));
+        let relayed = client::relay(addr, &call).unwrap();
+        call.h_note_new = 0_u128.into();
+        let rejected = client::relay(addr, &call).unwrap();
+        (info, relayed, rejected)
+    });
+    for _ in 0..3 {
+        handle_request(&mut relayer, server.recv().unwrap()).unwrap();
+    }
+
+    let (info, relayed, rejected) = client.join().unwrap();
+    assert_eq!(
+        info,
+        RelayerInfo {
+            address: relayer_address()
+        }
+    );
+    assert_eq!(relayed, Ok(0));
+    assert_eq!(
+        rejected,
+        Err(format!(
+            "{:?}",
+            RelayerError::<()>::InvalidProof(ZkpError::NoteHashMismatch)
+        ))
+    );
+    assert_eq!(relayer.backend.submitted.len(), 1);
+}
+
+#[test]
+fn test_http_malformed_bodies() {
+    let mut relayer = Relayer::new(MockBackend::default());
+    let server = Server::http("127.0.0.1:0").unwrap();
+    let addr = server.server_addr().to_ip().unwrap();
+
+    let client = thread::spawn(move || {
+        let post = |body: &[u8]| -> RelayResult {
+            client::request(addr, "POST", "/update_note", body).unwrap()
+        };
+        let non_utf8 = post(&[0xff, 0xfe]);
+        let too_long = post(&vec![b' '; MAX_BODY + 1]);
+        // bare call, not in the wire format
+        let unversioned = post(&serde_json::to_vec(&withdraw_call(None)).unwrap());
+        let mut json = wire::to_json(&withdraw_call(None));
+        json["version"] = (WIRE_VERSION + 1).into();
+        let other_version = post(&serde_json::to_vec(&json).unwrap());
+        let relayed = client::relay(addr, &withdraw_call(None)).unwrap();
+        (non_utf8, too_long, unversioned, other_version, relayed)
+    });
+    for _ in 0..5 {
+        handle_request(&mut relayer, server.recv().unwrap()).unwrap();
+    }
+
+    let (non_utf8, too_long, unversioned, other_version, relayed) = client.join().unwrap();
+    assert!(non_utf8.is_err());
+    assert_eq!(too_long, Err(format!("body exceeds {MAX_BODY} bytes")));
+    assert_eq!(unversioned, Err("Malformed".into()));
+    assert_eq!(
+        other_version,
+        Err(format!("UnsupportedVersion({})", WIRE_VERSION + 1))
+    );
+    assert_eq!(relayed, Ok(0));
+    assert_eq!(relayer.backend.submitted.len(), 1);
+}
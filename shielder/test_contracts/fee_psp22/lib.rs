@@ -0,0 +1,137 @@
+//! PSP22 token used in shielder tests
+//! Burns a fixed fee from every transferred amount, so recipients get less than `value`
+
+#![cfg_attr(not(feature = "std"), no_std, no_main)]
+
+use ink::{
+    prelude::{string::String, vec::Vec},
+    primitives::AccountId,
+};
+
+#[ink::scale_derive(Encode, Decode, TypeInfo)]
+#[derive(Debug, PartialEq, Eq)]
+pub enum PSP22Error {
+    Custom(String),
+    InsufficientBalance,
+    InsufficientAllowance,
+    ZeroRecipientAddress,
+    ZeroSenderAddress,
+    SafeTransferCheckFailed(String),
+}
+
+/// Subset of PSP22 used by the shielder and its tests
+#[ink::trait_definition]
+pub trait PSP22 {
+    #[ink(message)]
+    fn balance_of(&self, owner: AccountId) -> u128;
+
+    #[ink(message)]
+    fn allowance(&self, owner: AccountId, spender: AccountId) -> u128;
+
+    #[ink(message)]
+    fn transfer(&mut self, to: AccountId, value: u128, data: Vec<u8>) -> Result<(), PSP22Error>;
+
+    #[ink(message)]
+    fn transfer_from(
+        &mut self,
+        from: AccountId,
+        to: AccountId,
+        value: u128,
+        data: Vec<u8>,
+    ) -> Result<(), PSP22Error>;
+
+    #[ink(message)]
+    fn approve(&mut self, spender: AccountId, value: u128) -> Result<(), PSP22Error>;
+}
+
+#[ink::contract]
+pub mod fee_psp22 {
+    use ink::{prelude::vec::Vec, storage::Mapping};
+
+    use crate::{PSP22Error, PSP22};
+
+    #[ink(storage)]
+    pub struct FeePsp22 {
+        balances: Mapping<AccountId, u128>,
+        allowances: Mapping<(AccountId, AccountId), u128>,
+        /// burned from every transfer
+        fee: u128,
+    }
+
+    impl FeePsp22 {
+        #[ink(constructor)]
+        pub fn new(supply: u128, fee: u128) -> Self {
+            let mut balances = Mapping::default();
+            balances.insert(Self::env().caller(), &supply);
+            Self {
+                balances,
+                allowances: Default::default(),
+                fee,
+            }
+        }
+
+        fn move_tokens(
+            &mut self,
+            from: AccountId,
+            to: AccountId,
+            value: u128,
+        ) -> Result<(), PSP22Error> {
+            let from_balance = self
+                .balance_of(from)
+                .checked_sub(value)
+                .ok_or(PSP22Error::InsufficientBalance)?;
+            self.balances.insert(from, &from_balance);
+            let to_balance = self
+                .balance_of(to)
+                .saturating_add(value.saturating_sub(self.fee));
+            self.balances.insert(to, &to_balance);
+            Ok(())
+        }
+    }
+
+    impl PSP22 for FeePsp22 {
+        #[ink(message)]
+        fn balance_of(&self, owner: AccountId) -> u128 {
+            self.balances.get(owner).unwrap_or(0)
+        }
+
+        #[ink(message)]
+        fn allowance(&self, owner: AccountId, spender: AccountId) -> u128 {
+            self.allowances.get((owner, spender)).unwrap_or(0)
+        }
+
+        #[ink(message)]
+        fn transfer(
+            &mut self,
+            to: AccountId,
+            value: u128,
+            _data: Vec<u8>,
+        ) -> Result<(), PSP22Error> {
+            self.move_tokens(self.env().caller(), to, value)
+        }
+
+        #[ink(message)]
+        fn transfer_from(
+            &mut self,
+            from: AccountId,
+            to: AccountId,
+            value: u128,
+            _data: Vec<u8>,
+        ) -> Result<(), PSP22Error> {
+            let spender = self.env().caller();
+            let allowance = self
+                .allowance(from, spender)
+                .checked_sub(value)
+                .ok_or(PSP22Error::InsufficientAllowance)?;
+            self.allowances.insert((from, spender), &allowance);
+            self.move_tokens(from, to, value)
+        }
+
+        #[ink(message)]
+        fn approve(&mut self, spender: AccountId, value: u128) -> Result<(), PSP22Error> {
+            self.allowances
+                .insert((self.env().caller(), spender), &value);
+            Ok(())
+        }
+    }
+}
@@ -0,0 +1,183 @@
+//! PSP22 token used in shielder tests
+//! Once `set_reentry` is called, re-enters the shielder with `add_note` every time tokens are
+//! pulled with `transfer_from`
+
+#![cfg_attr(not(feature = "std"), no_std, no_main)]
+
+use ink::{
+    prelude::{string::String, vec::Vec},
+    primitives::AccountId,
+};
+
+#[ink::scale_derive(Encode, Decode, TypeInfo)]
+#[derive(Debug, PartialEq, Eq)]
+pub enum PSP22Error {
+    Custom(String),
+    InsufficientBalance,
+    InsufficientAllowance,
+    ZeroRecipientAddress,
+    ZeroSenderAddress,
+    SafeTransferCheckFailed(String),
+}
+
+/// Subset of PSP22 used by the shielder and its tests
+#[ink::trait_definition]
+pub trait PSP22 {
+    #[ink(message)]
+    fn balance_of(&self, owner: AccountId) -> u128;
+
+    #[ink(message)]
+    fn allowance(&self, owner: AccountId, spender: AccountId) -> u128;
+
+    #[ink(message)]
+    fn transfer(&mut self, to: AccountId, value: u128, data: Vec<u8>) -> Result<(), PSP22Error>;
+
+    #[ink(message)]
+    fn transfer_from(
+        &mut self,
+        from: AccountId,
+        to: AccountId,
+        value: u128,
+        data: Vec<u8>,
+    ) -> Result<(), PSP22Error>;
+
+    #[ink(message)]
+    fn approve(&mut self, spender: AccountId, value: u128) -> Result<(), PSP22Error>;
+}
+
+#[ink::contract]
+pub mod malicious_psp22 {
+    use ink::{
+        env::{
+            call::{build_call, ExecutionInput, Selector},
+            DefaultEnvironment,
+        },
+        prelude::{format, vec::Vec},
+        storage::Mapping,
+    };
+    use mocked_zk::Scalar;
+    use shielder_contract::errors::ShielderError;
+
+    use crate::{PSP22Error, PSP22};
+
+    /// Gas limit of the nested `add_note` call, fits in the default gas limit of PSP22 calls
+    /// made by the shielder
+    pub const REENTRY_GAS_LIMIT: u64 = 50_000_000_000;
+
+    #[ink(storage)]
+    pub struct MaliciousPsp22 {
+        balances: Mapping<AccountId, u128>,
+        allowances: Mapping<(AccountId, AccountId), u128>,
+        /// arguments of `add_note` sent on re-entry
+        reentry: Option<(Scalar, Vec<u8>, Vec<u8>)>,
+    }
+
+    impl MaliciousPsp22 {
+        #[ink(constructor)]
+        pub fn new(supply: u128) -> Self {
+            let mut balances = Mapping::default();
+            balances.insert(Self::env().caller(), &supply);
+            Self {
+                balances,
+                allowances: Default::default(),
+                reentry: None,
+            }
+        }
+
+        /// Sets arguments of `add_note` sent on re-entry, `proof` in the wire format
+        #[ink(message)]
+        pub fn set_reentry(&mut self, h_note_new: Scalar, proof: Vec<u8>, memo: Vec<u8>) {
+            self.reentry = Some((h_note_new, proof, memo));
+        }
+
+        /// Calls `add_note` of the caller, error of the shielder is returned as `Custom` one,
+        /// as PSP22 errors carry only strings
+        fn reenter(&self) -> Result<(), PSP22Error> {
+            let Some((h_note_new, proof, memo)) = self.reentry.clone() else {
+                return Ok(());
+            };
+            let res = build_call::<DefaultEnvironment>()
+                .call(self.env().caller())
+                .call_v1()
+                .gas_limit(REENTRY_GAS_LIMIT)
+                .transferred_value(0)
+                .exec_input(
+                    ExecutionInput::new(Selector::new(ink::selector_bytes!("add_note")))
+                        .push_arg(h_note_new)
+                        .push_arg(proof)
+                        .push_arg(memo),
+                )
+                .returns::<Result<u32, ShielderError>>()
+                .try_invoke();
+            match res {
+                Ok(Ok(Ok(_))) => Ok(()),
+                Ok(Ok(Err(err))) => Err(PSP22Error::Custom(format!("{err:?}"))),
+                Ok(Err(err)) => Err(PSP22Error::Custom(format!("{err:?}"))),
+                Err(err) => Err(PSP22Error::Custom(format!("{err:?}"))),
+            }
+        }
+
+        fn move_tokens(
+            &mut self,
+            from: AccountId,
+            to: AccountId,
+            value: u128,
+        ) -> Result<(), PSP22Error> {
+            let from_balance = self.balance_of(from);
+            let from_balance = from_balance
+                .checked_sub(value)
+                .ok_or(PSP22Error::InsufficientBalance)?;
+            self.balances.insert(from, &from_balance);
+            let to_balance = self.balance_of(to).saturating_add(value);
+            self.balances.insert(to, &to_balance);
+            Ok(())
+        }
+    }
+
+    impl PSP22 for MaliciousPsp22 {
+        #[ink(message)]
+        fn balance_of(&self, owner: AccountId) -> u128 {
+            self.balances.get(owner).unwrap_or(0)
+        }
+
+        #[ink(message)]
+        fn allowance(&self, owner: AccountId, spender: AccountId) -> u128 {
+            self.allowances.get((owner, spender)).unwrap_or(0)
+        }
+
+        #[ink(message)]
+        fn transfer(
+            &mut self,
+            to: AccountId,
+            value: u128,
+            _data: Vec<u8>,
+        ) -> Result<(), PSP22Error> {
+            self.move_tokens(self.env().caller(), to, value)
+        }
+
+        #[ink(message)]
+        fn transfer_from(
+            &mut self,
+            from: AccountId,
+            to: AccountId,
+            value: u128,
+            _data: Vec<u8>,
+        ) -> Result<(), PSP22Error> {
+            self.reenter()?;
+            let spender = self.env().caller();
+            let allowance = self
+                .allowance(from, spender)
+                .checked_sub(value)
+                .ok_or(PSP22Error::InsufficientAllowance)?;
+            self.allowances.insert((from, spender), &allowance);
+            self.move_tokens(from, to, value)
+        }
+
+        #[ink(message)]
+        fn approve(&mut self, spender: AccountId, value: u128) -> Result<(), PSP22Error> {
+            self.allowances
+                .insert((self.env().caller(), spender), &value);
+            Ok(())
+        }
+    }
+}
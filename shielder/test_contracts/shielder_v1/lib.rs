@@ -0,0 +1,55 @@
+//! Shielder storage at version 1, used in shielder upgrade tests
+//! Writes the storage layout of the first shielder code with upgrades, names of the storage
+//! structs and of their fields are the shielder ones, as storage keys are derived from them
+
+#![cfg_attr(not(feature = "std"), no_std, no_main)]
+
+#[ink::contract]
+pub mod contract {
+    use ink::storage::Mapping;
+    use mocked_zk::{Scalar, MERKLE_TREE_DEPTH, TOKENS_NUMBER};
+
+    #[ink::storage_item]
+    pub struct MerkleTree {
+        nodes: Mapping<u32, Scalar>,
+        roots_log: Mapping<Scalar, ()>,
+        next_leaf_idx: u32,
+        size: u32,
+    }
+
+    #[ink(storage)]
+    pub struct Contract {
+        nullifier_set: Mapping<Scalar, ()>,
+        notes: MerkleTree,
+        supported_tokens: [Scalar; TOKENS_NUMBER],
+        owner: AccountId,
+        storage_version: u16,
+    }
+
+    impl Contract {
+        #[ink(constructor)]
+        pub fn new(supported_tokens: [Scalar; TOKENS_NUMBER]) -> Self {
+            Self {
+                nullifier_set: Default::default(),
+                notes: MerkleTree {
+                    nodes: Default::default(),
+                    roots_log: Default::default(),
+                    next_leaf_idx: 0,
+                    size: 1 << MERKLE_TREE_DEPTH,
+                },
+                supported_tokens,
+                owner: Self::env().caller(),
+                storage_version: 1,
+            }
+        }
+
+        /// Replaces code of the contract, keeping its storage
+        #[ink(message)]
+        pub fn set_code(&mut self, code_hash: Hash) {
+            assert_eq!(self.env().caller(), self.owner, "not owner");
+            self.env()
+                .set_code_hash(&code_hash)
+                .expect("code hash is uploaded");
+        }
+    }
+}
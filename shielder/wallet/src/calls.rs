@@ -0,0 +1,183 @@
+//! Arguments of shielder messages built by the wallet
+//! Proofs are passed to the contract in the SCALE wire format of `mocked_zk::wire`, calls are
+//! exchanged with tooling in its JSON wire format
+use ink::scale::{Decode, Encode};
+use mocked_zk::{
+    errors::ZkpError,
+    ops::OpPub,
+    relations::ZkProof,
+    wire::{self, Proof, WireError},
+    Scalar,
+};
+
+/// `0x`-prefixed hex, the format of `Vec<u8>` arguments expected by contract transcoder
+pub fn to_hex(bytes: &[u8]) -> String {
+    format!("0x{}", hex::encode(bytes))
+}
+
+/// Hex of SCALE encoding of `value`, contract transcoder takes it for scalars as for byte arrays
+fn scale_hex<T: Encode>(value: &T) -> String {
+    to_hex(&value.encode())
+}
+
+/// `Option` in the syntax of contract transcoder, `arg` renders the value
+fn option_arg<T>(value: Option<T>, arg: impl FnOnce(T) -> String) -> String {
+    value.map_or("None".into(), |value| format!("Some({})", arg(value)))
+}
+
+/// `OpPub` in the syntax of contract transcoder, scalars given by `scale_hex`
+fn op_pub_arg(op_pub: &OpPub) -> String {
+    let (variant, amount, token, user, valid_until) = match *op_pub {
+        OpPub::Deposit {
+            amount,
+            token,
+            user,
+            valid_until,
+        } => ("Deposit", amount, token, user, valid_until),
+        OpPub::Withdraw {
+            amount,
+            token,
+            user,
+            valid_until,
+        } => ("Withdraw", amount, token, user, valid_until),
+    };
+    format!(
+        "{variant} {{ amount: {amount}, token: {}, user: {}, valid_until: {} }}",
+        scale_hex(&token),
+        scale_hex(&user),
+        option_arg(valid_until, |block| block.to_string()),
+    )
+}
+
+/// Strips selector of `message` from `payload`
+fn message_data(payload: &[u8], message: [u8; 4]) -> Result<&[u8], WireError> {
+    payload.strip_prefix(&message).ok_or(WireError::Malformed)
+}
+
+/// Decodes arguments, trailing bytes are rejected
+fn decode_args<T: Decode>(mut data: &[u8]) -> Result<T, WireError> {
+    let args = T::decode(&mut data).map_err(|_| WireError::Malformed)?;
+    data.is_empty().then_some(args).ok_or(WireError::Malformed)
+}
+
+/// Arguments of `add_note` message
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct AddNoteCall {
+    pub h_note_new: Scalar,
+    pub proof: ZkProof,
+    pub memo: Vec<u8>,
+}
+
+impl AddNoteCall {
+    /// Proof in the SCALE wire format
+    pub fn proof_bytes(&self) -> Vec<u8> {
+        wire::to_scale(&Proof::Mocked(self.proof))
+    }
+
+    /// Arguments in the format expected by contract transcoder
+    pub fn args(&self) -> Vec<String> {
+        vec![
+            scale_hex(&self.h_note_new),
+            to_hex(&self.proof_bytes()),
+            to_hex(&self.memo),
+        ]
+    }
+
+    /// Input data of the contract call: message selector followed by SCALE-encoded arguments
+    pub fn payload(&self) -> Vec<u8> {
+        [
+            ink::selector_bytes!("add_note").as_slice(),
+            &(self.h_note_new, self.proof_bytes(), &self.memo).encode(),
+        ]
+        .concat()
+    }
+
+    /// Call from input data built by `payload`, proof is rejected the same way the contract does
+    pub fn from_payload(payload: &[u8]) -> Result<Self, ZkpError> {
+        let (h_note_new, proof, memo): (Scalar, Vec<u8>, Vec<u8>) =
+            decode_args(message_data(payload, ink::selector_bytes!("add_note"))?)?;
+        Ok(Self {
+            h_note_new,
+            proof: Proof::mocked_from_scale(&proof)?,
+            memo,
+        })
+    }
+}
+
+/// Arguments of `update_note` message
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct UpdateNoteCall {
+    pub op_pub: OpPub,
+    pub h_note_new: Scalar,
+    pub merkle_root: Scalar,
+    pub nullifier_old: Scalar,
+    pub submitter: Option<Scalar>,
+    pub proof: ZkProof,
+    pub memo: Vec<u8>,
+}
+
+impl UpdateNoteCall {
+    /// Proof in the SCALE wire format
+    pub fn proof_bytes(&self) -> Vec<u8> {
+        wire::to_scale(&Proof::Mocked(self.proof))
+    }
+
+    /// Arguments in the format expected by contract transcoder, with `proof` in the wire format
+    pub fn args_with_proof(&self, proof: &[u8]) -> Vec<String> {
+        vec![
+            op_pub_arg(&self.op_pub),
+            scale_hex(&self.h_note_new),
+            scale_hex(&self.merkle_root),
+            scale_hex(&self.nullifier_old),
+            option_arg(self.submitter, |submitter| scale_hex(&submitter)),
+            to_hex(proof),
+            to_hex(&self.memo),
+        ]
+    }
+
+    /// Arguments in the format expected by contract transcoder
+    pub fn args(&self) -> Vec<String> {
+        self.args_with_proof(&self.proof_bytes())
+    }
+
+    /// Input data of the contract call: message selector followed by SCALE-encoded arguments
+    pub fn payload(&self) -> Vec<u8> {
+        [
+            ink::selector_bytes!("update_note").as_slice(),
+            &(
+                self.op_pub,
+                self.h_note_new,
+                self.merkle_root,
+                self.nullifier_old,
+                self.submitter,
+                self.proof_bytes(),
+                &self.memo,
+            )
+                .encode(),
+        ]
+        .concat()
+    }
+
+    /// Call from input data built by `payload`, proof is rejected the same way the contract does
+    pub fn from_payload(payload: &[u8]) -> Result<Self, ZkpError> {
+        #[allow(clippy::type_complexity)]
+        let (op_pub, h_note_new, merkle_root, nullifier_old, submitter, proof, memo): (
+            OpPub,
+            Scalar,
+            Scalar,
+            Scalar,
+            Option<Scalar>,
+            Vec<u8>,
+            Vec<u8>,
+        ) = decode_args(message_data(payload, ink::selector_bytes!("update_note"))?)?;
+        Ok(Self {
+            op_pub,
+            h_note_new,
+            merkle_root,
+            nullifier_old,
+            submitter,
+            proof: Proof::mocked_from_scale(&proof)?,
+            memo,
+        })
+    }
+}
@@ -0,0 +1,120 @@
+//! Transport driving shielder deployed in drink `Session`
+use std::marker::PhantomData;
+
+use drink::{
+    minimal::MinimalSandbox,
+    session::{error::SessionError, Session, NO_ARGS, NO_ENDOWMENT},
+    AccountId32,
+};
+use ink::{
+    scale::{self, Decode},
+    LangError, MessageResult,
+};
+use mocked_zk::{Scalar, MERKLE_TREE_DEPTH, TOKENS_NUMBER};
+
+use crate::{
+    calls::{AddNoteCall, UpdateNoteCall},
+    transport::Transport,
+};
+
+#[derive(Debug)]
+pub enum DrinkTransportError<E> {
+    Session(SessionError),
+    Lang(LangError),
+    Decode(scale::Error),
+    /// Error returned by the contract
+    Contract(E),
+    /// Call failed before reaching the contract, e.g. ran out of gas
+    Dispatch(String),
+}
+
+/// `E` is the error type of shielder messages, `ShielderError`
+pub struct DrinkTransport<'a, E> {
+    session: &'a mut Session<MinimalSandbox>,
+    shielder_address: AccountId32,
+    _error: PhantomData<E>,
+}
+
+impl<'a, E: Decode> DrinkTransport<'a, E> {
+    pub fn new(session: &'a mut Session<MinimalSandbox>, shielder_address: AccountId32) -> Self {
+        Self {
+            session,
+            shielder_address,
+            _error: PhantomData,
+        }
+    }
+
+    pub fn session(&mut self) -> &mut Session<MinimalSandbox> {
+        self.session
+    }
+
+    pub fn shielder_address(&self) -> &AccountId32 {
+        &self.shielder_address
+    }
+
+    fn query<T: Decode>(&mut self, message: &str) -> Result<T, DrinkTransportError<E>> {
+        self.session
+            .call_with_address(
+                self.shielder_address.clone(),
+                message,
+                NO_ARGS,
+                NO_ENDOWMENT,
+            )
+            .map_err(DrinkTransportError::Session)?
+            .map_err(DrinkTransportError::Lang)
+    }
+
+    /// Calls message returning `Result<T, E>`, decoding `E` also from reverted calls
+    fn call<T: Decode>(
+        &mut self,
+        message: &str,
+        args: &[String],
+    ) -> Result<T, DrinkTransportError<E>> {
+        let res = match self.session.call_with_address(
+            self.shielder_address.clone(),
+            message,
+            args,
+            NO_ENDOWMENT,
+        ) {
+            Ok(res) => res,
+            Err(SessionError::CallReverted(data)) => {
+                MessageResult::<Result<T, E>>::decode(&mut data.as_slice())
+                    .map_err(DrinkTransportError::Decode)?
+            }
+            Err(err) => return Err(DrinkTransportError::Session(err)),
+        };
+        res.map_err(DrinkTransportError::Lang)?
+            .map_err(DrinkTransportError::Contract)
+    }
+}
+
+impl<E: Decode> Transport for DrinkTransport<'_, E> {
+    type Error = DrinkTransportError<E>;
+
+    fn domain_separator(&mut self) -> Result<Scalar, Self::Error> {
+        self.query("domain_separator")
+    }
+
+    fn supported_tokens(&mut self) -> Result<[Scalar; TOKENS_NUMBER], Self::Error> {
+        self.query("supported_tokens")
+    }
+
+    fn notes_merkle_root(&mut self) -> Result<Scalar, Self::Error> {
+        self.call("notes_merkle_root", &[])
+    }
+
+    fn notes_merkle_path(
+        &mut self,
+        leaf_id: u32,
+    ) -> Result<[Scalar; MERKLE_TREE_DEPTH], Self::Error> {
+        self.call("notes_merkle_path", &[leaf_id.to_string()])
+    }
+
+    fn add_note(&mut self, call: &AddNoteCall) -> Result<u32, Self::Error> {
+        self.call("add_note", &call.args())
+    }
+
+    fn update_note(&mut self, call: &UpdateNoteCall) -> Result<u32, Self::Error> {
+        self.call("update_note", &call.args())
+    }
+}
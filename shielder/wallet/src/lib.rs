@@ -0,0 +1,261 @@
+//! Wallet managing notes of a single shielded account
+//!
+//! Secrets of all notes are derived from the wallet seed. Calls are built in two steps:
+//! `prepare_*` builds the call and remembers the pending note, `confirm` stores it once
+//! the call is included and its leaf id is known.
+//!
+//! Memos of the notes are encrypted to the wallet's own address, so the wallet can be recovered
+//! from the seed and the events emitted by the shielder.
+pub mod calls;
+#[cfg(feature = "drink")]
+pub mod drink;
+pub mod state;
+#[cfg(test)]
+mod tests;
+pub mod transport;
+
+use std::{io, path::Path};
+
+use mocked_zk::{
+    account::Account,
+    derivation::Seed,
+    derive_nullifier,
+    errors::ZkpError,
+    ops::{OpPriv, OpPub, Operation},
+    relations::ZkProof,
+    traits::Hashable,
+    Scalar,
+};
+use shielder_client::{
+    events::NoteAdded,
+    keys::{Address, SpendingKey},
+    memo::encrypt_memo,
+    recovery::recover,
+};
+
+use crate::{
+    calls::{AddNoteCall, UpdateNoteCall},
+    state::{NoteState, PendingNote, WalletState},
+    transport::Transport,
+};
+
+#[derive(Debug, PartialEq)]
+pub enum WalletError<E> {
+    Transport(E),
+    Zkp(ZkpError),
+    AccountExists,
+    NoAccount,
+    NoPendingNote,
+}
+
+impl<E> From<ZkpError> for WalletError<E> {
+    fn from(err: ZkpError) -> Self {
+        WalletError::Zkp(err)
+    }
+}
+
+pub struct Wallet {
+    state: WalletState,
+}
+
+impl Wallet {
+    pub fn new(seed: Seed) -> Self {
+        Self::from_state(WalletState::new(seed))
+    }
+
+    pub fn from_state(state: WalletState) -> Self {
+        Self { state }
+    }
+
+    /// Wallet with all notes of the account of `seed` found in `events`
+    pub fn recover<'a>(seed: Seed, events: impl IntoIterator<Item = &'a NoteAdded>) -> Self {
+        let mut state = WalletState::new(seed);
+        state.notes = recover(&seed, events)
+            .into_iter()
+            .map(|note| NoteState {
+                counter: note.counter,
+                leaf_id: note.leaf_id,
+                account: note.account,
+            })
+            .collect();
+        Self::from_state(state)
+    }
+
+    pub fn state(&self) -> &WalletState {
+        &self.state
+    }
+
+    pub fn load(path: &Path) -> io::Result<Self> {
+        WalletState::load(path).map(Self::from_state)
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        self.state.save(path)
+    }
+
+    /// Current note of the account
+    pub fn note(&self) -> Option<&NoteState> {
+        self.state.notes.last()
+    }
+
+    /// Nullifier of the current note, published when the note is spent
+    pub fn nullifier(&self) -> Option<Scalar> {
+        self.note()
+            .map(|note| derive_nullifier(self.state.seed.nullifier_key(note.counter), note.leaf_id))
+    }
+
+    /// Address which memos of the notes are encrypted to
+    pub fn address(&self) -> Address {
+        SpendingKey::from_seed(&self.state.seed).address()
+    }
+
+    pub fn balance(&self, token: Scalar) -> u128 {
+        self.note()
+            .and_then(|note| {
+                note.account
+                    .balances()
+                    .into_iter()
+                    .find(|(t, _)| *t == token)
+            })
+            .map_or(0, |(_, balance)| balance.into())
+    }
+
+    /// Builds `add_note` call creating the account
+    pub fn prepare_create_account<T: Transport>(
+        &mut self,
+        transport: &mut T,
+    ) -> Result<AddNoteCall, WalletError<T::Error>> {
+        if !self.state.notes.is_empty() {
+            return Err(WalletError::AccountExists);
+        }
+        let domain = transport
+            .domain_separator()
+            .map_err(WalletError::Transport)?;
+        let tokens = transport
+            .supported_tokens()
+            .map_err(WalletError::Transport)?;
+
+        let account = Account::new(tokens);
+        let note = self.state.seed.note(0, account.hash());
+        let proof = ZkProof::new(
+            note.id(),
+            note.trapdoor(),
+            note.nullifier_key(),
+            dummy_op_priv(),
+            account,
+            domain,
+        );
+        self.state.pending = Some(PendingNote {
+            counter: 0,
+            account,
+        });
+        Ok(AddNoteCall {
+            h_note_new: note.hash(),
+            proof,
+            memo: encrypt_memo(&mut rand::thread_rng(), &self.address(), note, account),
+        })
+    }
+
+    /// Builds `update_note` call applying operation to the account
+    /// If `submitter` is set, only that account can submit the call
+    pub fn prepare_update<T: Transport>(
+        &mut self,
+        transport: &mut T,
+        op_pub: OpPub,
+        op_priv: OpPriv,
+        submitter: Option<Scalar>,
+    ) -> Result<UpdateNoteCall, WalletError<T::Error>> {
+        let current = *self.note().ok_or(WalletError::NoAccount)?;
+        let nullifier_old = self.nullifier().ok_or(WalletError::NoAccount)?;
+        let domain = transport
+            .domain_separator()
+            .map_err(WalletError::Transport)?;
+        let merkle_root = transport
+            .notes_merkle_root()
+            .map_err(WalletError::Transport)?;
+        let merkle_proof = transport
+            .notes_merkle_path(current.leaf_id)
+            .map_err(WalletError::Transport)?;
+
+        let seed = self.state.seed;
+        let counter = current.counter + 1;
+        let operation = Operation::combine(op_pub, op_priv)?;
+        let (h_note_new, proof) = ZkProof::new(
+            seed.id(),
+            seed.trapdoor(current.counter),
+            seed.nullifier_key(current.counter),
+            dummy_op_priv(),
+            current.account,
+            domain,
+        )
+        .update_account(
+            operation,
+            seed.trapdoor(counter),
+            seed.nullifier_key(counter),
+            merkle_proof,
+            current.leaf_id,
+            submitter,
+        )?;
+
+        let account = current.account.update(operation)?;
+        let note = seed.note(counter, account.hash());
+        self.state.pending = Some(PendingNote { counter, account });
+        Ok(UpdateNoteCall {
+            op_pub,
+            h_note_new,
+            merkle_root,
+            nullifier_old,
+            submitter,
+            proof,
+            memo: encrypt_memo(&mut rand::thread_rng(), &self.address(), note, account),
+        })
+    }
+
+    /// Stores pending note, once its call was included at `leaf_id`
+    pub fn confirm<E>(&mut self, leaf_id: u32) -> Result<(), WalletError<E>> {
+        let PendingNote { counter, account } = self
+            .state
+            .pending
+            .take()
+            .ok_or(WalletError::NoPendingNote)?;
+        self.state.notes.push(NoteState {
+            counter,
+            leaf_id,
+            account,
+        });
+        Ok(())
+    }
+
+    /// Creates the account and returns its leaf id
+    pub fn create_account<T: Transport>(
+        &mut self,
+        transport: &mut T,
+    ) -> Result<u32, WalletError<T::Error>> {
+        let call = self.prepare_create_account(transport)?;
+        let leaf_id = transport.add_note(&call).map_err(WalletError::Transport)?;
+        self.confirm(leaf_id)?;
+        Ok(leaf_id)
+    }
+
+    /// Applies operation to the account and returns leaf id of the new note
+    pub fn update<T: Transport>(
+        &mut self,
+        transport: &mut T,
+        op_pub: OpPub,
+        op_priv: OpPriv,
+    ) -> Result<u32, WalletError<T::Error>> {
+        let call = self.prepare_update(transport, op_pub, op_priv, None)?;
+        let leaf_id = transport
+            .update_note(&call)
+            .map_err(WalletError::Transport)?;
+        self.confirm(leaf_id)?;
+        Ok(leaf_id)
+    }
+}
+
+/// Private part of operation is not used by the proof of the current note
+fn dummy_op_priv() -> OpPriv {
+    OpPriv {
+        user: 0_u128.into(),
+    }
+}
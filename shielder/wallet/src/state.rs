@@ -0,0 +1,48 @@
+//! Persisted state of the wallet
+use std::{fs, io, path::Path};
+
+use mocked_zk::{account::Account, derivation::Seed};
+
+/// Note stored in the shielder
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct NoteState {
+    /// number of the note of the account, its secrets are derived from the seed and counter
+    pub counter: u32,
+    pub leaf_id: u32,
+    pub account: Account,
+}
+
+/// Note whose call was built, but not confirmed on chain yet
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct PendingNote {
+    pub counter: u32,
+    pub account: Account,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct WalletState {
+    pub seed: Seed,
+    /// all notes of the account, the last one is the current
+    pub notes: Vec<NoteState>,
+    pub pending: Option<PendingNote>,
+}
+
+impl WalletState {
+    pub fn new(seed: Seed) -> Self {
+        Self {
+            seed,
+            notes: vec![],
+            pending: None,
+        }
+    }
+
+    pub fn load(path: &Path) -> io::Result<Self> {
+        let json = fs::read_to_string(path)?;
+        serde_json::from_str(&json).map_err(io::Error::other)
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let json = serde_json::to_string_pretty(self).map_err(io::Error::other)?;
+        fs::write(path, json)
+    }
+}
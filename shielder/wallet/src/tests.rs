@@ -0,0 +1,315 @@
+use std::collections::{HashMap, HashSet};
+
+use mocked_zk::{
+    combine_merkle_hash,
+    derivation::Seed,
+    domain_separator,
+    ops::{OpPriv, OpPub},
+    Scalar, MERKLE_TREE_DEPTH, MOCKED_TOKEN, TOKENS_NUMBER,
+};
+use shielder_client::events::NoteAdded;
+
+use crate::{
+    calls::{to_hex, AddNoteCall, UpdateNoteCall},
+    transport::Transport,
+    Wallet, WalletError,
+};
+
+/// In-memory shielder, verifying calls the same way as the contract
+struct MockShielder {
+    nodes: HashMap<u32, Scalar>,
+    next_leaf_id: u32,
+    roots: Vec<Scalar>,
+    nullifiers: HashSet<[u8; 32]>,
+    events: Vec<NoteAdded>,
+}
+
+#[derive(Debug, PartialEq)]
+enum MockError {
+    Rejected,
+}
+
+const SIZE: u32 = 1 << MERKLE_TREE_DEPTH;
+
+impl MockShielder {
+    fn new() -> Self {
+        Self {
+            nodes: HashMap::new(),
+            next_leaf_id: 0,
+            roots: vec![],
+            nullifiers: HashSet::new(),
+            events: vec![],
+        }
+    }
+
+    fn node(&self, id: u32) -> Scalar {
+        self.nodes.get(&id).copied().unwrap_or(0_u128.into())
+    }
+
+    /// Adds the note and emits `NoteAdded` event
+    fn add_leaf(&mut self, leaf: Scalar, nullifier_old: Option<Scalar>, memo: Vec<u8>) -> u32 {
+        self.events.push(NoteAdded {
+            leaf_id: self.next_leaf_id,
+            note_hash: leaf,
+            nullifier_old,
+            memo,
+        });
+        let mut id = self.next_leaf_id + SIZE;
+        self.nodes.insert(id, leaf);
+        id /= 2;
+        while id > 0 {
+            let hash = combine_merkle_hash(self.node(2 * id), self.node(2 * id + 1));
+            self.nodes.insert(id, hash);
+            id /= 2;
+        }
+        self.roots.push(self.node(1));
+        self.next_leaf_id += 1;
+        self.next_leaf_id - 1
+    }
+
+    fn tokens() -> [Scalar; TOKENS_NUMBER] {
+        let mut tokens = [0_u128.into(); TOKENS_NUMBER];
+        tokens[0] = MOCKED_TOKEN;
+        tokens
+    }
+}
+
+impl Transport for MockShielder {
+    type Error = MockError;
+
+    fn domain_separator(&mut self) -> Result<Scalar, MockError> {
+        Ok(domain_separator(
+            Scalar::from_bytes([0x1; 32]),
+            0_u128.into(),
+        ))
+    }
+
+    fn supported_tokens(&mut self) -> Result<[Scalar; TOKENS_NUMBER], MockError> {
+        Ok(Self::tokens())
+    }
+
+    fn notes_merkle_root(&mut self) -> Result<Scalar, MockError> {
+        Ok(self.node(1))
+    }
+
+    fn notes_merkle_path(
+        &mut self,
+        leaf_id: u32,
+    ) -> Result<[Scalar; MERKLE_TREE_DEPTH], MockError> {
+        let mut id = leaf_id + SIZE;
+        let mut path = [0_u128.into(); MERKLE_TREE_DEPTH];
+        for node in path.iter_mut() {
+            *node = self.node(id ^ 1);
+            id /= 2;
+        }
+        Ok(path)
+    }
+
+    fn add_note(&mut self, call: &AddNoteCall) -> Result<u32, MockError> {
+        // the contract receives the call in the wire format
+        let call = AddNoteCall::from_payload(&call.payload()).map_err(|_| MockError::Rejected)?;
+        let domain = self.domain_separator()?;
+        call.proof
+            .verify_creation(call.h_note_new, Self::tokens(), domain)
+            .map_err(|_| MockError::Rejected)?;
+        Ok(self.add_leaf(call.h_note_new, None, call.memo))
+    }
+
+    fn update_note(&mut self, call: &UpdateNoteCall) -> Result<u32, MockError> {
+        let call =
+            UpdateNoteCall::from_payload(&call.payload()).map_err(|_| MockError::Rejected)?;
+        let domain = self.domain_separator()?;
+        if !self.roots.contains(&call.merkle_root)
+            || !self.nullifiers.insert(call.nullifier_old.bytes)
+        {
+            return Err(MockError::Rejected);
+        }
+        call.proof
+            .verify_update(
+                call.op_pub,
+                call.h_note_new,
+                call.merkle_root,
+                call.nullifier_old,
+                domain,
+                call.submitter,
+            )
+            .map_err(|_| MockError::Rejected)?;
+        Ok(self.add_leaf(call.h_note_new, Some(call.nullifier_old), call.memo))
+    }
+}
+
+fn user() -> Scalar {
+    7_u128.into()
+}
+
+fn deposit(amount: u128) -> (OpPub, OpPriv) {
+    (
+        OpPub::Deposit {
+            amount,
+            token: MOCKED_TOKEN,
+            user: user(),
+            valid_until: None,
+        },
+        OpPriv { user: user() },
+    )
+}
+
+fn withdraw(amount: u128) -> (OpPub, OpPriv) {
+    (
+        OpPub::Withdraw {
+            amount,
+            token: MOCKED_TOKEN,
+            user: user(),
+            valid_until: None,
+        },
+        OpPriv { user: user() },
+    )
+}
+
+#[test]
+fn test_wallet_updates() -> Result<(), WalletError<MockError>> {
+    let mut shielder = MockShielder::new();
+    let mut wallet = Wallet::new(Seed::from_bytes([0x1; 32]));
+    // other user fills the tree
+    let mut other = Wallet::new(Seed::from_bytes([0x2; 32]));
+    other.create_account(&mut shielder)?;
+
+    assert_eq!(wallet.create_account(&mut shielder)?, 1);
+    let (op_pub, op_priv) = deposit(10);
+    assert_eq!(wallet.update(&mut shielder, op_pub, op_priv)?, 2);
+    let (op_pub, op_priv) = deposit(5);
+    other.update(&mut shielder, op_pub, op_priv)?;
+    let (op_pub, op_priv) = withdraw(3);
+    assert_eq!(wallet.update(&mut shielder, op_pub, op_priv)?, 4);
+
+    assert_eq!(wallet.balance(MOCKED_TOKEN), 7);
+    assert_eq!(wallet.balance(0_u128.into()), 0);
+    assert_eq!(other.balance(MOCKED_TOKEN), 5);
+    assert_eq!(wallet.state().notes.len(), 3);
+    assert_eq!(wallet.state().pending, None);
+
+    let (op_pub, op_priv) = withdraw(8);
+    assert_eq!(
+        wallet.update(&mut shielder, op_pub, op_priv),
+        Err(WalletError::Zkp(
+            mocked_zk::errors::ZkpError::BalanceUnderflow
+        ))
+    );
+    Ok(())
+}
+
+#[test]
+fn test_wallet_errors() -> Result<(), WalletError<MockError>> {
+    let mut shielder = MockShielder::new();
+    let mut wallet = Wallet::new(Seed::from_bytes([0x1; 32]));
+
+    let (op_pub, op_priv) = deposit(10);
+    assert_eq!(
+        wallet
+            .prepare_update(&mut shielder, op_pub, op_priv, None)
+            .unwrap_err(),
+        WalletError::NoAccount
+    );
+    assert_eq!(
+        wallet.confirm::<MockError>(0),
+        Err(WalletError::NoPendingNote)
+    );
+
+    wallet.create_account(&mut shielder)?;
+    assert_eq!(
+        wallet.create_account(&mut shielder),
+        Err(WalletError::AccountExists)
+    );
+
+    // rejected call is not confirmed, the current note stays spendable
+    let current = *wallet.note().unwrap();
+    let mut call = wallet.prepare_update(&mut shielder, op_pub, op_priv, None)?;
+    call.merkle_root = 0_u128.into();
+    assert_eq!(shielder.update_note(&call), Err(MockError::Rejected));
+    assert!(wallet.state().pending.is_some());
+    assert_eq!(wallet.note(), Some(&current));
+    assert_eq!(wallet.balance(MOCKED_TOKEN), 0);
+
+    // included call spends the note, so it can't be resubmitted
+    let call = wallet.prepare_update(&mut shielder, op_pub, op_priv, None)?;
+    let leaf_id = shielder
+        .update_note(&call)
+        .map_err(WalletError::Transport)?;
+    wallet.confirm::<MockError>(leaf_id)?;
+    assert_eq!(wallet.balance(MOCKED_TOKEN), 10);
+    assert_eq!(shielder.update_note(&call), Err(MockError::Rejected));
+    Ok(())
+}
+
+#[test]
+fn test_wallet_persisted() -> Result<(), WalletError<MockError>> {
+    let mut shielder = MockShielder::new();
+    let mut wallet = Wallet::new(Seed::from_bytes([0x1; 32]));
+    wallet.create_account(&mut shielder)?;
+    let (op_pub, op_priv) = deposit(10);
+    wallet.update(&mut shielder, op_pub, op_priv)?;
+
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("wallet.json");
+    wallet.save(&path).unwrap();
+    let mut restored = Wallet::load(&path).unwrap();
+    assert_eq!(restored.state(), wallet.state());
+
+    let (op_pub, op_priv) = withdraw(4);
+    restored.update(&mut shielder, op_pub, op_priv)?;
+    assert_eq!(restored.balance(MOCKED_TOKEN), 6);
+    Ok(())
+}
+
+#[test]
+fn test_wallet_recovered_from_seed() -> Result<(), WalletError<MockError>> {
+    let mut shielder = MockShielder::new();
+    let seed = Seed::from_bytes([0x1; 32]);
+    let mut wallet = Wallet::new(seed);
+    let mut other = Wallet::new(Seed::from_bytes([0x2; 32]));
+    other.create_account(&mut shielder)?;
+    wallet.create_account(&mut shielder)?;
+    let (op_pub, op_priv) = deposit(10);
+    wallet.update(&mut shielder, op_pub, op_priv)?;
+    other.update(&mut shielder, op_pub, op_priv)?;
+    let (op_pub, op_priv) = withdraw(3);
+    wallet.update(&mut shielder, op_pub, op_priv)?;
+
+    let mut recovered = Wallet::recover(seed, &shielder.events);
+    assert_eq!(recovered.state(), wallet.state());
+    assert_eq!(recovered.note().map(|note| note.leaf_id), Some(4));
+    assert_eq!(recovered.balance(MOCKED_TOKEN), 7);
+
+    // recovered wallet spends the latest note
+    recovered.update(&mut shielder, op_pub, op_priv)?;
+    assert_eq!(recovered.balance(MOCKED_TOKEN), 4);
+    Ok(())
+}
+
+#[test]
+fn test_call_args() -> Result<(), WalletError<MockError>> {
+    let mut shielder = MockShielder::new();
+    let mut wallet = Wallet::new(Seed::from_bytes([0x1; 32]));
+    wallet.create_account(&mut shielder)?;
+    let (op_pub, op_priv) = withdraw(0);
+    let call = wallet.prepare_update(&mut shielder, op_pub, op_priv, Some(user()))?;
+
+    let scalar = |scalar: Scalar| to_hex(&scalar.bytes);
+    assert_eq!(
+        call.args(),
+        vec![
+            format!(
+                "Withdraw {{ amount: 0, token: {}, user: {}, valid_until: None }}",
+                scalar(MOCKED_TOKEN),
+                scalar(user()),
+            ),
+            scalar(call.h_note_new),
+            scalar(call.merkle_root),
+            scalar(call.nullifier_old),
+            format!("Some({})", scalar(user())),
+            to_hex(&call.proof_bytes()),
+            to_hex(&call.memo),
+        ]
+    );
+    Ok(())
+}
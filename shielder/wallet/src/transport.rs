@@ -0,0 +1,27 @@
+//! Access to a deployed shielder contract
+use mocked_zk::{Scalar, MERKLE_TREE_DEPTH, TOKENS_NUMBER};
+
+use crate::calls::{AddNoteCall, UpdateNoteCall};
+
+/// Queries and submits calls to the shielder contract
+/// Implemented for drink `Session` (with `drink` feature), node clients can implement it as well
+pub trait Transport {
+    type Error;
+
+    fn domain_separator(&mut self) -> Result<Scalar, Self::Error>;
+
+    fn supported_tokens(&mut self) -> Result<[Scalar; TOKENS_NUMBER], Self::Error>;
+
+    fn notes_merkle_root(&mut self) -> Result<Scalar, Self::Error>;
+
+    fn notes_merkle_path(
+        &mut self,
+        leaf_id: u32,
+    ) -> Result<[Scalar; MERKLE_TREE_DEPTH], Self::Error>;
+
+    /// Returns id of the added leaf
+    fn add_note(&mut self, call: &AddNoteCall) -> Result<u32, Self::Error>;
+
+    /// Returns id of the added leaf
+    fn update_note(&mut self, call: &UpdateNoteCall) -> Result<u32, Self::Error>;
+}